@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::{collections::HashMap, time::Duration};
+use std::{collections::HashMap, path::Path, time::Duration};
 
 use smithay::{
     backend::{
@@ -10,17 +10,21 @@ use smithay::{
             gbm::{GbmAllocator, GbmBufferFlags, GbmDevice},
         },
         drm::{
-            DrmDevice, DrmDeviceFd, DrmEvent, DrmNode, NodeType,
+            DrmDevice, DrmDeviceFd, DrmError, DrmEvent, DrmNode, NodeType,
             compositor::{DrmCompositor, FrameFlags},
             exporter::gbm::GbmFramebufferExporter,
         },
         egl::{EGLContext, EGLDisplay},
         input::InputEvent,
         libinput::{LibinputInputBackend, LibinputSessionInterface},
-        renderer::glow::GlowRenderer,
+        renderer::{
+            element::utils::{Relocate, RelocateRenderElement},
+            glow::GlowRenderer,
+        },
         session::{Event as SessionEvent, Session, libseat::LibSeatSession},
         udev::{UdevBackend, UdevEvent, all_gpus, primary_gpu},
     },
+    delegate_drm_lease,
     desktop::layer_map_for_output,
     output::{Output, OutputModeSource, PhysicalProperties, Subpixel},
     reexports::{
@@ -33,7 +37,15 @@ use smithay::{
         rustix::fs::OFlags,
     },
     utils::DeviceFd,
-    wayland::{dmabuf::DmabufFeedbackBuilder, image_copy_capture::DmabufConstraints},
+    wayland::{
+        dmabuf::DmabufFeedbackBuilder,
+        drm_lease::{
+            DrmLease, DrmLeaseBuilder, DrmLeaseHandler, DrmLeaseRequest, DrmLeaseState,
+            LeaseRejected,
+        },
+        drm_syncobj::{DrmSyncobjState, supports_syncobj_eventfd},
+        image_copy_capture::DmabufConstraints,
+    },
 };
 
 use smithay_drm_extras::{
@@ -51,7 +63,7 @@ use crate::{
     Monotile,
     handlers::screencopy,
     render::Shaders,
-    shell::{MonitorSettings, Monitors},
+    shell::{MonitorSettings, Monitors, Windows},
     state::State,
 };
 
@@ -73,7 +85,14 @@ pub struct OutputSurface {
     pub render: RenderState,
     pub connector: connector::Handle,
     pub powered: bool,
+    // built-in panel, see config::Panel
+    pub internal: bool,
+    // turned off and without a monitor by the panel policy
+    pub disabled: bool,
     pub power_clients: Vec<Weak<ZwlrOutputPowerV1>>,
+    // failed frames and DRM errors in a row, the renderer is rebuilt past
+    // MAX_RENDER_FAILURES
+    pub render_failures: u32,
 }
 
 impl OutputSurface {
@@ -105,6 +124,12 @@ pub struct DrmState {
     pub dma_constraints: Option<DmabufConstraints>,
     pub surfaces: HashMap<crtc::Handle, OutputSurface>,
     pub loop_handle: LoopHandle<'static, Monotile>,
+    pub lid_closed: bool,
+    // wp_drm_lease_v1, None if the device can't lease
+    pub lease_state: Option<DrmLeaseState>,
+    // non-desktop connectors (VR headsets) offered for lease instead of used as outputs
+    lease_connectors: HashMap<connector::Handle, crtc::Handle>,
+    leases: Vec<DrmLease>,
     libinput: Libinput,
     scanner: DrmScanner,
 }
@@ -121,7 +146,119 @@ impl Drop for DrmState {
     }
 }
 
+const MAX_RENDER_FAILURES: u32 = 3;
+
+// the output a DRM error is about, if it names one
+fn error_crtc(err: &DrmError) -> Option<crtc::Handle> {
+    match *err {
+        DrmError::TestFailed(crtc)
+        | DrmError::CrtcAlreadyInUse(crtc)
+        | DrmError::SurfaceWithoutConnectors(crtc)
+        | DrmError::PlaneNotCompatible(crtc, _)
+        | DrmError::UnknownConnector(crtc, _) => Some(crtc),
+        _ => None,
+    }
+}
+
 impl DrmState {
+    // rebuild the EGL context and renderer on the same device, e.g. after a GPU reset
+    pub fn recreate_renderer(
+        &mut self,
+        windows: &mut Windows,
+        shader_dir: Option<&Path>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let egl_display = unsafe { EGLDisplay::new(self.gbm.clone()) }?;
+        let egl_context = EGLContext::new(&egl_display)?;
+        let mut renderer = unsafe { GlowRenderer::new(egl_context) }?;
+        self.shaders = crate::render::compile_shaders(&mut renderer, shader_dir)?;
+        self.renderer = renderer;
+
+        // client buffers are imported again on the next frame
+        for we in windows.values_mut() {
+            we.release_textures();
+        }
+        for surface in self.surfaces.values_mut() {
+            surface.compositor.reset_buffers();
+        }
+        self.schedule_render_all();
+        Ok(())
+    }
+
+    // keeps the current renderer with fresh shaders if a new one can't be made
+    pub fn reload_renderer(&mut self, windows: &mut Windows, shader_dir: Option<&Path>) {
+        info!("reloading renderer");
+        if let Err(err) = self.recreate_renderer(windows, shader_dir) {
+            warn!(
+                ?err,
+                "failed to recreate renderer, recompiling shaders only"
+            );
+            match crate::render::compile_shaders(&mut self.renderer, shader_dir) {
+                Ok(shaders) => self.shaders = shaders,
+                Err(err) => error!(?err, "failed to compile shaders"),
+            }
+            for surface in self.surfaces.values_mut() {
+                surface.compositor.reset_buffers();
+            }
+            self.schedule_render_all();
+        }
+    }
+
+    // start over on every output from a clean KMS state once the session
+    // comes back
+    pub fn recover(&mut self) {
+        let crtcs: Vec<_> = self.surfaces.keys().copied().collect();
+        for crtc in crtcs {
+            self.reset_surface(crtc);
+        }
+    }
+
+    // start over on one output from a clean KMS state
+    fn reset_surface(&mut self, crtc: crtc::Handle) {
+        let Some(surface) = self.surfaces.get_mut(&crtc) else {
+            return;
+        };
+        if let Err(err) = surface.compositor.reset_state() {
+            warn!(?err, "failed to reset compositor state");
+        }
+        surface.compositor.reset_buffers();
+        // a lost page flip would leave it waiting for a vblank forever
+        surface.render = RenderState::Idle;
+        self.schedule_render_crtc(crtc);
+    }
+
+    // a DRM error on one output resets only that one
+    pub fn crtc_error(
+        &mut self,
+        crtc: crtc::Handle,
+        windows: &mut Windows,
+        shader_dir: Option<&Path>,
+    ) {
+        self.reset_surface(crtc);
+        self.count_failure(crtc, windows, shader_dir);
+    }
+
+    // a failed frame or DRM error on crtc, the renderer is rebuilt once there
+    // are MAX_RENDER_FAILURES in a row
+    fn count_failure(
+        &mut self,
+        crtc: crtc::Handle,
+        windows: &mut Windows,
+        shader_dir: Option<&Path>,
+    ) {
+        let Some(surface) = self.surfaces.get_mut(&crtc) else {
+            return;
+        };
+        surface.render_failures += 1;
+        if surface.render_failures < MAX_RENDER_FAILURES {
+            return;
+        }
+        surface.render_failures = 0;
+        warn!("rendering keeps failing, recreating the renderer");
+        if let Err(err) = self.recreate_renderer(windows, shader_dir) {
+            error!(?err, "failed to recreate renderer");
+        }
+    }
+
     pub fn schedule_render_all(&mut self) {
         let crtcs: Vec<_> = self.surfaces.keys().copied().collect();
         for crtc in crtcs {
@@ -161,11 +298,10 @@ impl DrmState {
         }
     }
 
-    fn refresh_duration(output: &Output) -> Duration {
-        let refresh = output.current_mode().map(|m| m.refresh).unwrap_or(60_000);
-        Duration::from_micros(1_000_000 / (refresh as u64 / 1000))
-    }
-
+    #[cfg_attr(
+        feature = "profiling",
+        tracing::instrument(level = "trace", skip_all, fields(elements))
+    )]
     pub fn render(&mut self, crtc: crtc::Handle, state: &mut State) {
         let Some(surface) = self.surfaces.get_mut(&crtc) else {
             return;
@@ -183,13 +319,44 @@ impl DrmState {
             state.screencopy.fail_pending_for_output(&surface.output);
             return;
         };
-        let mon = &mut state.monitors[idx];
-        mon.views.pop_ready();
+        let duplicate = state.monitors.duplicate_source(idx);
+        let frame = crate::render::OutputFrame {
+            locked: state.locked,
+            idle_dim: state.idle_dim(),
+            active: state.outlined(idx),
+            scale: duplicate.map(|(_, scale)| scale),
+            switcher: state
+                .switcher
+                .as_ref()
+                .filter(|_| idx == state.active_monitor),
+            input_inhibited: state.input_inhibited,
+        };
+        let duplicates = state.monitors.duplicates_of(idx);
+        let letterbox = state.monitors.letterbox(idx);
+        // a duplicate draws the source monitor instead of its own, leaving
+        // the source's transitions to its own frames
+        let mon = &mut state.monitors[duplicate.map_or(idx, |(src, _)| src)];
+        if duplicate.is_none() {
+            mon.views.pop_ready();
+        }
 
-        let refresh = Self::refresh_duration(&surface.output);
-        let ptr = state.seat.get_pointer().unwrap();
-        let pos = ptr.current_location();
-        let mut elems = state.cursor.elements(&mut self.renderer, pos);
+        let frame_start = std::time::Instant::now();
+        let refresh = crate::render::frame_interval(&surface.output);
+        let scale = frame
+            .scale
+            .unwrap_or_else(|| surface.output.current_scale().fractional_scale());
+        let pointer = state.seat.get_pointer().map(|p| p.current_location());
+        let pointer = match duplicate {
+            // the pointer as seen on the source, if it is there
+            Some(_) => pointer
+                .filter(|&p| mon.global_geometry().to_f64().contains(p))
+                .map(|p| p - mon.global_geometry().loc.to_f64()),
+            None => pointer,
+        };
+        let mut elems = match pointer {
+            Some(pos) => state.cursor.elements(&mut self.renderer, pos, scale),
+            None => Vec::new(),
+        };
         let cursor_count = elems.len();
         elems.extend(crate::render::output_elements(
             &mut self.renderer,
@@ -197,13 +364,17 @@ impl DrmState {
             &mut state.windows,
             &self.shaders,
             &state.config,
-            state.locked,
+            frame,
         ));
+        let elems: Vec<_> = elems
+            .into_iter()
+            .map(|e| RelocateRenderElement::from_element(e, letterbox, Relocate::Relative))
+            .collect();
 
         let result = match surface.compositor.render_frame(
             &mut self.renderer,
             &elems,
-            mon.settings.background,
+            mon.background(),
             FrameFlags::DEFAULT,
         ) {
             Ok(result) => result,
@@ -212,13 +383,40 @@ impl DrmState {
                 surface.compositor.reset_buffers();
                 state.screencopy.fail_pending_for_output(&surface.output);
                 reschedule_render(&self.loop_handle, crtc, refresh);
+                self.count_failure(crtc, &mut state.windows, state.config.shaders.as_deref());
                 return;
             }
         };
+        surface.render_failures = 0;
+        if !result.is_empty && !duplicates.is_empty() {
+            self.loop_handle.insert_idle(move |mt: &mut Monotile| {
+                for output in &duplicates {
+                    mt.backend.schedule_render(output);
+                }
+            });
+        }
+        if let Some(stats) = &mut mon.stats
+            && !result.is_empty
+            && duplicate.is_none()
+        {
+            let windows = mon.tags[mon.active_tag].window_ids().len();
+            stats.record(frame_start.elapsed(), elems.len(), windows);
+        }
+
+        #[cfg(feature = "profiling")]
+        {
+            tracing::Span::current().record("elements", elems.len());
+            tracing::trace!(
+                output = %surface.output.name(),
+                damaged = !result.is_empty,
+                took = ?frame_start.elapsed(),
+                "frame",
+            );
+        }
 
         let elapsed = state.start_time.elapsed();
 
-        let bg = mon.settings.background;
+        let bg = mon.background();
         screencopy::capture_frame(
             &mut self.renderer,
             state,
@@ -228,6 +426,14 @@ impl DrmState {
             bg,
             elapsed,
         );
+        crate::render::capture_screenshots(
+            &mut self.renderer,
+            state,
+            &surface.output,
+            &elems,
+            cursor_count,
+            bg,
+        );
         screencopy::capture_cursor(&mut self.renderer, state, &surface.output, elapsed);
 
         if result.is_empty {
@@ -296,6 +502,9 @@ impl DrmState {
         let Some(surface) = self.surfaces.get_mut(&crtc) else {
             return;
         };
+        if on && surface.disabled {
+            return;
+        }
         surface.powered = on;
         surface.notify_power(on);
         if on {
@@ -316,7 +525,58 @@ impl DrmState {
     }
 
     pub fn any_output_off(&self) -> bool {
-        self.surfaces.values().any(|s| !s.powered)
+        self.surfaces.values().any(|s| !s.powered && !s.disabled)
+    }
+
+    // apply config::Panel to the internal panels
+    pub fn update_internal_outputs(&mut self, state: &mut State) {
+        let external = self.surfaces.values().any(|s| !s.internal);
+        let off = state.config.panel.off(external, self.lid_closed);
+        let crtcs: Vec<_> = self
+            .surfaces
+            .iter()
+            .filter(|(_, s)| s.internal && s.disabled != off)
+            .map(|(&c, _)| c)
+            .collect();
+        for crtc in crtcs {
+            self.set_output_enabled(crtc, !off, state);
+        }
+    }
+
+    // a disabled output stays off and its windows move to the remaining monitors
+    fn set_output_enabled(&mut self, crtc: crtc::Handle, on: bool, state: &mut State) {
+        let Some(surface) = self.surfaces.get_mut(&crtc) else {
+            return;
+        };
+        let output = surface.output.clone();
+        info!(
+            "{}: {}",
+            output.name(),
+            if on { "enabled" } else { "disabled" }
+        );
+        if on {
+            surface.disabled = false;
+            let props = output.physical_properties();
+            let s = MonitorSettings::resolve(
+                &state.config.outputs,
+                &output.name(),
+                &props.make,
+                &props.model,
+                &props.serial_number,
+            );
+            state.add_monitor(output, s);
+            state.remove_fallback_output();
+            self.set_output_power_crtc(crtc, true);
+        } else {
+            self.set_output_power_crtc(crtc, false);
+            if let Some(surface) = self.surfaces.get_mut(&crtc) {
+                surface.disabled = true;
+            }
+            state.remove_monitor(&output);
+            if let Some(mon) = state.mon() {
+                self.schedule_render(&mon.output);
+            }
+        }
     }
 
     pub fn apply_output_settings(&mut self, monitors: &Monitors) {
@@ -378,11 +638,13 @@ pub fn device_changed(drm: &mut DrmState, state: &mut State) {
                 crtc: Some(crtc),
             } => connector_connected(drm, state, connector, crtc),
             DrmScanEvent::Disconnected {
-                crtc: Some(crtc), ..
-            } => connector_disconnected(drm, state, crtc),
+                connector,
+                crtc: Some(crtc),
+            } => connector_disconnected(drm, state, connector.handle(), crtc),
             _ => {}
         }
     }
+    drm.update_internal_outputs(state);
 }
 
 fn connector_connected(
@@ -396,18 +658,25 @@ fn connector_connected(
         connector.interface().as_str(),
         connector.interface_id()
     );
-    let di = display_info::for_connector(&drm.drm, connector.handle());
-    let (make, model, serial) = if let Some(di) = &di {
-        (
-            di.make().unwrap_or_else(|| "Unknown".into()),
-            di.model().unwrap_or_else(|| "Unknown".into()),
-            di.serial().unwrap_or_else(|| "Unknown".into()),
-        )
-    } else {
-        ("Unknown".into(), "Unknown".into(), "Unknown".into())
-    };
+    let (make, model, serial) = edid_identity(&drm.drm, connector.handle(), &name);
     info!("'{name}': make={make} model={model} serial={serial}");
 
+    if is_non_desktop(&drm.drm, connector.handle()) {
+        info!("{name}: non-desktop, offered for lease");
+        if let Some(lease_state) = &mut drm.lease_state {
+            let description = format!("{make} {model}");
+            lease_state.add_connector::<Monotile>(connector.handle(), name, description);
+        }
+        drm.lease_connectors.insert(connector.handle(), crtc);
+        return;
+    }
+    let internal = matches!(
+        connector.interface(),
+        connector::Interface::EmbeddedDisplayPort
+            | connector::Interface::LVDS
+            | connector::Interface::DSI
+    );
+
     let s = MonitorSettings::resolve(&state.config.outputs, &name, &make, &model, &serial);
     let Some((preferred, selected)) = drm_mode_for_config(connector.modes(), &s) else {
         warn!("connector {name} has no modes, skipping");
@@ -425,6 +694,10 @@ fn connector_connected(
             serial_number: serial,
         },
     );
+    // advertise every connector mode to clients
+    for &mode in connector.modes() {
+        output.add_mode(mode.into());
+    }
     output.set_preferred(preferred.into());
     output.change_current_state(Some(selected.into()), s.transform, s.scale, Some(s.pos));
 
@@ -462,6 +735,7 @@ fn connector_connected(
 
     let powered = !drm.any_output_off();
     state.add_monitor(output.clone(), s);
+    state.remove_fallback_output();
     drm.surfaces.insert(
         crtc,
         OutputSurface {
@@ -470,7 +744,10 @@ fn connector_connected(
             render: RenderState::default(),
             connector: connector.handle(),
             powered,
+            internal,
+            disabled: false,
             power_clients: Vec::new(),
+            render_failures: 0,
         },
     );
     if powered {
@@ -481,17 +758,78 @@ fn connector_connected(
     }
 }
 
-fn connector_disconnected(drm: &mut DrmState, state: &mut State, crtc: crtc::Handle) {
+fn connector_disconnected(
+    drm: &mut DrmState,
+    state: &mut State,
+    connector: connector::Handle,
+    crtc: crtc::Handle,
+) {
+    if drm.lease_connectors.remove(&connector).is_some() {
+        if let Some(lease_state) = &mut drm.lease_state {
+            lease_state.withdraw_connector(connector);
+        }
+        return;
+    }
     let Some(surface) = drm.surfaces.remove(&crtc) else {
         return;
     };
     info!("{}: disconnected", surface.output.name());
     state.remove_monitor(&surface.output);
-    if !state.monitors.is_empty() {
+    if let Some(output) = state.ensure_fallback_output() {
+        drive_fallback_output(&drm.loop_handle, output);
+    } else if !state.monitors.is_empty() {
         drm.schedule_render(&state.monitors[state.active_monitor].output);
     }
 }
 
+// make, model and serial from the EDID, the model falls back to the connector
+// name so outputs without one stay distinguishable
+fn edid_identity(
+    drm: &DrmDevice,
+    connector: connector::Handle,
+    name: &str,
+) -> (String, String, String) {
+    let di = display_info::for_connector(drm, connector);
+    let known = |s: Option<String>| s.filter(|s| !s.trim().is_empty());
+    let make = known(di.as_ref().and_then(|di| di.make()));
+    let model = known(di.as_ref().and_then(|di| di.model()));
+    let serial = known(di.as_ref().and_then(|di| di.serial()));
+    (
+        make.unwrap_or_else(|| "Unknown".into()),
+        model.unwrap_or_else(|| name.to_string()),
+        serial.unwrap_or_else(|| "Unknown".into()),
+    )
+}
+
+// headsets set the non-desktop property, they're not meant to show the desktop
+fn is_non_desktop(drm: &DrmDevice, connector: connector::Handle) -> bool {
+    let Ok(props) = drm.get_properties(connector) else {
+        return false;
+    };
+    props
+        .into_iter()
+        .find_map(|(handle, value)| {
+            let info = drm.get_property(handle).ok()?;
+            let name = info.name().to_str().ok()?;
+            (name == "non-desktop").then(|| info.value_type().convert_value(value).as_boolean())
+        })
+        .flatten()
+        .unwrap_or(false)
+}
+
+// nothing is rendered to the fallback output, keep clients ticking with frame callbacks
+fn drive_fallback_output(loop_handle: &LoopHandle<'static, Monotile>, output: Output) {
+    let interval = crate::render::frame_interval(&output);
+    let timer = Timer::from_duration(interval);
+    let _ = loop_handle.insert_source(timer, move |_, _, mt| {
+        if mt.state.fallback_output.as_ref() != Some(&output) {
+            return TimeoutAction::Drop;
+        }
+        mt.state.send_frame_callbacks(&output, None);
+        TimeoutAction::ToDuration(interval)
+    });
+}
+
 pub fn init(
     event_loop: &mut EventLoop<'static, Monotile>,
     monotile: &mut Monotile,
@@ -503,7 +841,7 @@ pub fn init(
     libinput.udev_assign_seat(&seat).unwrap();
     let libinput_ctx = libinput.clone();
 
-    let (render_node, card_node) = find_gpu(&seat)?;
+    let (render_node, card_node) = find_gpu(&seat, monotile.state.config.render_device.as_deref())?;
     info!(
         "GPU: gpu={} card={}",
         render_node.dev_path().unwrap_or_default().display(),
@@ -521,7 +859,8 @@ pub fn init(
     let egl_display = unsafe { EGLDisplay::new(gbm.clone()) }?;
     let egl_context = EGLContext::new(&egl_display)?;
     let mut renderer = unsafe { GlowRenderer::new(egl_context) }?;
-    let shaders = crate::render::compile_shaders(&mut renderer);
+    let shader_dir = monotile.state.config.shaders.as_deref();
+    let shaders = crate::render::compile_shaders(&mut renderer, shader_dir)?;
 
     let allocator = GbmAllocator::new(
         gbm.clone(),
@@ -551,6 +890,18 @@ pub fn init(
             ),
     );
 
+    let fd = drm.device_fd();
+    if supports_syncobj_eventfd(fd) {
+        let dh = &monotile.state.display_handle;
+        monotile.state.syncobj_state = Some(DrmSyncobjState::new::<Monotile>(dh, fd.clone()));
+    } else {
+        info!("explicit sync unsupported by the DRM device");
+    }
+
+    let lease_state = DrmLeaseState::new::<Monotile>(&monotile.state.display_handle, &card_node)
+        .inspect_err(|err| warn!(?err, "DRM leasing unavailable"))
+        .ok();
+
     monotile.backend = crate::backend::Backend::Drm(DrmState {
         renderer,
         session,
@@ -563,13 +914,26 @@ pub fn init(
         dma_constraints,
         surfaces: HashMap::new(),
         loop_handle: loop_handle.clone(),
+        lid_closed: false,
+        lease_state,
+        lease_connectors: HashMap::new(),
+        leases: Vec::new(),
         libinput: libinput_ctx,
         scanner: DrmScanner::new(),
     });
 
     loop_handle.insert_source(drm_notifier, |event, _, mt| match event {
         DrmEvent::VBlank(crtc) => mt.backend.drm().frame_finish(crtc),
-        DrmEvent::Error(err) => error!(?err, "DRM error"),
+        DrmEvent::Error(err) => match error_crtc(&err) {
+            Some(crtc) => {
+                error!(?err, ?crtc, "DRM error, resetting the output");
+                let shader_dir = mt.state.config.shaders.as_deref();
+                mt.backend
+                    .drm()
+                    .crtc_error(crtc, &mut mt.state.windows, shader_dir);
+            }
+            None => error!(?err, "DRM error"),
+        },
     })?;
 
     loop_handle.insert_source(session_notifier, |event, _, mt| {
@@ -582,6 +946,9 @@ pub fn init(
                     surface.render = RenderState::Idle;
                 }
                 drm.drm.pause();
+                if let Some(lease_state) = &mut drm.lease_state {
+                    lease_state.suspend();
+                }
             }
             SessionEvent::ActivateSession => {
                 info!("session activated");
@@ -591,15 +958,10 @@ pub fn init(
                 if let Err(err) = drm.drm.activate(false) {
                     error!(?err, "failed to activate DRM");
                 }
-                for surface in drm.surfaces.values_mut() {
-                    if let Err(err) = surface.compositor.reset_state() {
-                        warn!(?err, "failed to reset compositor state");
-                    }
-                    surface.compositor.reset_buffers();
-                }
-                for crtc in drm.surfaces.keys().copied().collect::<Vec<_>>() {
-                    drm.schedule_render_crtc(crtc);
+                if let Some(lease_state) = &mut drm.lease_state {
+                    lease_state.resume::<Monotile>();
                 }
+                drm.recover();
 
                 drm.loop_handle.insert_idle(|mt: &mut Monotile| {
                     device_changed(mt.backend.drm(), &mut mt.state);
@@ -642,9 +1004,18 @@ fn reschedule_render(
     });
 }
 
-fn find_gpu(seat: &str) -> Result<(DrmNode, DrmNode), Box<dyn std::error::Error>> {
+fn find_gpu(
+    seat: &str,
+    configured: Option<&Path>,
+) -> Result<(DrmNode, DrmNode), Box<dyn std::error::Error>> {
     let render_node = if let Ok(var) = std::env::var("DRM_DEVICE") {
         DrmNode::from_path(var)?
+    } else if let Some(path) = configured {
+        // accept card nodes too, rendering goes through the render node
+        let node = DrmNode::from_path(path)?;
+        node.node_with_type(NodeType::Render)
+            .and_then(Result::ok)
+            .unwrap_or(node)
     } else {
         primary_gpu(seat)?
             .and_then(|p| {
@@ -691,5 +1062,67 @@ fn drm_mode_for_config(
         }
     }
     let preferred = preferred.or(modes.first().copied())?;
+    if let Some(requested) = s.mode
+        && matching.is_none()
+    {
+        let (w, h) = requested.size;
+        warn!("mode {w}x{h} not available, using the preferred mode");
+    }
     Some((preferred, matching.unwrap_or(preferred)))
 }
+
+impl DrmLeaseHandler for Monotile {
+    fn drm_lease_state(&mut self, _node: DrmNode) -> &mut DrmLeaseState {
+        self.backend
+            .drm()
+            .lease_state
+            .as_mut()
+            .expect("lease request without a lease global")
+    }
+
+    // only non-desktop connectors are leased, each with its crtc and planes
+    fn lease_request(
+        &mut self,
+        _node: DrmNode,
+        request: DrmLeaseRequest,
+    ) -> Result<DrmLeaseBuilder, LeaseRejected> {
+        let drm = self.backend.drm();
+        let mut builder = DrmLeaseBuilder::new(&drm.drm);
+        for connector in request.connectors {
+            let Some(&crtc) = drm.lease_connectors.get(&connector) else {
+                warn!(?connector, "lease requested for a desktop connector");
+                return Err(LeaseRejected::default());
+            };
+            builder.add_connector(connector);
+            builder.add_crtc(crtc);
+            let planes = drm.drm.planes(&crtc).map_err(LeaseRejected::with_cause)?;
+            let (primary, claim) = planes
+                .primary
+                .iter()
+                .find_map(|p| drm.drm.claim_plane(p.handle, crtc).map(|c| (p, c)))
+                .ok_or_else(LeaseRejected::default)?;
+            builder.add_plane(primary.handle, claim);
+            if let Some((cursor, claim)) = planes
+                .cursor
+                .iter()
+                .find_map(|p| drm.drm.claim_plane(p.handle, crtc).map(|c| (p, c)))
+            {
+                builder.add_plane(cursor.handle, claim);
+            }
+        }
+        Ok(builder)
+    }
+
+    fn new_active_lease(&mut self, _node: DrmNode, lease: DrmLease) {
+        info!(id = lease.id(), "DRM lease granted");
+        self.backend.drm().leases.push(lease);
+    }
+
+    // the connectors are offered again once the lease is gone
+    fn lease_destroyed(&mut self, _node: DrmNode, lease: u32) {
+        info!(id = lease, "DRM lease ended");
+        self.backend.drm().leases.retain(|l| l.id() != lease);
+    }
+}
+
+delegate_drm_lease!(Monotile);
@@ -3,6 +3,8 @@
 pub mod drm;
 pub mod winit;
 
+use std::path::Path;
+
 use smithay::{
     backend::session::Session, output::Output, wayland::image_copy_capture::DmabufConstraints,
 };
@@ -10,7 +12,10 @@ use tracing::warn;
 use winit::WinitState;
 
 use self::drm::DrmState;
-use crate::shell::Monitors;
+use crate::{
+    shell::{Monitors, Windows},
+    state::State,
+};
 
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug)]
@@ -69,6 +74,13 @@ impl Backend {
         }
     }
 
+    pub fn set_lid_closed(&mut self, closed: bool, state: &mut State) {
+        if let Backend::Drm(drm) = self {
+            drm.lid_closed = closed;
+            drm.update_internal_outputs(state);
+        }
+    }
+
     pub fn any_output_off(&self) -> bool {
         match self {
             Backend::Drm(drm) => drm.any_output_off(),
@@ -89,4 +101,13 @@ impl Backend {
             drm.apply_output_settings(monitors);
         }
     }
+
+    // recompile the shaders and redraw everything, on DRM with a new renderer
+    pub fn reload_renderer(&mut self, windows: &mut Windows, shader_dir: Option<&Path>) {
+        match self {
+            Backend::Winit(winit) => winit.reload_renderer(shader_dir),
+            Backend::Drm(drm) => drm.reload_renderer(windows, shader_dir),
+            Backend::Unset => {}
+        }
+    }
 }
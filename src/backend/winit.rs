@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
+use std::path::Path;
+
 use tracing::info;
 
 use crate::{Monotile, shell::MonitorSettings, state::State};
@@ -23,10 +25,28 @@ pub struct WinitState {
 }
 
 impl WinitState {
+    // fresh shaders and a full redraw
+    pub fn reload_renderer(&mut self, shader_dir: Option<&Path>) {
+        info!("reloading renderer");
+        match crate::render::compile_shaders(self.backend.renderer(), shader_dir) {
+            Ok(shaders) => self.shaders = shaders,
+            Err(err) => tracing::error!(?err, "failed to compile shaders"),
+        }
+        self.damage_tracker = OutputDamageTracker::from_output(&self.output);
+    }
+
+    #[cfg_attr(
+        feature = "profiling",
+        tracing::instrument(level = "trace", skip_all, fields(elements))
+    )]
     pub fn render(&mut self, state: &mut State) -> Result<(), Box<dyn std::error::Error>> {
+        let frame_start = std::time::Instant::now();
+        let idle_dim = state.idle_dim();
+        let Some(mon) = state.monitors.get_mut(state.active_monitor) else {
+            return Ok(());
+        };
         let age = self.backend.buffer_age().unwrap_or(0);
         let (renderer, mut fb) = self.backend.bind()?;
-        let mon = &mut state.monitors[state.active_monitor];
         mon.views.pop_ready();
         let elems = crate::render::output_elements(
             renderer,
@@ -34,20 +54,45 @@ impl WinitState {
             &mut state.windows,
             &self.shaders,
             &state.config,
-            state.locked,
+            crate::render::OutputFrame {
+                locked: state.locked,
+                idle_dim,
+                // the only output
+                active: false,
+                scale: None,
+                switcher: state.switcher.as_ref(),
+                input_inhibited: state.input_inhibited,
+            },
         );
-        let rendered = self.damage_tracker.render_output(
-            renderer,
-            &mut fb,
-            age,
-            &elems,
-            state.mon().settings.background,
-        )?;
+        let bg = mon.background();
+        let rendered = self
+            .damage_tracker
+            .render_output(renderer, &mut fb, age, &elems, bg)?;
+        if let Some(stats) = &mut mon.stats
+            && rendered.damage.is_some()
+        {
+            let windows = mon.tags[mon.active_tag].window_ids().len();
+            stats.record(frame_start.elapsed(), elems.len(), windows);
+        }
+
+        #[cfg(feature = "profiling")]
+        {
+            tracing::Span::current().record("elements", elems.len());
+            tracing::trace!(took = ?frame_start.elapsed(), "frame");
+        }
 
         std::mem::drop(fb);
         self.backend.submit(rendered.damage.map(|x| x.as_slice()))?;
+        crate::render::capture_screenshots(
+            self.backend.renderer(),
+            state,
+            &self.output,
+            &elems,
+            0,
+            bg,
+        );
 
-        let throttle = Some(std::time::Duration::from_millis(16));
+        let throttle = Some(crate::render::frame_interval(&self.output));
         state.send_frame_callbacks(&self.output, throttle);
 
         state.confirm_lock(&self.output);
@@ -61,11 +106,12 @@ pub fn init(
     monotile: &mut Monotile,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let (mut backend, winit) = winit::init()?;
-    let shaders = crate::render::compile_shaders(backend.renderer());
+    let shader_dir = monotile.state.config.shaders.as_deref();
+    let shaders = crate::render::compile_shaders(backend.renderer(), shader_dir)?;
 
     let mode = Mode {
         size: backend.window_size(),
-        refresh: 60_000,
+        refresh: host_refresh(&backend),
     };
 
     let output = Output::new(
@@ -90,6 +136,7 @@ pub fn init(
     monotile
         .state
         .add_monitor(output.clone(), MonitorSettings::default());
+    monotile.state.seat.add_pointer();
     info!("output: winit {}x{}", mode.size.w, mode.size.h);
 
     let damage_tracker = OutputDamageTracker::from_output(&output);
@@ -106,16 +153,19 @@ pub fn init(
         .insert_source(winit, move |event, _, monotile| {
             match event {
                 WinitEvent::Resized { size, .. } => {
-                    monotile.backend.winit().output.change_current_state(
-                        Some(Mode {
-                            size,
-                            refresh: 60_000,
-                        }),
+                    let winit = monotile.backend.winit();
+                    let refresh = host_refresh(&winit.backend);
+                    winit.output.change_current_state(
+                        Some(Mode { size, refresh }),
                         None,
                         None,
                         None,
                     );
                     layer_map_for_output(&monotile.backend.winit().output).arrange();
+                    let state = &mut monotile.state;
+                    if let Some(mon) = state.monitors.get(state.active_monitor) {
+                        mon.rescue_offscreen(&mut state.windows);
+                    }
                     monotile.recompute_layout(monotile.state.active_monitor);
                 }
                 WinitEvent::Input(event) => monotile.process_input_event(event),
@@ -134,3 +184,12 @@ pub fn init(
 
     Ok(())
 }
+
+// refresh rate of the monitor the window is on, in mHz
+fn host_refresh(backend: &WinitGraphicsBackend<GlowRenderer>) -> i32 {
+    backend
+        .window()
+        .current_monitor()
+        .and_then(|m| m.refresh_rate_millihertz())
+        .map_or(60_000, |r| r as i32)
+}
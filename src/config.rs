@@ -1,13 +1,16 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use std::cell::RefCell;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 
-use derive_more::Deref;
+use derive_more::{Deref, DerefMut};
 use inline_default::inline_default;
 use regex::Regex;
-use serde::{Deserialize, Deserializer, de::Error};
+use serde::{
+    Deserialize, Deserializer,
+    de::{Error, IntoDeserializer, Visitor},
+};
 use smithay::input::keyboard::{Keysym, ModifiersState, XkbConfig, xkb};
 use smithay::reexports::input::AccelProfile as InputAccelProfile;
 use tracing::{info, warn};
@@ -56,6 +59,33 @@ impl Color {
     }
 }
 
+// an optional color that may also be written bare, without Some(..)
+fn optional_color<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Color>, D::Error> {
+    struct OptionalColor;
+
+    impl<'de> Visitor<'de> for OptionalColor {
+        type Value = Option<Color>;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            f.write_str("a color, Some(color) or None")
+        }
+
+        fn visit_str<E: Error>(self, s: &str) -> Result<Self::Value, E> {
+            Color::deserialize(s.into_deserializer()).map(Some)
+        }
+
+        fn visit_none<E: Error>(self) -> Result<Self::Value, E> {
+            Ok(None)
+        }
+
+        fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+            Color::deserialize(d).map(Some)
+        }
+    }
+
+    d.deserialize_any(OptionalColor)
+}
+
 thread_local! {
     static PALETTE: RefCell<HashMap<String, Color>> = RefCell::new(HashMap::new());
 }
@@ -124,6 +154,10 @@ pub struct InputMatch {
     pub path: Option<Pattern>,
 }
 
+fn default_suspend() -> Vec<String> {
+    vec!["systemctl".into(), "suspend".into()]
+}
+
 pub fn default_tags() -> Vec<String> {
     (1..=9).map(|i| i.to_string()).collect()
 }
@@ -162,6 +196,22 @@ impl From<OutputTransform> for smithay::utils::Transform {
     }
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+pub enum ScaleFilter {
+    #[default]
+    Linear,
+    Nearest,
+}
+
+impl From<ScaleFilter> for smithay::backend::renderer::TextureFilter {
+    fn from(f: ScaleFilter) -> Self {
+        match f {
+            ScaleFilter::Linear => Self::Linear,
+            ScaleFilter::Nearest => Self::Nearest,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default, Deserialize)]
 #[serde(default)]
 pub struct OutputRule {
@@ -171,7 +221,11 @@ pub struct OutputRule {
     pub pos: Option<(i32, i32)>,
     pub mode: Option<ModeConfig>,
     pub transform: Option<OutputTransform>,
+    pub scale_filter: Option<ScaleFilter>,
     pub background: Option<Color>,
+    pub tag_backgrounds: Option<Vec<Color>>,
+    // name of an output to show instead of this one's own tags
+    pub duplicate: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Deserialize)]
@@ -193,6 +247,17 @@ pub struct WindowInit {
     pub position: Option<(i32, i32)>,
     pub output: Option<String>,
     pub tags: Option<Vec<usize>>,
+    // single tag, instead of tags
+    pub tag: Option<InitTag>,
+    // keep the float size when tiled, as with ToggleSizeLock
+    pub size_locked: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum InitTag {
+    // the active tag, undoing the tags of earlier rules
+    Current,
+    Tag(usize),
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -207,7 +272,9 @@ pub enum RenderStep {
     WindowSurface {
         #[serde(default)]
         radius: f32,
-        fill: Color,
+        // shown where the client has not drawn yet, the output background if unset
+        #[serde(default, deserialize_with = "optional_color")]
+        fill: Option<Color>,
     },
     Border {
         width: i32,
@@ -221,6 +288,8 @@ pub struct WindowRule {
     pub r#match: WindowMatch,
     pub init: Option<WindowInit>,
     pub render: Option<BTreeMap<u32, RenderStep>>,
+    // draw only the surface, without border, shadow, background or rounding
+    pub no_decorations: Option<bool>,
 }
 
 // --- Layout ---
@@ -229,6 +298,67 @@ pub struct WindowRule {
 pub enum LayoutMode {
     #[default]
     Tile,
+    Spiral,
+    CenteredMaster,
+}
+
+// position of a new window's floating geometry on its output
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+pub enum FloatPlacement {
+    #[default]
+    Center,
+    // centered, offset down and right by the floating windows on the tag
+    Cascade,
+    // centered on the pointer
+    UnderMouse,
+}
+
+// what a fullscreen window does when another window on its output is focused
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+pub enum FullscreenUnfocus {
+    // stays fullscreen over the newly focused window
+    #[default]
+    Keep,
+    Exit,
+    // leaves fullscreen and goes to the bottom, fullscreen again once focused
+    Lower,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum FloatSize {
+    // of the output's usable area
+    Fraction(f32),
+    // logical pixels, independent of the output scale
+    Size(i32, i32),
+}
+
+impl LayoutMode {
+    pub const ALL: [LayoutMode; 3] = [
+        LayoutMode::Tile,
+        LayoutMode::Spiral,
+        LayoutMode::CenteredMaster,
+    ];
+
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|&m| m == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LayoutMode::Tile => "tile",
+            LayoutMode::Spiral => "spiral",
+            LayoutMode::CenteredMaster => "centered",
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            LayoutMode::Tile => "[]=",
+            LayoutMode::Spiral => "[@]",
+            LayoutMode::CenteredMaster => "|M|",
+        }
+    }
 }
 
 inline_default! {
@@ -246,10 +376,126 @@ inline_default! {
         pub outer_gap: i32 = 2,
         pub smart_gaps: bool,
         pub smart_borders: bool,
+        // ms a closed tile's slot is kept for the next window, 0 disables
+        pub reuse_slot_timeout: u64,
+        // columns and rows floating windows snap to while moved or resized
+        pub float_grid: Option<(u32, u32)>,
+        // windows floated for having a parent tile again once it is unset
+        pub tile_unparented: bool = true,
+        // a floating window left alone on its tag by a close tiles, unless
+        // the user floated it
+        pub auto_tile_sole_window: bool,
+        // where new windows float until moved
+        pub float_placement: FloatPlacement = FloatPlacement::Center,
+        // size of floating windows that bring none of their own
+        pub float_size: FloatSize = FloatSize::Fraction(0.75),
+        pub fullscreen_on_unfocus: FullscreenUnfocus = FullscreenUnfocus::Keep,
+        // Next/Prev focus and swap wrap around at the ends of the stack
+        pub focus_wrap: bool = true,
+        // closing the last window on a tag shows the previous tag
+        pub view_prev_on_empty: bool,
+        // one element per border instead of eight pieces, fewer draw calls;
+        // like the pieces it damages, draws and is opaque only on the ring
+        pub single_pass_borders: bool,
+        // width in pixels of the anti-aliased edge of rounded corners, lower
+        // is sharper down to a hard edge at 0, negative values count as 0
+        pub corner_feather: f32 = 1.0,
         pub default: LayoutMode = LayoutMode::Tile,
         pub tile: TileConfig = TileConfig::default(),
     }
 
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    #[serde(default)]
+    pub struct Osd {
+        // ms the osd stays up before fading out, 0 disables it
+        pub duration: u64 = 1000,
+        pub show_on: Vec<OsdTrigger> = vec![OsdTrigger::Layout],
+        // main ratio and count show on the main/stack boundary
+        pub at_split: bool = true,
+    }
+
+    // internal laptop panel (eDP, LVDS, DSI), only applies while another output is connected
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    #[serde(default)]
+    pub struct Panel {
+        // turn the panel off while the lid is closed
+        pub off_when_lid_closed: bool = true,
+        // turn the panel off whenever an external output is connected
+        pub off_with_external: bool,
+    }
+
+    // outline around the active output while more than one is connected
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    #[serde(default)]
+    pub struct ActiveOutput {
+        // None draws no outline
+        pub border: Option<Color>,
+        pub width: i32 = 2,
+    }
+
+    // windows with an open modal dialog, marked through xdg-dialog
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    #[serde(default)]
+    pub struct Modal {
+        // opacity of the shade over the parent, 0 disables it
+        pub dim: f32 = 0.4,
+        // clicks and keyboard focus on the parent go to the dialog instead
+        pub block_input: bool = true,
+    }
+
+    // border flash of windows that request attention
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    #[serde(default)]
+    pub struct Urgent {
+        // times the urgent border blinks before it stays, 0 keeps it static
+        pub flashes: u32 = 3,
+        // ms all flashes take together
+        pub duration: u64 = 1000,
+    }
+
+    // screensaver after a period without input
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    #[serde(default)]
+    pub struct Idle {
+        // ms without input before it starts, 0 disables it
+        pub timeout: u64,
+        // run instead of the built-in fade to black
+        pub command: Option<Vec<String>>,
+        // ms the outputs take to fade to black
+        pub fade: u64 = 3000,
+        // turn the outputs off once faded
+        pub power_off: bool = true,
+    }
+
+    // windows started from a terminal take its place until they close
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    #[serde(default)]
+    pub struct Swallow {
+        // app_ids of terminals that are swallowed, empty disables it
+        pub terminals: Vec<String>,
+        // app_ids that never swallow, e.g. editors or dialogs run by a command
+        pub exclude: Vec<String>,
+    }
+
+    // KillTag and KillOthers, closing many windows at once
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    #[serde(default)]
+    pub struct BulkClose {
+        // ms in which the bind must be pressed again to close, 0 closes at once
+        pub confirm: u64 = 2000,
+    }
+
+    // keys bound to FocusTag, SetTag and ToggleTag for the tag at their index
+    #[derive(Debug, Clone, PartialEq, Deserialize)]
+    #[serde(default)]
+    pub struct TagKeys {
+        pub keys: Vec<String> = (1..=9).map(|i| i.to_string()).collect(),
+        // modifiers of each action, None leaves it unbound
+        pub view: Option<Vec<Mod>> = Some(vec![Mod::Main]),
+        pub tag: Option<Vec<Mod>> = Some(vec![Mod::Main, Mod::Shift]),
+        pub toggle: Option<Vec<Mod>> = Some(vec![Mod::Main, Mod::Ctrl, Mod::Shift]),
+    }
+
     // --- Seats and input ---
 
     #[derive(Debug, Clone, PartialEq, Deserialize)]
@@ -258,8 +504,22 @@ inline_default! {
         pub focus_follows_cursor: bool = true,
         pub hide_cursor_when_typing: bool = true,
         pub cursor_warp: bool,
-        pub cursor_theme: String = "default".into(),
-        pub cursor_size: u32 = 24,
+        // where ParkCursor moves the pointer
+        pub cursor_park: Corner = Corner::BottomRight,
+        // ms without pointer motion before it is parked, 0 disables it
+        pub cursor_park_timeout: u64,
+        // clicking a floating window raises it
+        pub raise_on_click: bool = true,
+        // any focus change raises, including from the keyboard
+        pub raise_on_focus: bool,
+        // ms between the presses of a DoubleClick bind
+        pub double_click_interval: u64 = 400,
+        // scrolling over the gaps or an empty tag, not over a window or bar
+        pub root_scroll: Option<AxisAction>,
+        // XCURSOR_THEME and XCURSOR_SIZE apply while these are unset
+        pub cursor_theme: Option<String>,
+        pub cursor_size: Option<u32>,
+        pub submap_timeout: u64 = 1000,
         pub keyboard: Keyboard = Keyboard::default(),
         pub touchpad: Touchpad = Touchpad::default(),
         pub mouse: Mouse = Mouse::default(),
@@ -300,9 +560,35 @@ inline_default! {
         pub natural_scroll: bool,
         pub left_handed: bool,
         pub middle_emulation: bool,
+        // logical pixels a wheel notch scrolls
+        pub scroll_step: f64 = 15.0,
     }
 }
 
+impl Mouse {
+    // pixels and v120 steps of one scroll axis, flip applies natural_scroll
+    // where the device doesn't do it itself
+    pub fn scroll(&self, amount: Option<f64>, v120: Option<f64>, flip: bool) -> (f64, Option<f64>) {
+        let sign = if flip && self.natural_scroll { -1.0 } else { 1.0 };
+        let pixels = amount.unwrap_or_else(|| v120.unwrap_or(0.0) * self.scroll_step / 120.0);
+        (pixels * sign, v120.map(|v| v * sign))
+    }
+}
+
+impl Panel {
+    // whether the internal panel is turned off, never without an external output
+    pub fn off(&self, external: bool, lid_closed: bool) -> bool {
+        external && (self.off_with_external || (lid_closed && self.off_when_lid_closed))
+    }
+}
+
+// what scrolling does where no client gets it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum AxisAction {
+    // down focuses the next window, up the previous
+    FocusStack,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
 pub enum AccelProfile {
     Flat,
@@ -318,7 +604,7 @@ impl From<AccelProfile> for InputAccelProfile {
     }
 }
 
-#[derive(Debug, Default, Clone, Deref)]
+#[derive(Debug, Default, Clone, Deref, DerefMut)]
 pub struct SeatMap(HashMap<String, SeatConfig>);
 
 impl<'de> Deserialize<'de> for SeatMap {
@@ -327,6 +613,27 @@ impl<'de> Deserialize<'de> for SeatMap {
     }
 }
 
+impl SeatConfig {
+    // cursor theme and size: the config, else XCURSOR_THEME and XCURSOR_SIZE,
+    // else "default" at 24
+    pub fn cursor(&self) -> (String, u32) {
+        let theme = self.cursor_theme.clone().unwrap_or_else(|| {
+            std::env::var("XCURSOR_THEME")
+                .ok()
+                .filter(|env| !env.is_empty())
+                .unwrap_or_else(|| "default".into())
+        });
+        let size = self.cursor_size.map(|s| s.max(1)).unwrap_or_else(|| {
+            std::env::var("XCURSOR_SIZE")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .filter(|&env| env > 0)
+                .unwrap_or(24)
+        });
+        (theme, size)
+    }
+}
+
 impl Keyboard {
     pub fn xkb_config(&self) -> XkbConfig<'_> {
         XkbConfig {
@@ -338,6 +645,45 @@ impl Keyboard {
     }
 }
 
+// --- OSD ---
+
+// actions that show their result in the osd
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum OsdTrigger {
+    Layout,
+    MainRatio,
+    MainCount,
+    Tag,
+}
+
+impl OsdTrigger {
+    pub fn of(action: &Action) -> Option<Self> {
+        use Action::*;
+        match action {
+            SetLayout(_) | CycleLayout => Some(Self::Layout),
+            AdjustMainRatio(_) | SetMainRatio(_) | ZoomAndExpand(_) | BalanceLayout
+            | ResetAllLayouts => Some(Self::MainRatio),
+            AdjustMainCount(_) | SetMainCount(_) => Some(Self::MainCount),
+            FocusTag(_) | FocusTagGlobal(_) | FocusPrevTag | PeekPrevTag => Some(Self::Tag),
+            _ => None,
+        }
+    }
+}
+
+// --- Popups ---
+
+// popups that still do not fit once the client's constraint adjustments ran
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum PopupOverflow {
+    // partly off screen, as the positioner left them
+    #[default]
+    Keep,
+    // moved fully on screen, the top left stays visible if too large
+    Slide,
+    // moved on screen and cut down to its size
+    Shrink,
+}
+
 // --- Config ---
 
 #[derive(Debug, Default, Clone, Deserialize)]
@@ -345,10 +691,33 @@ impl Keyboard {
 pub struct Config {
     pub colors: Palette,
     pub outputs: Vec<OutputRule>,
+    // DRM device rendering and driving the outputs, read at startup
+    pub render_device: Option<PathBuf>,
+    // directory of fragment shaders replacing the built-in ones, read at
+    // startup and by ReloadRenderer
+    pub shaders: Option<PathBuf>,
+    // where screenshots are written, see screenshot_dir()
+    pub screenshot_dir: Option<PathBuf>,
     pub layout: Layout,
+    pub osd: Osd,
+    pub panel: Panel,
+    pub active_output: ActiveOutput,
+    pub modal: Modal,
+    pub urgent: Urgent,
+    pub idle: Idle,
+    pub bulk_close: BulkClose,
+    pub swallow: Swallow,
+    pub popup_overflow: PopupOverflow,
+    // environment for spawned commands and the autostart script
+    pub env: BTreeMap<String, String>,
+    // remember the tags of windows by app_id and restore them on relaunch
+    pub session: bool,
     pub windows: Vec<WindowRule>,
     pub seats: SeatMap,
+    pub main_mod: MainMod,
+    pub tag_keys: TagKeys,
     pub binds: BindMap,
+    pub submaps: HashMap<String, BindMap>,
     #[serde(skip)]
     pub path: PathBuf,
 }
@@ -359,7 +728,32 @@ impl Config {
     }
 
     pub fn parse(text: &str) -> Result<Self, String> {
-        ron::from_str::<Config>(text).map_err(|e| e.to_string())
+        let mut config = ron::from_str::<Config>(text).map_err(|e| e.to_string())?;
+        // Main stays unresolved until main_mod is known, wherever it is written
+        let main = config.main_mod.0;
+        config.binds.resolve(main)?;
+        for (name, binds) in &mut config.submaps {
+            binds
+                .resolve(main)
+                .map_err(|e| format!("submap {name}: {e}"))?;
+        }
+        config.binds.add_tag_keys(&config.tag_keys, main)?;
+        for (i, rule) in config.windows.iter().enumerate() {
+            if let Some(init) = &rule.init
+                && init.tag.is_some()
+                && init.tags.is_some()
+            {
+                return Err(format!("window rule {i}: set either tag or tags"));
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn screenshot_dir(&self) -> PathBuf {
+        self.screenshot_dir
+            .clone()
+            .or_else(|| dirs::picture_dir().map(|d| d.join("Screenshots")))
+            .unwrap_or_else(std::env::temp_dir)
     }
 
     pub fn load(explicit: Option<PathBuf>) -> Result<Self, String> {
@@ -375,12 +769,33 @@ impl Config {
 
 // --- Bindings ---
 
-#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
 pub enum Mod {
     Shift,
     Ctrl,
     Alt,
     Super,
+    // resolves to main_mod
+    Main,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MainMod(pub Mod);
+
+impl Default for MainMod {
+    fn default() -> Self {
+        Self(Mod::Super)
+    }
+}
+
+impl<'de> Deserialize<'de> for MainMod {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let m = Mod::deserialize(d)?;
+        if m == Mod::Main {
+            return Err(Error::custom("main_mod must be Shift, Ctrl, Alt or Super"));
+        }
+        Ok(Self(m))
+    }
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
@@ -402,13 +817,15 @@ impl From<&ModifiersState> for Mods {
     }
 }
 
-impl From<&[Mod]> for Mods {
-    fn from(v: &[Mod]) -> Self {
+impl Mods {
+    // Main in v stands for main
+    pub fn resolve(v: &[Mod], main: Mod) -> Self {
+        let has = |m| v.contains(&m) || (m == main && v.contains(&Mod::Main));
         Self {
-            shift: v.contains(&Mod::Shift),
-            ctrl: v.contains(&Mod::Ctrl),
-            alt: v.contains(&Mod::Alt),
-            logo: v.contains(&Mod::Super),
+            shift: has(Mod::Shift),
+            ctrl: has(Mod::Ctrl),
+            alt: has(Mod::Alt),
+            logo: has(Mod::Super),
         }
     }
 }
@@ -437,28 +854,83 @@ pub enum Direction {
     Right,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub enum Action {
     Noop,
 
     Focus(Rel),
+    // focus and raise the next (1) or previous (-1) floating window
+    CycleFloating(i32),
+    // move between a dialog and the window it belongs to
+    FocusParent,
+    FocusChild,
     Swap(Rel),
+    Zoom,
+    // list the tag's windows by recent focus and step through them while
+    // the modifiers are held, releasing them focuses the selection
+    SwitchWindow(i32),
+    // Zoom, then SetMainRatio to this
+    ZoomAndExpand(f32),
     Close,
+    // close every window on the active tag
+    KillTag,
+    // close the tiled windows on the active tag but the focused one
+    KillOthers,
     ToggleFloat,
     ToggleFullscreen,
+    // fullscreen inside the layout area, keeping bars and gaps
+    ToggleFakeFullscreen,
+    // keep the float size while tiled, for clients that misbehave when resized
+    ToggleSizeLock,
 
     FocusTag(usize),
+    // FocusTag on every monitor at once
+    FocusTagGlobal(usize),
     FocusPrevTag,
+    // show the previous tag while the key is held
+    PeekPrevTag,
     SetTag(usize),
     ToggleTag(usize),
 
     FocusOutput(Direction),
     SendToOutput(Direction),
+    // keep the pointer from leaving the active output
+    TogglePointerConfinement,
+    // move floating windows that ended up off screen back onto it
+    RescueWindows,
+    // the output in this direction shows the active one, again to stop
+    DuplicateOutput(Direction),
 
     AdjustMainCount(i32),
     SetMainCount(usize),
     AdjustMainRatio(f32),
     SetMainRatio(f32),
+    // Left/Right move the main/stack split, Up/Down shrink or grow the
+    // focused tile within its column
+    ResizeTile(Direction, f32),
+    // reset the main ratio and tile heights of the active tag
+    BalanceLayout,
+    // BalanceLayout and the main count on every tag of every output
+    ResetAllLayouts,
+    SetLayout(LayoutMode),
+    CycleLayout,
+    // main area on the right of the active tag, or back on the left
+    ToggleMirror,
+    // label the visible windows and focus the one whose key is pressed next
+    WindowHints,
+    // move the pointer to the cursor_park corner, e.g. off a video
+    ParkCursor,
+    ShowOsd(String),
+    // frame rate, frame time and element count in a corner of every output
+    ToggleRenderStats,
 
     Move,
     Resize,
@@ -467,8 +939,36 @@ pub enum Action {
     PowerOn,
 
     Spawn(Vec<String>),
+    SpawnAndTag(Vec<String>),
+    // start the locker, suspend only once its lock shows on every output
+    LockAndSuspend {
+        locker: Vec<String>,
+        #[serde(default = "default_suspend")]
+        suspend: Vec<String>,
+    },
     Exit,
+    // ask every window to close, exit once all did
+    ExitGracefully,
+    // drop all input but this key bind, e.g. while cleaning the keyboard
+    ToggleInputInhibit,
     ReloadConfig,
+    // recompile shaders and redraw, recreating the renderer on DRM
+    ReloadRenderer,
+    EnterSubmap(String),
+    DumpState(Option<PathBuf>),
+    Screenshot {
+        // file name inside screenshot_dir, a timestamped one if unset
+        #[serde(default)]
+        name: Option<String>,
+        // (x, y, w, h) in output-local logical coordinates
+        #[serde(default)]
+        region: Option<(i32, i32, i32, i32)>,
+        #[serde(default)]
+        cursor: bool,
+        // only the focused window
+        #[serde(default)]
+        window: bool,
+    },
     ChangeVt(i32),
 }
 
@@ -478,6 +978,8 @@ pub enum Action {
 pub enum Trigger {
     Key(Keysym),
     Mouse(u32),
+    // second press of a button within double_click_interval
+    DoubleClick(u32),
 }
 
 impl<'de> Deserialize<'de> for Trigger {
@@ -486,31 +988,84 @@ impl<'de> Deserialize<'de> for Trigger {
         enum Raw {
             Key(String),
             Mouse(Button),
+            DoubleClick(Button),
         }
         match Raw::deserialize(d)? {
-            Raw::Key(name) => {
-                let sym = xkb::keysym_from_name(&name, xkb::KEYSYM_CASE_INSENSITIVE);
-                if sym.raw() == 0 {
-                    return Err(Error::custom(format!("unknown key: {name}")));
-                }
-                Ok(Trigger::Key(sym))
-            }
+            Raw::Key(name) => keysym(&name).map(Trigger::Key).map_err(Error::custom),
             Raw::Mouse(btn) => Ok(Trigger::Mouse(btn as u32)),
+            Raw::DoubleClick(btn) => Ok(Trigger::DoubleClick(btn as u32)),
         }
     }
 }
 
+fn keysym(name: &str) -> Result<Keysym, String> {
+    let sym = xkb::keysym_from_name(name, xkb::KEYSYM_CASE_INSENSITIVE);
+    if sym.raw() == 0 {
+        return Err(format!("unknown key: {name}"));
+    }
+    Ok(sym)
+}
+
 #[derive(Debug, Default, Clone, Deref)]
-pub struct BindMap(HashMap<(Trigger, Mods), Action>);
+pub struct BindMap {
+    #[deref]
+    map: HashMap<(Trigger, Mods), Action>,
+    // as written, keyed into map by resolve once main_mod is known
+    raw: Vec<(Vec<Mod>, Trigger, Action)>,
+}
+
+impl BindMap {
+    fn resolve(&mut self, main: Mod) -> Result<(), String> {
+        let mut via_main = HashSet::new();
+        for (mods, trigger, action) in std::mem::take(&mut self.raw) {
+            let key = (trigger, Mods::resolve(&mods, main));
+            // a main_mod bind must not silently shadow an explicit one
+            let is_main = mods.contains(&Mod::Main);
+            if self.map.contains_key(&key) && (is_main || via_main.contains(&key)) {
+                return Err(format!(
+                    "{trigger:?} with {mods:?} conflicts with another binding"
+                ));
+            }
+            if is_main {
+                via_main.insert(key);
+            }
+            self.map.insert(key, action);
+        }
+        Ok(())
+    }
+
+    // a key that can turn inhibited input back on, mouse binds are inhibited too
+    pub fn has_inhibit_toggle(&self) -> bool {
+        self.iter().any(|((trigger, _), action)| {
+            matches!(trigger, Trigger::Key(_)) && matches!(action, Action::ToggleInputInhibit)
+        })
+    }
+
+    // binds generated from tag_keys, explicit binds keep their combination
+    fn add_tag_keys(&mut self, tag_keys: &TagKeys, main: Mod) -> Result<(), String> {
+        for (i, name) in tag_keys.keys.iter().enumerate() {
+            let sym = keysym(name).map_err(|e| format!("tag_keys: {e}"))?;
+            let actions = [
+                (&tag_keys.view, Action::FocusTag(i)),
+                (&tag_keys.tag, Action::SetTag(i)),
+                (&tag_keys.toggle, Action::ToggleTag(i)),
+            ];
+            for (mods, action) in actions {
+                let Some(mods) = mods else { continue };
+                let key = (Trigger::Key(sym), Mods::resolve(mods, main));
+                self.map.entry(key).or_insert(action);
+            }
+        }
+        Ok(())
+    }
+}
 
 impl<'de> Deserialize<'de> for BindMap {
     fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
-        let raw: Vec<(Vec<Mod>, Trigger, Action)> = Vec::deserialize(d)?;
-        let mut map = HashMap::new();
-        for (mods, trigger, action) in raw {
-            map.insert((trigger, Mods::from(mods.as_slice())), action);
-        }
-        Ok(BindMap(map))
+        Ok(BindMap {
+            map: HashMap::new(),
+            raw: Vec::deserialize(d)?,
+        })
     }
 }
 
@@ -602,6 +1157,17 @@ mod tests {
         let code = Config::default();
 
         assert_eq!(file.layout, code.layout);
+        assert_eq!(file.osd, code.osd);
+        assert_eq!(file.panel, code.panel);
+        assert_eq!(file.modal, code.modal);
+        assert_eq!(file.urgent, code.urgent);
+        assert_eq!(file.idle, code.idle);
+        assert_eq!(file.bulk_close, code.bulk_close);
+        assert_eq!(file.swallow, code.swallow);
+        assert_eq!(file.popup_overflow, code.popup_overflow);
+        assert_eq!(file.active_output, code.active_output);
+        assert_eq!(file.tag_keys, code.tag_keys);
+        assert_eq!(file.env, code.env);
         assert_eq!(file.seats["seat0"], SeatConfig::default());
         assert!(!file.binds.is_empty(), "binds empty");
     }
@@ -671,6 +1237,137 @@ mod tests {
         assert!(Config::parse(ron).is_err());
     }
 
+    #[test]
+    fn main_mod_defaults_to_super() {
+        let ron = r#"(binds: [([Main], Key("q"), Close)])"#;
+        let config = Config::parse(ron).unwrap();
+        let mods = Mods {
+            logo: true,
+            ..Default::default()
+        };
+        assert!(config.binds.contains_key(&(Trigger::Key(Keysym::q), mods)));
+    }
+
+    #[test]
+    fn main_mod_alt() {
+        let ron = r#"(main_mod: Alt, binds: [([Main, Shift], Key("q"), Close)])"#;
+        let config = Config::parse(ron).unwrap();
+        let mods = Mods {
+            alt: true,
+            shift: true,
+            ..Default::default()
+        };
+        assert!(config.binds.contains_key(&(Trigger::Key(Keysym::q), mods)));
+    }
+
+    #[test]
+    fn main_mod_conflict_errors() {
+        let ron = r#"(main_mod: Alt, binds: [
+            ([Alt], Key("q"), Close),
+            ([Main], Key("q"), Exit),
+        ])"#;
+        assert!(Config::parse(ron).is_err());
+    }
+
+    #[test]
+    fn main_mod_after_binds_still_applies() {
+        let ron = r#"(
+            binds: [([Main], Key("q"), Close)],
+            submaps: {"resize": [([Main], Key("r"), Close)]},
+            main_mod: Alt,
+        )"#;
+        let config = Config::parse(ron).unwrap();
+        let alt = Mods {
+            alt: true,
+            ..Default::default()
+        };
+        assert!(config.binds.contains_key(&(Trigger::Key(Keysym::q), alt)));
+        let resize = &config.submaps["resize"];
+        assert!(resize.contains_key(&(Trigger::Key(Keysym::r), alt)));
+    }
+
+    #[test]
+    fn main_mod_cannot_be_main() {
+        assert!(Config::parse("(main_mod: Main)").is_err());
+    }
+
+    #[test]
+    fn submaps_parse() {
+        let ron = r#"(submaps: {"layout": [([], Key("Left"), AdjustMainRatio(-0.05))]})"#;
+        let config = Config::parse(ron).unwrap();
+        let binds = &config.submaps["layout"];
+        let key = (Trigger::Key(Keysym::Left), Mods::default());
+        assert!(matches!(binds.get(&key), Some(Action::AdjustMainRatio(_))));
+    }
+
+    #[test]
+    fn tag_keys_generate_binds() {
+        let ron = r#"(
+            main_mod: Alt,
+            tag_keys: (keys: ["a", "s"], toggle: None),
+            binds: [([Alt], Key("s"), Close)],
+        )"#;
+        let config = Config::parse(ron).unwrap();
+        let alt = Mods {
+            alt: true,
+            ..Default::default()
+        };
+        let shift = Mods { shift: true, ..alt };
+        let get = |sym, mods| config.binds.get(&(Trigger::Key(sym), mods));
+        assert!(matches!(get(Keysym::a, alt), Some(Action::FocusTag(0))));
+        assert!(matches!(get(Keysym::s, shift), Some(Action::SetTag(1))));
+        // explicit binds win over generated ones
+        assert!(matches!(get(Keysym::s, alt), Some(Action::Close)));
+        assert_eq!(config.binds.len(), 4);
+
+        assert!(Config::parse(r#"(tag_keys: (keys: ["nope"]))"#).is_err());
+    }
+
+    #[test]
+    fn wheel_notches_scroll_by_the_step_and_flip_when_natural() {
+        let mut mouse = Mouse {
+            scroll_step: 30.0,
+            ..Mouse::default()
+        };
+        assert_eq!(mouse.scroll(None, Some(120.0), true), (30.0, Some(120.0)));
+        assert_eq!(mouse.scroll(Some(7.5), None, true), (7.5, None), "smooth");
+
+        mouse.natural_scroll = true;
+        assert_eq!(mouse.scroll(None, Some(120.0), true), (-30.0, Some(-120.0)));
+        let libinput = mouse.scroll(None, Some(120.0), false);
+        assert_eq!(libinput, (30.0, Some(120.0)), "the device flips it");
+    }
+
+    #[test]
+    fn panel_turns_off_only_with_an_external_output() {
+        let panel = Panel::default();
+        assert!(panel.off(true, true), "lid closed, external connected");
+        assert!(!panel.off(true, false), "lid open");
+        assert!(!panel.off(false, true), "the only output stays on");
+
+        let panel = Config::parse("(panel: (off_with_external: true))")
+            .unwrap()
+            .panel;
+        assert!(panel.off(true, false));
+        assert!(!panel.off(false, false));
+    }
+
+    #[test]
+    fn inhibit_toggle_needs_a_key_bind() {
+        let mouse = r#"(binds: [([Super], Mouse(Middle), ToggleInputInhibit)])"#;
+        assert!(!Config::parse(mouse).unwrap().binds.has_inhibit_toggle());
+        let key = r#"(binds: [([Ctrl, Alt], Key("Pause"), ToggleInputInhibit)])"#;
+        assert!(Config::parse(key).unwrap().binds.has_inhibit_toggle());
+    }
+
+    #[test]
+    fn double_click_parses() {
+        let ron = r#"(binds: [([], DoubleClick(Left), ToggleFloat)])"#;
+        let config = Config::parse(ron).unwrap();
+        let key = (Trigger::DoubleClick(Button::Left as u32), Mods::default());
+        assert!(matches!(config.binds.get(&key), Some(Action::ToggleFloat)));
+    }
+
     #[test]
     fn keysym_unknown() {
         let ron = r#"(binds: [([Super], Key("NonExistentKey_XYZ"), Quit)])"#;
@@ -695,6 +1392,38 @@ mod tests {
         assert!(config.windows[0].r#match.floating.is_none());
     }
 
+    #[test]
+    fn env_map_parses() {
+        let config: Config = ron::from_str(r#"(env: {"GDK_BACKEND": "wayland"})"#).unwrap();
+        assert_eq!(config.env["GDK_BACKEND"], "wayland");
+        assert!(Config::default().env.is_empty());
+    }
+
+    #[test]
+    fn window_surface_fill_defaults_to_background() {
+        let ron = "WindowSurface(radius: 6.0)";
+        let step: RenderStep = ron::from_str(ron).unwrap();
+        assert!(matches!(step, RenderStep::WindowSurface { fill: None, .. }));
+    }
+
+    #[test]
+    fn window_surface_fill_takes_a_bare_color() {
+        let black = Some(Color([0.0, 0.0, 0.0, 1.0]));
+        for ron in [
+            r##"WindowSurface(fill: "#000000")"##,
+            r##"WindowSurface(fill: Some("#000000"))"##,
+            r##"#![enable(implicit_some)] WindowSurface(fill: "#000000")"##,
+        ] {
+            let step: RenderStep = ron::from_str(ron).unwrap();
+            assert!(
+                matches!(step, RenderStep::WindowSurface { fill, .. } if fill == black),
+                "{ron}"
+            );
+        }
+        let step: RenderStep = ron::from_str("WindowSurface(fill: None)").unwrap();
+        assert!(matches!(step, RenderStep::WindowSurface { fill: None, .. }));
+    }
+
     #[test]
     fn pattern_exact_match() {
         let p: Pattern = ron::from_str("\"firefox\"").unwrap();
@@ -758,4 +1487,33 @@ mod tests {
         let tags = config.outputs[0].tags.as_ref().unwrap();
         assert_eq!(tags, &["9", "7-1", "music"]);
     }
+
+    #[test]
+    fn window_rule_takes_tag_or_tags_not_both() {
+        let ron =
+            "#![enable(implicit_some)]\n(windows: [(match: (), init: (tag: Tag(1), tags: [2]))])";
+        let err = Config::parse(ron).unwrap_err();
+        assert!(err.contains("window rule 0"), "{err}");
+
+        let ron = "#![enable(implicit_some)]\n(windows: [(match: (), init: (tag: Current))])";
+        assert!(Config::parse(ron).is_ok());
+    }
+
+    #[test]
+    fn configured_cursor_wins_over_env() {
+        let seat = SeatConfig {
+            cursor_theme: Some("Adwaita".into()),
+            cursor_size: Some(32),
+            ..Default::default()
+        };
+        assert_eq!(seat.cursor(), ("Adwaita".to_string(), 32));
+
+        // the fallback values set explicitly still override the env
+        let seat = SeatConfig {
+            cursor_theme: Some("default".into()),
+            cursor_size: Some(24),
+            ..Default::default()
+        };
+        assert_eq!(seat.cursor(), ("default".to_string(), 24));
+    }
 }
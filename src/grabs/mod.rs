@@ -14,6 +14,41 @@ macro_rules! forward_gesture {
     };
 }
 
+use smithay::{
+    desktop::layer_map_for_output,
+    utils::{Logical, Point, Rectangle},
+};
+
+use crate::{shell::WindowId, state::State};
+
+// cell size and origin of the float grid for a window's output, None when
+// snapping is off or shift is held
+fn float_grid(state: &State, id: WindowId) -> Option<Rectangle<i32, Logical>> {
+    let (cols, rows) = state.config.layout.float_grid?;
+    let shift = state
+        .seat
+        .get_keyboard()
+        .is_some_and(|kb| kb.modifier_state().shift);
+    if shift || cols == 0 || rows == 0 {
+        return None;
+    }
+    let mon = state.monitors.get(state.windows.get(id)?.monitor)?;
+    let area = layer_map_for_output(&mon.output).non_exclusive_zone();
+    let cell = (area.size.w / cols as i32, area.size.h / rows as i32);
+    (cell.0 > 0 && cell.1 > 0).then(|| Rectangle::new(area.loc, cell.into()))
+}
+
+fn snap(p: Point<i32, Logical>, grid: Rectangle<i32, Logical>) -> Point<i32, Logical> {
+    let round = |v: i32, origin: i32, cell: i32| {
+        origin + ((v - origin) as f64 / cell as f64).round() as i32 * cell
+    };
+    (
+        round(p.x, grid.loc.x, grid.size.w),
+        round(p.y, grid.loc.y, grid.size.h),
+    )
+        .into()
+}
+
 pub mod move_grab;
 pub use move_grab::MoveSurfaceGrab;
 
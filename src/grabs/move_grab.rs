@@ -38,9 +38,11 @@ impl PointerGrab<Monotile> for MoveSurfaceGrab {
         // While the grab is active, no client has pointer focus
         handle.motion(monotile, None, event);
 
+        let grid = super::float_grid(&monotile.state, self.window_id);
         if let Some(we) = monotile.state.windows.get_mut(self.window_id) {
             let delta = event.location - self.start_data.location;
-            we.float_geo.loc = (self.initial_loc.to_f64() + delta).to_i32_round();
+            let loc = (self.initial_loc.to_f64() + delta).to_i32_round();
+            we.float_geo.loc = grid.map_or(loc, |g| super::snap(loc, g));
         }
     }
 
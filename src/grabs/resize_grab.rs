@@ -37,13 +37,21 @@ impl PointerGrab<Monotile> for ResizeSurfaceGrab {
     ) {
         handle.motion(monotile, None, event);
 
+        let grid = super::float_grid(&monotile.state, self.window_id);
         let Some(we) = monotile.state.windows.get_mut(self.window_id) else {
             return;
         };
 
         let delta = event.location - self.start_data.location;
-        let new_w = self.initial_rect.size.w + delta.x as i32;
-        let new_h = self.initial_rect.size.h + delta.y as i32;
+        let mut new_w = self.initial_rect.size.w + delta.x as i32;
+        let mut new_h = self.initial_rect.size.h + delta.y as i32;
+        // snap the bottom right corner, keeping at least one cell
+        if let Some(g) = grid {
+            let loc = we.float_geo.loc;
+            let corner = super::snap(loc + Point::from((new_w, new_h)), g);
+            new_w = (corner.x - loc.x).max(g.size.w);
+            new_h = (corner.y - loc.y).max(g.size.h);
+        }
 
         let (min, max) = we.min_max_size();
         let clamp = |v: i32, lo: i32, hi: i32| {
@@ -9,6 +9,7 @@ use smithay::{
         Client, Resource,
         protocol::{wl_buffer, wl_surface::WlSurface},
     },
+    utils::Rectangle,
     wayland::{
         buffer::BufferHandler,
         compositor::{
@@ -29,6 +30,10 @@ impl CompositorHandler for Monotile {
         &client.get_data::<ClientState>().unwrap().compositor_state
     }
 
+    fn new_surface(&mut self, surface: &WlSurface) {
+        super::drm_syncobj::add_acquire_hook(surface);
+    }
+
     fn commit(&mut self, surface: &WlSurface) {
         on_commit_buffer_handler::<Self>(surface);
 
@@ -50,7 +55,9 @@ impl CompositorHandler for Monotile {
 
         // TODO: use the output the surface is mapped on when
         // multi-monitor is implemented
-        self.backend.schedule_render(&self.state.mon().output);
+        if let Some(mon) = self.state.mon() {
+            self.backend.schedule_render(&mon.output);
+        }
     }
 }
 
@@ -61,8 +68,34 @@ impl Monotile {
             root = parent;
         }
         let id = self.state.windows.find_by_surface(&root)?;
-        self.state.windows[id].on_commit();
-        None
+        let we = &mut self.state.windows[id];
+        we.on_commit();
+
+        // min/max changed after map: float tiles that no longer fit, in place
+        if !we.size_hints_changed() || we.floating || we.fullscreen || !we.violates_size_hints() {
+            return None;
+        }
+        let mon = we.monitor;
+        let tile = self
+            .state
+            .monitors
+            .get(mon)
+            .and_then(|m| m.window_rect(&self.state.windows, id))
+            .unwrap_or_default();
+        let we = &mut self.state.windows[id];
+        let (min, max) = we.min_max_size();
+        let clamp = |v: i32, lo: i32, hi: i32| {
+            let v = v.max(lo);
+            if hi > 0 { v.min(hi) } else { v }
+        };
+        let size = (
+            clamp(tile.size.w, min.w, max.w),
+            clamp(tile.size.h, min.h, max.h),
+        );
+        we.float_geo = Rectangle::new(tile.loc, size.into());
+        we.set_floating(true);
+        we.configure(we.float_geo.size);
+        Some(mon)
     }
 
     fn on_popup_commit(&mut self, surface: &WlSurface) -> Option<usize> {
@@ -90,18 +123,19 @@ impl Monotile {
         if unmapped.placement.is_none() {
             // phase 1: first commit - send configure with tiled size
             let floating = unmapped.should_float();
-            let mon = &self.state.monitors[self.state.active_monitor];
-            let configured_size = if floating {
-                (0, 0).into()
-            } else {
-                let tag = mon.tag();
-                let count = tag.layout.len() + 1;
-                let area = layer_map_for_output(&mon.output).non_exclusive_zone();
-                tag.layout
-                    .compute_rects(count, area)
-                    .last()
-                    .map(|r| r.size)
-                    .unwrap_or(area.size)
+            let mon = self.state.monitors.get(self.state.active_monitor);
+            let configured_size = match mon {
+                Some(mon) if !floating => {
+                    let tag = mon.tag();
+                    let count = tag.layout.len() + 1;
+                    let area = layer_map_for_output(&mon.output).non_exclusive_zone();
+                    tag.layout
+                        .compute_rects(count, area)
+                        .last()
+                        .map(|r| r.size)
+                        .unwrap_or(area.size)
+                }
+                _ => (0, 0).into(),
             };
             unmapped.configure_initial(configured_size, !floating);
             unmapped.placement = Some(Placement {
@@ -164,8 +198,9 @@ impl Monotile {
             }
             let changed = map.arrange();
             drop(map);
-            self.state.monitors[i].update_exclusive_layer();
-            return if changed { Some(i) } else { None };
+            // a layer gaining or dropping exclusive interactivity moves the keyboard
+            let refocus = self.state.monitors[i].update_exclusive_layer();
+            return (changed || refocus).then_some(i);
         }
         None
     }
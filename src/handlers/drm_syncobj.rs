@@ -0,0 +1,48 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use smithay::{
+    delegate_drm_syncobj,
+    reexports::wayland_server::{Resource, protocol::wl_surface::WlSurface},
+    wayland::{
+        compositor::{CompositorHandler, add_blocker, add_pre_commit_hook, with_states},
+        drm_syncobj::{DrmSyncobjCachedState, DrmSyncobjHandler, DrmSyncobjState},
+    },
+};
+
+use crate::Monotile;
+
+impl DrmSyncobjHandler for Monotile {
+    fn drm_syncobj_state(&mut self) -> Option<&mut DrmSyncobjState> {
+        self.state.syncobj_state.as_mut()
+    }
+}
+
+delegate_drm_syncobj!(Monotile);
+
+// explicit sync commits wait for their acquire point before the buffer is
+// used, the release point is signalled by smithay once the buffer is released
+pub fn add_acquire_hook(surface: &WlSurface) {
+    add_pre_commit_hook::<Monotile, _>(surface, |mt, _, surface| {
+        let acquire = with_states(surface, |states| {
+            let mut cached = states.cached_state.get::<DrmSyncobjCachedState>();
+            cached.pending().acquire_point.clone()
+        });
+        let Some(point) = acquire else {
+            return;
+        };
+        let Some(client) = surface.client() else {
+            return;
+        };
+        let Ok((blocker, source)) = point.generate_blocker() else {
+            return;
+        };
+        let res = mt.state.loop_handle.insert_source(source, move |_, _, mt| {
+            let dh = mt.state.display_handle.clone();
+            mt.client_compositor_state(&client).blocker_cleared(mt, &dh);
+            Ok(())
+        });
+        if res.is_ok() {
+            add_blocker(surface, blocker);
+        }
+    });
+}
@@ -26,10 +26,14 @@ impl WlrLayerShellHandler for Monotile {
         _layer: Layer,
         namespace: String,
     ) {
-        let output = wl_output
+        let Some(output) = wl_output
             .as_ref()
             .and_then(Output::from_resource)
-            .unwrap_or_else(|| self.state.mon().output.clone());
+            .or_else(|| self.state.mon().map(|m| m.output.clone()))
+        else {
+            surface.send_close();
+            return;
+        };
         let mut map = layer_map_for_output(&output);
         let layer = LayerSurface::new(surface, namespace);
         map.map_layer(&layer).unwrap();
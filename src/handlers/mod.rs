@@ -2,6 +2,7 @@
 
 mod compositor;
 mod dmabuf;
+mod drm_syncobj;
 pub mod foreign_toplevel;
 mod idle_notifier;
 mod layer_shell;
@@ -72,7 +73,9 @@ impl SeatHandler for Monotile {
             return;
         }
         self.state.cursor.status = image;
-        self.backend.schedule_render(&self.state.mon().output);
+        if let Some(mon) = self.state.mon() {
+            self.backend.schedule_render(&mon.output);
+        }
     }
 
     fn led_state_changed(&mut self, seat: &Seat<Self>, led_state: LedState) {
@@ -134,7 +137,9 @@ impl DndGrabHandler for Monotile {
         _location: Point<f64, Logical>,
     ) {
         self.state.cursor.clear_dnd_icon();
-        self.backend.schedule_render(&self.state.mon().output);
+        if let Some(mon) = self.state.mon() {
+            self.backend.schedule_render(&mon.output);
+        }
     }
 }
 
@@ -150,7 +155,9 @@ impl WaylandDndGrabHandler for Monotile {
         self.state.cursor.set_dnd_icon(icon);
         match type_ {
             GrabType::Pointer => {
-                let ptr = seat.get_pointer().unwrap();
+                let Some(ptr) = seat.get_pointer() else {
+                    return;
+                };
                 let start_data = ptr.grab_start_data().unwrap();
 
                 let grab =
@@ -188,11 +195,7 @@ impl XdgActivationHandler for Monotile {
         if self.state.windows[id].focused {
             return;
         }
-        self.state.windows[id].urgent = true;
-        self.state.windows[id].resolve_render();
-        self.state.ipc.dirty = true;
-        self.backend
-            .schedule_render(&self.state.monitors[self.state.windows[id].monitor].output);
+        self.set_urgent(id);
     }
 }
 delegate_xdg_activation!(Monotile);
@@ -1,7 +1,12 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use smithay::{
-    backend::renderer::{Color32F, damage::OutputDamageTracker, element::Kind, glow::GlowRenderer},
+    backend::renderer::{
+        Color32F,
+        damage::OutputDamageTracker,
+        element::{Kind, RenderElement},
+        glow::GlowRenderer,
+    },
     delegate_image_capture_source, delegate_image_copy_capture, delegate_output_capture_source,
     delegate_toplevel_capture_source,
     output::{Output, WeakOutput},
@@ -28,11 +33,12 @@ use tracing::warn;
 
 use crate::{
     Monotile,
-    render::MonotileElement,
     shell::{Monitors, WindowId, Windows},
     state::State,
 };
 
+// a session casts a whole output or a single toplevel, ext-image-copy-capture
+// has no region source so region casts are left to screenshots for now
 pub struct ScreencopySession {
     pub session: Session,
     pub damage_tracker: OutputDamageTracker,
@@ -392,11 +398,11 @@ impl ImageCopyCaptureHandler for Monotile {
 }
 delegate_image_copy_capture!(Monotile);
 
-pub fn capture_frame(
+pub fn capture_frame<E: RenderElement<GlowRenderer>>(
     renderer: &mut GlowRenderer,
     state: &mut State,
     output: &Output,
-    output_elems: &[MonotileElement],
+    output_elems: &[E],
     cursor_count: usize,
     background: impl Into<Color32F> + Copy,
     elapsed: std::time::Duration,
@@ -465,14 +471,16 @@ pub fn capture_frame(
                 }
                 let mut elems =
                     we.render_content(renderer, (0, 0).into(), scale, Kind::Unspecified);
-                if s.session.draw_cursor() {
+                if s.session.draw_cursor()
+                    && let Some(ptr) = state.seat.get_pointer()
+                {
                     let window_loc = state.monitors[we.monitor]
                         .window_rect(&state.windows, id)
                         .map(|r| r.loc)
                         .unwrap_or_default();
-                    let ptr_pos = state.seat.get_pointer().unwrap().current_location();
+                    let ptr_pos = ptr.current_location();
                     let local_pos = ptr_pos - window_loc.to_f64();
-                    elems.splice(0..0, state.cursor.elements(renderer, local_pos));
+                    elems.splice(0..0, state.cursor.elements(renderer, local_pos, scale.x));
                 }
                 match crate::render::render_to_buffer(
                     renderer,
@@ -513,7 +521,7 @@ pub fn capture_cursor(
             continue;
         }
         let hotspot = state.cursor.hotspot.to_f64();
-        let elems = state.cursor.elements(renderer, hotspot);
+        let elems = state.cursor.elements(renderer, hotspot, 1.0);
         let size = state.cursor.size as i32;
         match crate::render::render_to_buffer(
             renderer,
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use crate::{Monotile, state::State};
+use crate::{Monotile, spawn::spawn, state::State};
 use smithay::{
     delegate_session_lock,
     output::Output,
@@ -9,8 +9,11 @@ use smithay::{
         LockSurface, SessionLockHandler, SessionLockManagerState, SessionLocker,
     },
 };
-use std::collections::HashSet;
-use tracing::info;
+use std::{collections::HashSet, time::Duration};
+use tracing::{info, warn};
+
+// a lock confirmed later than this does not trigger a requested suspend
+const SUSPEND_LOCK_TIMEOUT: Duration = Duration::from_secs(10);
 
 impl SessionLockHandler for Monotile {
     fn lock_state(&mut self) -> &mut SessionLockManagerState {
@@ -31,6 +34,7 @@ impl SessionLockHandler for Monotile {
         if outputs.is_empty() {
             locker.lock();
             info!("session locked (no outputs)");
+            self.state.suspend_if_requested();
         } else {
             info!("session locking ({} outputs pending)", outputs.len());
             self.state.pending_lock = Some((locker, outputs));
@@ -40,6 +44,7 @@ impl SessionLockHandler for Monotile {
 
     fn unlock(&mut self) {
         self.state.locked = false;
+        self.state.suspend_after_lock = None;
         for mon in self.state.monitors.iter_mut() {
             mon.lock_surface = None;
         }
@@ -78,7 +83,22 @@ impl State {
                 let (locker, _) = self.pending_lock.take().unwrap();
                 locker.lock();
                 info!("session locked");
+                self.suspend_if_requested();
             }
         }
     }
+
+    // run the suspend LockAndSuspend is waiting on
+    pub fn suspend_if_requested(&mut self) {
+        let Some((cmd, since)) = self.suspend_after_lock.take() else {
+            return;
+        };
+        if since.elapsed() > SUSPEND_LOCK_TIMEOUT {
+            warn!("session locked too late, not suspending");
+            return;
+        }
+        if let Some((cmd, args)) = cmd.split_first() {
+            spawn(cmd, args, false);
+        }
+    }
 }
@@ -2,10 +2,11 @@
 
 use crate::{
     Monotile,
+    config::PopupOverflow,
     shell::{ToplevelSurfaceExt, Unmapped},
 };
 use smithay::{
-    delegate_kde_decoration, delegate_xdg_decoration, delegate_xdg_shell,
+    delegate_kde_decoration, delegate_xdg_decoration, delegate_xdg_dialog, delegate_xdg_shell,
     desktop::{
         PopupKeyboardGrab, PopupKind, PopupPointerGrab, WindowSurfaceType, find_popup_root_surface,
         get_popup_toplevel_coords, layer_map_for_output,
@@ -19,12 +20,12 @@ use smithay::{
             protocol::{wl_output, wl_seat, wl_surface::WlSurface},
         },
     },
-    utils::Serial,
+    utils::{Logical, Rectangle, Serial},
     wayland::shell::{
         kde::decoration::{KdeDecorationHandler, KdeDecorationState},
         xdg::{
             PopupSurface, PositionerState, ToplevelSurface, XdgShellHandler, XdgShellState,
-            decoration::XdgDecorationHandler,
+            decoration::XdgDecorationHandler, dialog::XdgDialogHandler,
         },
     },
 };
@@ -46,6 +47,7 @@ impl XdgShellHandler for Monotile {
             return;
         };
         self.recompute_layout(mon);
+        self.state.exit_if_all_closed();
     }
 
     fn new_popup(&mut self, surface: PopupSurface, _positioner: PositionerState) {
@@ -69,14 +71,24 @@ impl XdgShellHandler for Monotile {
     }
 
     fn parent_changed(&mut self, surface: ToplevelSurface) {
-        // for mapped windows that get a parent set late
+        // for mapped windows that get a parent set or unset late
         let wl = surface.wl_surface();
-        if let Some(id) = self.state.windows.find_by_surface(wl)
-            && surface.parent().is_some()
-        {
-            self.state.windows[id].set_floating(true);
-            self.recompute_layout(self.state.active_monitor);
+        let Some(id) = self.state.windows.find_by_surface(wl) else {
+            return;
+        };
+        let we = &mut self.state.windows[id];
+        match surface.parent() {
+            Some(_) if !we.floating => {
+                we.set_floating(true);
+                we.floated_by_parent = true;
+            }
+            // a detached dialog tiles again, unless floated by a rule or the user
+            None if we.floated_by_parent && self.state.config.layout.tile_unparented => {
+                we.set_floating(false);
+            }
+            _ => return,
         }
+        self.recompute_layout(self.state.active_monitor);
     }
 
     // ignored, compositor controls window geometry
@@ -179,6 +191,26 @@ impl KdeDecorationHandler for Monotile {
 
 delegate_kde_decoration!(Monotile);
 
+// dialogs mark themselves modal, blocking their parent
+impl XdgDialogHandler for Monotile {
+    fn modal_changed(&mut self, toplevel: ToplevelSurface, is_modal: bool) {
+        let wl = toplevel.wl_surface();
+        if let Some(unmapped) = self.state.unmapped.get_mut(&wl.id()) {
+            unmapped.modal = is_modal;
+            return;
+        }
+        let Some(id) = self.state.windows.find_by_surface(wl) else {
+            return;
+        };
+        self.state.windows[id].modal = is_modal;
+        // the parent is blocked or released
+        self.update_focus();
+        self.backend.schedule_render_all();
+    }
+}
+
+delegate_xdg_dialog!(Monotile);
+
 impl Monotile {
     fn set_server_side_decoration(toplevel: &ToplevelSurface, send_configure: bool) {
         toplevel.with_pending_state(|state| {
@@ -199,11 +231,13 @@ impl Monotile {
         // constraint rect depends on whether parent is a window or layer surface
         let popup_offset = get_popup_toplevel_coords(&kind);
         let parent_id = self.state.windows.find_by_surface(&root);
-        let mon = self.state.mon();
+        let Some(mon) = self.state.mon() else {
+            return;
+        };
 
-        let parent_loc = if let Some(id) = parent_id {
+        let (parent_loc, mut target) = if let Some(id) = parent_id {
             let rect = mon.window_rect(&self.state.windows, id).unwrap_or_default();
-            self.state.windows[id].surface_loc(rect.loc)
+            (self.state.windows[id].surface_loc(rect.loc), mon.geometry())
         } else {
             let map = layer_map_for_output(&mon.output);
             let Some(l) = map.layer_for_surface(&root, WindowSurfaceType::TOPLEVEL) else {
@@ -212,16 +246,38 @@ impl Monotile {
             let Some(geo) = map.layer_geometry(l) else {
                 return;
             };
-            geo.loc
+            // stay clear of other exclusive layers, the parent may cover its own zone
+            (geo.loc, map.non_exclusive_zone().merge(geo))
         };
 
         // convert output rect to popup-local coordinates
-        let mut target = mon.geometry();
         target.loc -= parent_loc;
         target.loc -= popup_offset;
 
+        let overflow = self.state.config.popup_overflow;
         popup.with_pending_state(|state| {
-            state.geometry = state.positioner.get_unconstrained_geometry(target);
+            let geo = state.positioner.get_unconstrained_geometry(target);
+            state.geometry = fit_popup(geo, target, overflow);
         });
     }
 }
+
+// what popup_overflow does with a popup still reaching out of target
+fn fit_popup(
+    mut geo: Rectangle<i32, Logical>,
+    target: Rectangle<i32, Logical>,
+    overflow: PopupOverflow,
+) -> Rectangle<i32, Logical> {
+    if overflow == PopupOverflow::Keep || target.contains_rect(geo) {
+        return geo;
+    }
+    if overflow == PopupOverflow::Shrink {
+        geo.size.w = geo.size.w.min(target.size.w);
+        geo.size.h = geo.size.h.min(target.size.h);
+    }
+    // the far edge first, so the near one wins for popups larger than target
+    let (right, bottom) = (target.loc.x + target.size.w, target.loc.y + target.size.h);
+    geo.loc.x = geo.loc.x.min(right - geo.size.w).max(target.loc.x);
+    geo.loc.y = geo.loc.y.min(bottom - geo.size.h).max(target.loc.y);
+    geo
+}
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// screensaver after a period without input: a configured command, or a fade
+// to black before the outputs turn off
+
+use std::time::{Duration, Instant};
+
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use tracing::{info, warn};
+
+use crate::{Monotile, spawn, state::State};
+
+impl State {
+    // opacity of the black over every output, 0 unless fading
+    pub fn idle_dim(&self) -> f32 {
+        let Some(since) = self.idle_since else {
+            return 0.0;
+        };
+        let idle = &self.config.idle;
+        if idle.command.is_some() {
+            return 0.0;
+        }
+        let fade = Duration::from_millis(idle.fade);
+        if fade.is_zero() {
+            return 1.0;
+        }
+        (since.elapsed().as_secs_f32() / fade.as_secs_f32()).min(1.0)
+    }
+
+    // the screensaver command and its arguments, None for the fade
+    pub fn idle_command(&self) -> Option<(&String, &[String])> {
+        self.config.idle.command.as_ref()?.split_first()
+    }
+}
+
+impl Monotile {
+    // (re)start waiting for the idle timeout, at startup and on config reload
+    pub fn arm_idle_timer(&mut self) {
+        if let Some(token) = self.state.idle_timer.take() {
+            self.state.loop_handle.remove(token);
+        }
+        let timeout = Duration::from_millis(self.state.config.idle.timeout);
+        if timeout.is_zero() {
+            return;
+        }
+        let timer = Timer::from_duration(timeout);
+        let token = self
+            .state
+            .loop_handle
+            .insert_source(timer, move |_, _, mt| {
+                let idle_for = mt.state.last_activity.elapsed();
+                if idle_for < timeout {
+                    return TimeoutAction::ToDuration(timeout - idle_for);
+                }
+                if mt.state.idle_since.is_none() && !mt.state.idle_inhibited {
+                    mt.start_idle();
+                }
+                TimeoutAction::ToDuration(timeout)
+            });
+        match token {
+            Ok(token) => self.state.idle_timer = Some(token),
+            Err(err) => warn!(?err, "failed to schedule idle timeout"),
+        }
+    }
+
+    pub fn start_idle(&mut self) {
+        info!("idle");
+        self.state.idle_since = Some(Instant::now());
+        if self.state.config.idle.command.is_some() {
+            if let Some((cmd, args)) = self.state.idle_command() {
+                spawn::spawn(cmd, args, false);
+            }
+            return;
+        }
+        // one repaint per frame of the fastest output
+        let timer = Timer::from_duration(self.state.frame_interval());
+        let token = self.state.loop_handle.insert_source(timer, |_, _, mt| {
+            // input cancelled the fade
+            if mt.state.idle_since.is_none() {
+                return TimeoutAction::Drop;
+            }
+            mt.backend.schedule_render_all();
+            if mt.state.idle_dim() < 1.0 {
+                return TimeoutAction::ToDuration(mt.state.frame_interval());
+            }
+            if mt.state.config.idle.power_off {
+                mt.backend.set_all_outputs_power(false);
+            }
+            TimeoutAction::Drop
+        });
+        if let Err(err) = token {
+            warn!(?err, "failed to schedule idle fade");
+        }
+    }
+
+    // input ends the screensaver and restarts the timeout
+    pub fn reset_idle(&mut self) {
+        self.state.last_activity = Instant::now();
+        if self.state.idle_since.take().is_some() {
+            self.backend.schedule_render_all();
+        }
+    }
+}
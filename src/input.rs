@@ -1,28 +1,42 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use tracing::info;
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
 
 use crate::{
     Monotile,
-    config::{Action, Config, Mods, Trigger},
+    backend::Backend,
+    config::{Action, AxisAction, Config, Corner, Mods, OsdTrigger, Rel, Trigger},
     grabs::{MoveSurfaceGrab, ResizeSurfaceGrab},
     handlers::Devices,
-    spawn::spawn,
+    shell::Relation,
+    spawn::{self, PendingSpawn, notify, spawn},
+    state::SurfaceUnder,
 };
 use smithay::{
     backend::input::{
         AbsolutePositionEvent, Axis, AxisSource, ButtonState, DeviceCapability, Event,
         GestureBeginEvent, GestureEndEvent, GesturePinchUpdateEvent as _, InputBackend, InputEvent,
         KeyState, KeyboardKeyEvent, PointerAxisEvent, PointerButtonEvent, PointerMotionEvent,
+        Switch, SwitchState, SwitchToggleEvent,
     },
     input::{
-        keyboard::{FilterResult, Keysym},
+        keyboard::{FilterResult, Keycode, Keysym},
         pointer::*,
     },
-    reexports::input::{Device, DragLockState},
-    utils::{Logical, Point, SERIAL_COUNTER},
+    reexports::{
+        calloop::timer::{TimeoutAction, Timer},
+        input::{Device, DragLockState},
+    },
+    utils::{Logical, Point, Rectangle, SERIAL_COUNTER},
 };
 
+// logical pixels the pointer may move between the presses of a double click
+const DOUBLE_CLICK_DISTANCE: f64 = 4.0;
+// how long windows get to close before ExitGracefully gives up
+const EXIT_TIMEOUT: Duration = Duration::from_secs(5);
+
 impl Monotile {
     fn wake_outputs<I: InputBackend>(&mut self, event: &InputEvent<I>) -> bool {
         if !self.backend.any_output_off() {
@@ -40,78 +54,57 @@ impl Monotile {
         wake
     }
 
+    #[cfg_attr(feature = "profiling", tracing::instrument(level = "trace", skip_all))]
     pub fn process_input_event<I: InputBackend>(&mut self, event: InputEvent<I>) {
         if self.wake_outputs(&event) {
             self.state.notify_activity();
+            self.reset_idle();
         }
 
-        let pointer = self.state.seat.get_pointer().unwrap();
-        let keyboard = self.state.seat.get_keyboard().unwrap();
         let serial = SERIAL_COUNTER.next_serial();
+        if let InputEvent::Keyboard { event, .. } = event {
+            self.handle_keyboard::<I>(event, serial);
+            return;
+        }
+        let hotplug = matches!(
+            event,
+            InputEvent::DeviceAdded { .. } | InputEvent::DeviceRemoved { .. }
+        );
+        if self.state.input_inhibited && !self.state.locked && !hotplug {
+            return;
+        }
+        if let InputEvent::SwitchToggle { event } = event {
+            if event.switch() == Some(Switch::Lid) {
+                let closed = event.state() == SwitchState::On;
+                info!("lid {}", if closed { "closed" } else { "opened" });
+                self.backend.set_lid_closed(closed, &mut self.state);
+                self.update_focus();
+            }
+            return;
+        }
 
-        match event {
-            InputEvent::Keyboard { event, .. } => {
-                let time = Event::time_msec(&event);
-                let key_code = event.key_code();
-                let key_state = event.state();
-
-                let action = keyboard.input(
-                    self,
-                    key_code,
-                    key_state,
-                    serial,
-                    time,
-                    |monotile, modifiers, handle| {
-                        if key_state != KeyState::Pressed {
-                            return FilterResult::Forward;
-                        }
-
-                        // VT switch
-                        let sym = handle.modified_sym();
-                        let vt_range =
-                            Keysym::XF86_Switch_VT_1.raw()..=Keysym::XF86_Switch_VT_12.raw();
-                        if vt_range.contains(&sym.raw()) {
-                            let vt = (sym.raw() - Keysym::XF86_Switch_VT_1.raw() + 1) as i32;
-                            return FilterResult::Intercept(Some(Action::ChangeVt(vt)));
-                        }
-
-                        // locked
-                        if monotile.state.locked {
-                            return FilterResult::Forward;
-                        }
-
-                        // exclusive layer
-                        if monotile.state.mon().exclusive_layer.is_some() {
-                            return FilterResult::Forward;
-                        }
-
-                        // key binds
-                        let mods = Mods::from(modifiers);
-                        for sym in handle.raw_syms() {
-                            if let Some(action) =
-                                monotile.state.config.binds.get(&(Trigger::Key(sym), mods))
-                            {
-                                return FilterResult::Intercept(Some(action.clone()));
-                            }
-                        }
-
-                        // forward to client
-                        FilterResult::Forward
-                    },
-                );
+        // pointer events only arrive from devices that added the capability
+        let Some(pointer) = self.state.seat.get_pointer() else {
+            return;
+        };
+        let keyboard = self.state.seat.get_keyboard().unwrap();
 
-                if let Some(Some(action)) = action {
-                    self.handle_action(action);
-                }
-            }
+        match event {
             InputEvent::PointerMotion { event, .. } => {
-                let geo = self.state.mon().geometry();
+                let Some(geo) = self.state.mon().map(|m| m.geometry()) else {
+                    return;
+                };
                 let pos = pointer.current_location() + event.delta();
-                let pos = pos.constrain(geo.to_f64());
+                let pos = match self.cross_monitor_edge(pos) {
+                    Some(pos) => pos,
+                    None => pos.constrain(geo.to_f64()),
+                };
                 self.handle_pointer_motion(pos, event.time_msec(), serial);
             }
             InputEvent::PointerMotionAbsolute { event, .. } => {
-                let geo = self.state.mon().geometry();
+                let Some(geo) = self.state.mon().map(|m| m.geometry()) else {
+                    return;
+                };
                 let pos = event.position_transformed(geo.size) + geo.loc.to_f64();
                 self.handle_pointer_motion(pos, event.time_msec(), serial);
             }
@@ -122,26 +115,55 @@ impl Monotile {
                 if button_state == ButtonState::Pressed
                     && !pointer.is_grabbed()
                     && !self.state.locked
-                    && self.state.mon().exclusive_layer.is_none()
+                    && self
+                        .state
+                        .mon()
+                        .is_some_and(|m| m.exclusive_layer.is_none())
                 {
                     let mods = Mods::from(&keyboard.modifier_state());
+                    let pos = pointer.current_location();
+                    if self.is_double_click(button, pos)
+                        && let Some(action) = self
+                            .state
+                            .config
+                            .binds
+                            .get(&(Trigger::DoubleClick(button), mods))
+                    {
+                        // acts on the window clicked, not whichever had focus
+                        if let Some(id) = self.state.surface_under(pos).window {
+                            self.set_focus(Some(id));
+                        }
+                        self.handle_action(action.clone());
+                        return;
+                    }
                     if let Some(action) =
                         self.state.config.binds.get(&(Trigger::Mouse(button), mods))
                     {
-                        self.handle_mouse_action(
-                            action.clone(),
-                            button,
-                            pointer.current_location(),
-                            serial,
-                        );
+                        self.handle_mouse_action(action.clone(), button, pos, serial);
                         return;
                     }
 
-                    // raise window and focus
-                    let id = self.state.surface_under(pointer.current_location()).window;
-                    if let Some(id) = id {
-                        self.state.mon_mut().tag_mut().raise(id);
+                    // on-demand layers, e.g. a bar's text field, take the keyboard when clicked
+                    let under = self.state.surface_under(pos);
+                    let layer = match (&under.surface, under.window) {
+                        (Some((s, _)), None) => self.state.mon().and_then(|m| m.on_demand_layer(s)),
+                        _ => None,
+                    };
+                    let refocus = layer.is_some() || self.state.layer_focus.is_some();
+                    self.state.layer_focus = layer;
+
+                    // raise window and focus, a window behind a modal dialog raises the dialog
+                    let id = under.window.map(|id| self.state.modal_target(id));
+                    let raise = self.state.config.seats["seat0"].raise_on_click;
+                    if let Some(id) = id
+                        && let Some(mon) = self.state.mon_mut()
+                    {
+                        if raise {
+                            mon.tag_mut().raise(id);
+                        }
                         self.set_focus(Some(id));
+                    } else if refocus {
+                        self.update_focus();
                     }
                 }
 
@@ -159,15 +181,12 @@ impl Monotile {
             InputEvent::PointerAxis { event, .. } => {
                 let source = event.source();
 
-                let horizontal_amount = event.amount(Axis::Horizontal).unwrap_or_else(|| {
-                    event.amount_v120(Axis::Horizontal).unwrap_or(0.0) * 15.0 / 120.
-                });
-                let vertical_amount = event.amount(Axis::Vertical).unwrap_or_else(|| {
-                    event.amount_v120(Axis::Vertical).unwrap_or(0.0) * 15.0 / 120.
-                });
-
-                let horizontal_amount_discrete = event.amount_v120(Axis::Horizontal);
-                let vertical_amount_discrete = event.amount_v120(Axis::Vertical);
+                let mouse = &self.state.config.seats["seat0"].mouse;
+                // libinput devices scroll naturally on their own, see configure_device
+                let flip = matches!(self.backend, Backend::Winit(_));
+                let scroll = |axis| mouse.scroll(event.amount(axis), event.amount_v120(axis), flip);
+                let (horizontal_amount, horizontal_amount_discrete) = scroll(Axis::Horizontal);
+                let (vertical_amount, vertical_amount_discrete) = scroll(Axis::Vertical);
 
                 let mut frame = AxisFrame::new(event.time_msec()).source(source);
                 if horizontal_amount != 0.0 {
@@ -192,9 +211,15 @@ impl Monotile {
                     }
                 }
 
+                // consumed, never forwarded to the surface below
+                if self.scroll_root(pointer.current_location(), vertical_amount) {
+                    return;
+                }
                 pointer.axis(self, frame);
                 pointer.frame(self);
-                self.backend.schedule_render(&self.state.mon().output);
+                if let Some(mon) = self.state.mon() {
+                    self.backend.schedule_render(&mon.output);
+                }
             }
             InputEvent::GesturePinchBegin { event, .. } => {
                 pointer.gesture_pinch_begin(
@@ -254,24 +279,436 @@ impl Monotile {
         }
     }
 
+    fn handle_keyboard<I: InputBackend>(
+        &mut self,
+        event: I::KeyboardKeyEvent,
+        serial: smithay::utils::Serial,
+    ) {
+        let keyboard = self.state.seat.get_keyboard().unwrap();
+        let time = Event::time_msec(&event);
+        let key_code = event.key_code();
+        let key_state = event.state();
+
+        // Some(pick) once a key ended hint mode
+        let mut hint_pick = None;
+        // Some(commit) once the window switcher closes
+        let mut switch_end = None;
+        let action = keyboard.input(
+            self,
+            key_code,
+            key_state,
+            serial,
+            time,
+            |monotile, modifiers, handle| {
+                // inhibited: only the bind turning it off gets through
+                if monotile.state.input_inhibited && !monotile.state.locked {
+                    if key_state == KeyState::Released && monotile.inhibit_releases(key_code) {
+                        return FilterResult::Forward;
+                    }
+                    let mods = Mods::from(modifiers);
+                    let binds = &monotile.state.config.binds;
+                    let toggle = handle.raw_syms().iter().any(|&sym| {
+                        let action = binds.get(&(Trigger::Key(sym), mods));
+                        matches!(action, Some(Action::ToggleInputInhibit))
+                    });
+                    if toggle && key_state == KeyState::Pressed {
+                        return FilterResult::Intercept(Some(Action::ToggleInputInhibit));
+                    }
+                    return FilterResult::Intercept(None);
+                }
+
+                if key_state != KeyState::Pressed {
+                    if let Some(back) = monotile.end_peek(key_code) {
+                        return FilterResult::Intercept(Some(back));
+                    }
+                    // the switcher stays while any modifier of its bind is held
+                    if monotile.state.switcher.is_some()
+                        && !(modifiers.alt || modifiers.ctrl || modifiers.logo)
+                    {
+                        switch_end = Some(true);
+                    }
+                    return FilterResult::Forward;
+                }
+
+                // VT switch
+                let sym = handle.modified_sym();
+                let vt_range = Keysym::XF86_Switch_VT_1.raw()..=Keysym::XF86_Switch_VT_12.raw();
+                if vt_range.contains(&sym.raw()) {
+                    let vt = (sym.raw() - Keysym::XF86_Switch_VT_1.raw() + 1) as i32;
+                    return FilterResult::Intercept(Some(Action::ChangeVt(vt)));
+                }
+
+                // locked
+                if monotile.state.locked {
+                    return FilterResult::Forward;
+                }
+
+                // exclusive layer
+                if monotile
+                    .state
+                    .mon()
+                    .is_some_and(|m| m.exclusive_layer.is_some())
+                {
+                    return FilterResult::Forward;
+                }
+
+                if monotile.state.switcher.is_some() && handle.raw_syms().contains(&Keysym::Escape)
+                {
+                    switch_end = Some(false);
+                    return FilterResult::Intercept(None);
+                }
+
+                // hints: one key picks a window, any other key cancels
+                if let Some(mon) = monotile.state.mon_mut()
+                    && !mon.hints.is_empty()
+                {
+                    if handle.raw_syms().iter().any(|s| s.is_modifier_key()) {
+                        return FilterResult::Forward;
+                    }
+                    let key = handle.modified_sym().key_char();
+                    let hints = std::mem::take(&mut mon.hints);
+                    hint_pick = Some(hints.into_iter().find(|h| Some(h.key) == key));
+                    return FilterResult::Intercept(None);
+                }
+
+                let mods = Mods::from(modifiers);
+                if !monotile.state.submaps.is_empty() {
+                    if handle.raw_syms().iter().any(|s| s.is_modifier_key()) {
+                        return FilterResult::Forward;
+                    }
+                    return FilterResult::Intercept(monotile.submap_key(handle.raw_syms(), mods));
+                }
+
+                // key binds
+                for sym in handle.raw_syms() {
+                    if let Some(action) =
+                        monotile.state.config.binds.get(&(Trigger::Key(sym), mods))
+                    {
+                        return FilterResult::Intercept(Some(action.clone()));
+                    }
+                }
+
+                // forward to client
+                FilterResult::Forward
+            },
+        );
+
+        if let Some(commit) = switch_end {
+            self.finish_switch(commit);
+        }
+
+        if let Some(pick) = hint_pick {
+            if let Some(hint) = pick
+                && self.state.windows.get(hint.window).is_some()
+                && let Some(mon) = self.state.mon_mut()
+            {
+                mon.tag_mut().raise(hint.window);
+                self.set_focus(Some(hint.window));
+            }
+            self.recompute_layout(self.state.active_monitor);
+        }
+
+        if let Some(Some(action)) = action {
+            if matches!(action, Action::PeekPrevTag) {
+                self.begin_peek(key_code);
+            }
+            self.handle_action(action);
+        }
+    }
+
+    // make the monitor the pointer moved onto active, returns the position local to it
+    pub fn cross_monitor_edge(&mut self, pos: Point<f64, Logical>) -> Option<Point<f64, Logical>> {
+        let mon = self.state.mon()?;
+        if self.state.pointer_confined || mon.geometry().to_f64().contains(pos) {
+            return None;
+        }
+        let global = pos + mon.output.current_location().to_f64();
+        let (idx, global) = self
+            .state
+            .monitors
+            .across(self.state.active_monitor, global)?;
+        let local = global - self.state.monitors[idx].output.current_location().to_f64();
+        self.focus_monitor(idx);
+        Some(local)
+    }
+
+    // root_scroll, true if the scroll was over the root and used for it
+    pub fn scroll_root(&mut self, pos: Point<f64, Logical>, amount: f64) -> bool {
+        let seat = &self.state.config.seats["seat0"];
+        let Some(AxisAction::FocusStack) = seat.root_scroll else {
+            return false;
+        };
+        let step = seat.mouse.scroll_step;
+        if amount == 0.0 || !self.state.over_root(pos) {
+            self.state.root_scroll = 0.0;
+            return false;
+        }
+        // one focus step per wheel notch, touchpads scroll the same distance
+        self.state.root_scroll += amount;
+        while self.state.root_scroll.abs() >= step {
+            let rel = if self.state.root_scroll > 0.0 { Rel::Next } else { Rel::Prev };
+            self.state.root_scroll -= step.copysign(self.state.root_scroll);
+            self.handle_action(Action::Focus(rel));
+        }
+        true
+    }
+
+    // KillTag and KillOthers, closing only on a second press within
+    // bulk_close.confirm
+    fn bulk_close(&mut self, others: bool) {
+        let focused = self.state.focused_id();
+        if others && focused.is_none() {
+            return;
+        }
+        let keep = focused.filter(|_| others);
+        let Some(mon) = self.state.mon() else {
+            return;
+        };
+        let count = mon.close_targets(&self.state.windows, keep).len();
+        if count == 0 {
+            return;
+        }
+        let confirm = Duration::from_millis(self.state.config.bulk_close.confirm);
+        let pending = self.state.pending_bulk_close.take();
+        let confirmed = confirm.is_zero()
+            || pending.is_some_and(|(o, at)| o == others && at.elapsed() < confirm);
+        if !confirmed {
+            self.state.pending_bulk_close = Some((others, Instant::now()));
+            self.show_osd(format!("press again to close {count} windows"));
+            return;
+        }
+        if let Some(mon) = self.state.mon() {
+            mon.close_windows(&self.state.windows, keep);
+        }
+    }
+
+    // windows that do not close in time, e.g. asking to save, cancel the exit
+    fn exit_gracefully(&mut self) {
+        if self.state.exit_timer.is_some() {
+            return;
+        }
+        // saved while the windows are still there
+        self.state.save_session();
+        for we in self.state.windows.live() {
+            if let Some(tl) = we.window.toplevel() {
+                tl.send_close();
+            }
+        }
+        let timer = Timer::from_duration(EXIT_TIMEOUT);
+        let token = self.state.loop_handle.insert_source(timer, |_, _, mt| {
+            mt.state.exit_timer = None;
+            let open = mt.state.windows.live().count();
+            info!("exit cancelled, {open} windows still open");
+            notify(
+                "normal",
+                "exit",
+                &format!("cancelled, {open} windows still open"),
+            );
+            TimeoutAction::Drop
+        });
+        match token {
+            Ok(token) => self.state.exit_timer = Some(token),
+            Err(err) => tracing::warn!(?err, "failed to schedule exit timeout"),
+        }
+        self.state.exit_if_all_closed();
+    }
+
+    fn toggle_input_inhibit(&mut self) {
+        let inhibit = !self.state.input_inhibited;
+        if inhibit && !self.state.config.binds.has_inhibit_toggle() {
+            warn!("ToggleInputInhibit needs a key bind to turn input back on");
+            return;
+        }
+        self.state.input_inhibited = inhibit;
+        self.state.inhibit_held = match self.state.seat.get_keyboard() {
+            Some(keyboard) if inhibit => keyboard.pressed_keys().into_iter().collect(),
+            _ => Vec::new(),
+        };
+        info!("input {}", if inhibit { "inhibited" } else { "restored" });
+        // the banner comes and goes with the flag
+        self.backend.schedule_render_all();
+    }
+
+    fn focus_related(&mut self, rel: Relation) {
+        let Some(mon) = self.state.monitors.get_mut(self.state.active_monitor) else {
+            return;
+        };
+        let Some(cur) = mon.tag().focused_id() else {
+            return;
+        };
+        if mon.focus_related(&self.state.windows, cur, rel).is_some() {
+            let output = mon.output.clone();
+            self.update_focus();
+            self.backend.schedule_render(&output);
+        }
+    }
+
+    fn warp_pointer_to_center(&mut self) {
+        let Some(geo) = self.state.mon().map(|m| m.geometry().to_f64()) else {
+            return;
+        };
+        let center = geo.loc + geo.size.downscale(2.0).to_point();
+        let time = self.state.start_time.elapsed().as_millis() as u32;
+        self.handle_pointer_motion(center, time, SERIAL_COUNTER.next_serial());
+    }
+
+    // the cursor_park corner of the active output, keeping focus where it is
+    pub fn park_cursor(&mut self) {
+        let Some(geo) = self.state.mon().map(|m| m.geometry().to_f64()) else {
+            return;
+        };
+        // last pixel inside the output
+        let (right, bottom) = (geo.loc.x + geo.size.w - 1.0, geo.loc.y + geo.size.h - 1.0);
+        let pos = match self.state.config.seats["seat0"].cursor_park {
+            Corner::TopLeft => geo.loc,
+            Corner::TopRight => (right, geo.loc.y).into(),
+            Corner::BottomLeft => (geo.loc.x, bottom).into(),
+            Corner::BottomRight => (right, bottom).into(),
+        };
+        let under = self.state.surface_under(pos);
+        let time = self.state.start_time.elapsed().as_millis() as u32;
+        self.move_pointer(pos, under, time, SERIAL_COUNTER.next_serial());
+        self.state.cursor_parked = true;
+    }
+
+    // (re)start parking the pointer after cursor_park_timeout without motion
+    pub fn arm_park_timer(&mut self) {
+        if let Some(token) = self.state.park_timer.take() {
+            self.state.loop_handle.remove(token);
+        }
+        let ms = self.state.config.seats["seat0"].cursor_park_timeout;
+        let timeout = Duration::from_millis(ms);
+        if timeout.is_zero() {
+            return;
+        }
+        let timer = Timer::from_duration(timeout);
+        let token = self
+            .state
+            .loop_handle
+            .insert_source(timer, move |_, _, mt| {
+                let still = mt.state.last_pointer_motion.elapsed();
+                if still < timeout {
+                    return TimeoutAction::ToDuration(timeout - still);
+                }
+                let grabbed = mt.state.seat.get_pointer().is_some_and(|p| p.is_grabbed());
+                if !mt.state.cursor_parked && !grabbed {
+                    mt.park_cursor();
+                }
+                TimeoutAction::ToDuration(timeout)
+            });
+        match token {
+            Ok(token) => self.state.park_timer = Some(token),
+            Err(err) => warn!(?err, "failed to schedule cursor parking"),
+        }
+    }
+
+    // the tag to go back to once the key of a PeekPrevTag bind is released
+    pub fn begin_peek(&mut self, key: Keycode) {
+        if let Some(mon) = self.state.mon() {
+            self.state.peek = Some((key, mon.active_tag));
+        }
+    }
+
+    // a key held since before input was inhibited is let go once, so it
+    // doesn't stay pressed for the client
+    pub fn inhibit_releases(&mut self, key: Keycode) -> bool {
+        let held = &mut self.state.inhibit_held;
+        let Some(pos) = held.iter().position(|&k| k == key) else {
+            return false;
+        };
+        held.swap_remove(pos);
+        true
+    }
+
+    // releasing the peek key returns to the tag peeked from
+    pub fn end_peek(&mut self, key: Keycode) -> Option<Action> {
+        let (_, tag) = self.state.peek.filter(|&(held, _)| held == key)?;
+        self.state.peek = None;
+        Some(Action::FocusTag(tag))
+    }
+
+    // a key pressed in the innermost submap: one bound key and it is back to
+    // the main binds, unless it enters a nested submap. Escape goes back one
+    // level, any other key leaves them all
+    pub fn submap_key(&mut self, syms: &[Keysym], mods: Mods) -> Option<Action> {
+        let name = self.state.submaps.last()?;
+        let binds = self.state.config.submaps.get(name);
+        let action = syms
+            .iter()
+            .find_map(|&sym| binds?.get(&(Trigger::Key(sym), mods)))
+            .cloned();
+        match &action {
+            Some(Action::EnterSubmap(_)) => {}
+            None if syms.contains(&Keysym::Escape) => {
+                self.state.submaps.pop();
+            }
+            _ => self.state.submaps.clear(),
+        }
+        self.arm_submap_timer();
+        action
+    }
+
+    // restarts submap_timeout for the innermost submap
+    pub fn arm_submap_timer(&mut self) {
+        if let Some(token) = self.state.submap_timer.take() {
+            self.state.loop_handle.remove(token);
+        }
+        let ms = self.state.config.seats["seat0"].submap_timeout;
+        if self.state.submaps.is_empty() || ms == 0 {
+            return;
+        }
+        let timer = Timer::from_duration(Duration::from_millis(ms));
+        let token = self.state.loop_handle.insert_source(timer, |_, _, mt| {
+            mt.submap_timed_out();
+            TimeoutAction::Drop
+        });
+        match token {
+            Ok(token) => self.state.submap_timer = Some(token),
+            Err(err) => warn!(?err, "failed to schedule the submap timeout"),
+        }
+    }
+
+    // submap_timeout passed without a key, back to the main binds
+    pub fn submap_timed_out(&mut self) {
+        self.state.submap_timer = None;
+        self.state.submaps.clear();
+    }
+
     fn handle_pointer_motion(
         &mut self,
         pos: Point<f64, Logical>,
         time: u32,
         serial: smithay::utils::Serial,
     ) {
-        let pointer = self.state.seat.get_pointer().unwrap();
+        let Some(pointer) = self.state.seat.get_pointer() else {
+            return;
+        };
 
         let under = self.state.surface_under(pos);
+        self.state.last_pointer_motion = Instant::now();
+        self.state.cursor_parked = false;
 
         if !pointer.is_grabbed()
             && self.state.config.seats["seat0"].focus_follows_cursor
             && under.window.is_some()
-            && under.window != self.state.mon().tag().focused_id()
+            && under.window != self.state.focused_id()
         {
             self.set_focus(under.window);
         }
+        self.move_pointer(pos, under, time, serial);
+    }
 
+    // motion without focus following, surfaces still get enter and leave
+    fn move_pointer(
+        &mut self,
+        pos: Point<f64, Logical>,
+        under: SurfaceUnder,
+        time: u32,
+        serial: smithay::utils::Serial,
+    ) {
+        let Some(pointer) = self.state.seat.get_pointer() else {
+            return;
+        };
         pointer.motion(
             self,
             under.surface,
@@ -284,7 +721,9 @@ impl Monotile {
         pointer.frame(self);
 
         // TODO: get cursor from seat when multi-seat is implemented
-        let output = &self.state.monitors[under.monitor].output;
+        let Some(output) = self.state.monitors.get(under.monitor).map(|m| &m.output) else {
+            return;
+        };
         if !self.state.locked {
             let hotspot = self.state.cursor.hotspot;
             self.state
@@ -297,90 +736,405 @@ impl Monotile {
     pub fn handle_action(&mut self, action: Action) {
         use Action::*;
 
+        let osd = OsdTrigger::of(&action).filter(|t| self.state.config.osd.show_on.contains(t));
         match action {
             Noop => return,
             Exit => {
+                self.state.save_session();
                 self.state.loop_signal.stop();
                 return;
             }
+            ExitGracefully => {
+                self.exit_gracefully();
+                return;
+            }
+            ToggleInputInhibit => {
+                self.toggle_input_inhibit();
+                return;
+            }
             Spawn(ref args) => {
                 if let Some((cmd, args)) = args.split_first() {
                     spawn(cmd, args, false);
                 }
                 return;
             }
+            LockAndSuspend {
+                ref locker,
+                ref suspend,
+            } => {
+                let locking = self.state.pending_lock.is_some();
+                self.state.suspend_after_lock = Some((suspend.clone(), Instant::now()));
+                if self.state.locked && !locking {
+                    self.state.suspend_if_requested();
+                } else if !locking
+                    && locker
+                        .split_first()
+                        .and_then(|(cmd, args)| spawn(cmd, args, false))
+                        .is_none()
+                {
+                    // without a lock the system must not suspend
+                    self.state.suspend_after_lock = None;
+                }
+                return;
+            }
+            SpawnAndTag(ref args) => {
+                let token = spawn::spawn_token();
+                let env = [(spawn::SPAWN_TOKEN_ENV, token.as_str())];
+                if let Some((cmd, args)) = args.split_first()
+                    && let Some(mon) = self.state.mon()
+                    && let Some(pid) = spawn::spawn_env(cmd, args, false, &env)
+                {
+                    let pending = PendingSpawn {
+                        token,
+                        pid,
+                        monitor: self.state.active_monitor,
+                        tag: mon.active_tag,
+                        since: Instant::now(),
+                    };
+                    self.state.pending_spawns.push(pending);
+                }
+                return;
+            }
             Focus(pos) => {
-                let tag = self.state.mon().tag();
+                let Some(mon) = self.state.mon() else {
+                    return;
+                };
+                let output = mon.output.clone();
+                let tag = mon.tag();
                 if let Some(cur) = tag.focused_id()
                     && let Some(id) = tag.layout.target(cur, pos)
                 {
                     self.set_focus(Some(id));
                 }
-                self.backend.schedule_render(&self.state.mon().output);
+                self.backend.schedule_render(&output);
+                return;
+            }
+            CycleFloating(delta) => {
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                let output = mon.output.clone();
+                if let Some(id) = mon.tag_mut().cycle_floating(delta) {
+                    self.set_focus(Some(id));
+                }
+                self.backend.schedule_render(&output);
                 return;
             }
             Swap(pos) => {
-                if let Some(cur) = self.state.mon().tag().focused_id() {
-                    self.state.mon_mut().tag_mut().layout.swap(cur, pos);
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                if let Some(cur) = mon.tag().focused_id() {
+                    mon.tag_mut().layout.swap(cur, pos);
+                }
+            }
+            Zoom => {
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                if let Some(cur) = mon.tag().focused_id() {
+                    mon.tag_mut().layout.zoom(cur);
+                }
+            }
+            SwitchWindow(delta) => {
+                self.switch_window(delta);
+                return;
+            }
+            ZoomAndExpand(ratio) => {
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                let focused = mon.tag().focused_id();
+                let layout = &mut mon.tag_mut().layout;
+                // unlike Zoom, the main window stays where it is
+                if let Some(cur) = focused
+                    && layout.ids().next() != Some(cur)
+                {
+                    layout.zoom(cur);
                 }
+                layout.set_main_factor(ratio);
             }
             Close => {
-                if let Some(id) = self.state.mon().tag().focused_id()
+                if let Some(id) = self.state.focused_id()
                     && let Some(tl) = self.state.windows[id].window.toplevel()
                 {
                     tl.send_close();
                 }
                 return;
             }
+            KillTag => {
+                self.bulk_close(false);
+                return;
+            }
+            KillOthers => {
+                self.bulk_close(true);
+                return;
+            }
             ToggleFloat => {
-                if let Some(id) = self.state.mon().tag().focused_id() {
-                    let floating = !self.state.windows[id].floating;
-                    self.state.windows[id].set_floating(floating);
-                }
+                let Some(id) = self.state.focused_id() else {
+                    return;
+                };
+                let we = &mut self.state.windows[id];
+                let floating = !we.floating;
+                we.set_floating(floating);
+                we.floated_by_user = floating;
+            }
+            ToggleSizeLock => {
+                let Some(id) = self.state.focused_id() else {
+                    return;
+                };
+                let we = &mut self.state.windows[id];
+                we.size_locked = !we.size_locked;
             }
             ToggleFullscreen => {
-                if let Some(id) = self.state.mon().tag().focused_id() {
-                    let on = !self.state.windows[id].fullscreen;
-                    self.state.windows[id].set_fullscreen(on);
-                }
+                let Some(id) = self.state.focused_id() else {
+                    return;
+                };
+                // a fake fullscreen window goes real fullscreen
+                let we = &mut self.state.windows[id];
+                let on = !we.fullscreen || we.fake_fullscreen;
+                we.set_fullscreen(on);
+            }
+            ToggleFakeFullscreen => {
+                let Some(id) = self.state.focused_id() else {
+                    return;
+                };
+                let we = &mut self.state.windows[id];
+                let on = !we.fake_fullscreen;
+                we.set_fake_fullscreen(on);
+            }
+            FocusParent => {
+                self.focus_related(Relation::Parent);
+                return;
+            }
+            FocusChild => {
+                self.focus_related(Relation::Child);
+                return;
             }
             FocusTag(tag) => {
-                self.state.mon_mut().set_active_tag(tag);
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                mon.set_active_tag(tag);
+            }
+            FocusTagGlobal(tag) => {
+                self.state.set_active_tag_global(tag);
+                // the active monitor is recomputed below
+                for idx in 0..self.state.monitors.len() {
+                    if idx != self.state.active_monitor {
+                        self.recompute_layout(idx);
+                    }
+                }
             }
-            FocusPrevTag => {
-                self.state.mon_mut().toggle_prev_tag();
+            FocusPrevTag | PeekPrevTag => {
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                mon.toggle_prev_tag();
             }
             SetTag(tag) => {
-                let mon = &mut self.state.monitors[self.state.active_monitor];
+                let Some(mon) = self.state.monitors.get_mut(self.state.active_monitor) else {
+                    return;
+                };
                 mon.move_to_tag(&mut self.state.windows, tag);
             }
             ToggleTag(tag) => {
-                self.state.mon_mut().toggle_tag(tag);
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                mon.toggle_tag(tag);
             }
             AdjustMainCount(delta) => {
-                self.state
-                    .mon_mut()
-                    .tag_mut()
-                    .layout
-                    .adjust_main_count(delta);
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                mon.tag_mut().layout.adjust_main_count(delta);
             }
             SetMainCount(count) => {
-                self.state.mon_mut().tag_mut().layout.set_main_count(count);
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                mon.tag_mut().layout.set_main_count(count);
             }
             AdjustMainRatio(delta) => {
-                self.state
-                    .mon_mut()
-                    .tag_mut()
-                    .layout
-                    .adjust_main_factor(delta);
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                mon.tag_mut().layout.adjust_main_factor(delta);
+            }
+            ToggleMirror => {
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                mon.toggle_mirror();
             }
             SetMainRatio(ratio) => {
-                self.state.mon_mut().tag_mut().layout.set_main_factor(ratio);
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                mon.tag_mut().layout.set_main_factor(ratio);
+            }
+            ResizeTile(dir, delta) => {
+                let Some(id) = self.state.focused_id() else {
+                    return;
+                };
+                if self.state.windows[id].floating {
+                    return;
+                }
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                mon.tag_mut().layout.resize_tile(id, dir, delta);
+            }
+            BalanceLayout => {
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                mon.tag_mut().layout.balance();
+            }
+            ResetAllLayouts => {
+                for mon in self.state.monitors.iter_mut() {
+                    mon.reset_layouts();
+                }
+                // the active monitor is recomputed below
+                for idx in 0..self.state.monitors.len() {
+                    if idx != self.state.active_monitor {
+                        self.recompute_layout(idx);
+                    }
+                }
+            }
+            ShowOsd(text) => {
+                self.show_osd(text);
+                return;
+            }
+            ParkCursor => {
+                self.park_cursor();
+                return;
+            }
+            ToggleRenderStats => {
+                self.toggle_render_stats();
+                return;
+            }
+            FocusOutput(dir) => {
+                let Some(to) = self
+                    .state
+                    .monitors
+                    .in_direction(self.state.active_monitor, dir)
+                else {
+                    return;
+                };
+                self.focus_monitor(to);
+                if self.state.config.seats["seat0"].cursor_warp {
+                    self.warp_pointer_to_center();
+                }
+            }
+            RescueWindows => {
+                let Some(mon) = self.state.monitors.get(self.state.active_monitor) else {
+                    return;
+                };
+                mon.rescue_offscreen(&mut self.state.windows);
+            }
+            TogglePointerConfinement => {
+                self.state.pointer_confined = !self.state.pointer_confined;
+                return;
+            }
+            DuplicateOutput(dir) => {
+                let from = self.state.active_monitor;
+                let Some(to) = self.state.monitors.output_in_direction(from, dir) else {
+                    return;
+                };
+                let name = self.state.monitors[from].output.name();
+                let target = &mut self.state.monitors[to];
+                let on = target.duplicate.as_deref() != Some(&name);
+                info!("duplicate {name} on {}: {on}", target.output.name());
+                target.duplicate = on.then_some(name);
+                self.backend.schedule_render_all();
+                return;
+            }
+            SendToOutput(dir) => {
+                let from = self.state.active_monitor;
+                if self.state.move_active_to_monitor_dir(dir).is_some() {
+                    self.recompute_layout(from);
+                }
+            }
+            WindowHints => {
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                mon.show_hints();
+            }
+            SetLayout(mode) => {
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                mon.tag_mut().layout.mode = mode;
+            }
+            CycleLayout => {
+                let Some(mon) = self.state.mon_mut() else {
+                    return;
+                };
+                let layout = &mut mon.tag_mut().layout;
+                layout.mode = layout.mode.next();
             }
             ReloadConfig => {
                 self.reload_config();
                 return;
             }
+            ReloadRenderer => {
+                let shader_dir = self.state.config.shaders.as_deref();
+                self.backend
+                    .reload_renderer(&mut self.state.windows, shader_dir);
+                return;
+            }
+            EnterSubmap(name) => {
+                if self.state.config.submaps.contains_key(&name) {
+                    self.state.submaps.push(name);
+                    self.arm_submap_timer();
+                } else {
+                    warn!("unknown submap: {name}");
+                }
+                return;
+            }
+            DumpState(ref path) => {
+                #[cfg(feature = "dump")]
+                self.state.dump_to(path.as_deref());
+                #[cfg(not(feature = "dump"))]
+                warn!("DumpState({path:?}) requires the 'dump' feature");
+                return;
+            }
+            Screenshot {
+                name,
+                region,
+                cursor,
+                window,
+            } => {
+                let dir = self.state.config.screenshot_dir();
+                let Some(path) = crate::render::screenshot_path(&dir, name.as_deref()) else {
+                    warn!("screenshot: {name:?} is not a plain file name");
+                    return;
+                };
+                let Some(mon) = self.state.mon() else {
+                    return;
+                };
+                let focused = self.state.focused_id();
+                if window && focused.is_none() {
+                    warn!("screenshot: no focused window");
+                    return;
+                }
+                let window = focused.filter(|_| window);
+                let output = mon.output.clone();
+                let region =
+                    region.map(|(x, y, w, h)| Rectangle::new((x, y).into(), (w, h).into()));
+                self.state.screenshots.push(crate::render::Screenshot {
+                    output: output.downgrade(),
+                    path,
+                    region,
+                    cursor,
+                    window,
+                });
+                self.backend.schedule_render(&output);
+                return;
+            }
             ChangeVt(vt) => {
                 self.backend.change_vt(vt);
                 return; // no recompute needed
@@ -393,12 +1147,27 @@ impl Monotile {
                 self.backend.set_all_outputs_power(true);
                 return;
             }
-            // TODO: implement multi-monitor
-            FocusOutput(_) | SendToOutput(_) => return,
             // mouse-only actions - no-op for keyboard
             Move | Resize => return,
         }
         self.recompute_layout(self.state.active_monitor);
+        if let Some(trigger) = osd {
+            self.show_trigger_osd(trigger);
+        }
+    }
+
+    // records the press, true if it completes a double click
+    pub fn is_double_click(&mut self, button: u32, pos: Point<f64, Logical>) -> bool {
+        let ms = self.state.config.seats["seat0"].double_click_interval;
+        let double = self.state.last_click.is_some_and(|(b, at, p)| {
+            let (dx, dy) = (pos.x - p.x, pos.y - p.y);
+            b == button
+                && at.elapsed() < Duration::from_millis(ms)
+                && dx * dx + dy * dy <= DOUBLE_CLICK_DISTANCE * DOUBLE_CLICK_DISTANCE
+        });
+        // a third press starts over
+        self.state.last_click = (!double).then(|| (button, Instant::now(), pos));
+        double
     }
 
     fn handle_mouse_action(
@@ -423,7 +1192,9 @@ impl Monotile {
             return;
         }
         let geo = self.state.windows[id].float_geo;
-        let ptr = self.state.seat.get_pointer().unwrap();
+        let Some(ptr) = self.state.seat.get_pointer() else {
+            return;
+        };
         match action {
             Action::Move => {
                 self.state.cursor.override_icon = Some(CursorIcon::AllScroll);
@@ -453,6 +1224,16 @@ impl Monotile {
 
     pub fn device_added(&mut self, dev: &mut Device) {
         configure_device(dev, &self.state.config);
+        if dev.has_capability(DeviceCapability::Pointer.into())
+            && self.state.seat.get_pointer().is_none()
+        {
+            self.state.seat.add_pointer();
+        }
+        if dev.has_capability(DeviceCapability::Touch.into())
+            && self.state.seat.get_touch().is_none()
+        {
+            self.state.seat.add_touch();
+        }
         if dev.has_capability(DeviceCapability::Keyboard.into())
             && let Some(kb) = self.state.seat.get_keyboard()
         {
@@ -465,6 +1246,23 @@ impl Monotile {
     pub fn device_removed(&mut self, dev: &Device) {
         let devices = self.state.seat.user_data().get::<Devices>().unwrap();
         devices.0.borrow_mut().retain(|d| d != dev);
+
+        // the keyboard stays, bindings must keep working without a device
+        let has = |cap: DeviceCapability| {
+            devices
+                .0
+                .borrow()
+                .iter()
+                .any(|d| d.has_capability(cap.into()))
+        };
+        let (has_pointer, has_touch) =
+            (has(DeviceCapability::Pointer), has(DeviceCapability::Touch));
+        if !has_pointer && self.state.seat.get_pointer().is_some() {
+            self.state.seat.remove_pointer();
+        }
+        if !has_touch && self.state.seat.get_touch().is_some() {
+            self.state.seat.remove_touch();
+        }
     }
 
     pub fn reconfigure_devices(&mut self) {
@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::path::Path;
+
+use serde::Serialize;
+use slotmap::Key;
+use smithay::utils::{Logical, Rectangle};
+use tracing::{info, warn};
+
+use crate::shell::{Monitor, Tag, WindowId, Windows};
+use crate::state::State;
+
+type Rect = (i32, i32, i32, i32);
+
+fn rect(r: Rectangle<i32, Logical>) -> Rect {
+    (r.loc.x, r.loc.y, r.size.w, r.size.h)
+}
+
+fn window_key(id: WindowId) -> u64 {
+    id.data().as_ffi()
+}
+
+#[derive(Serialize)]
+pub struct StateDump {
+    pub active_monitor: usize,
+    pub focused: Option<u64>,
+    pub locked: bool,
+    pub monitors: Vec<MonitorDump>,
+}
+
+#[derive(Serialize)]
+pub struct MonitorDump {
+    pub name: String,
+    pub geometry: Rect,
+    pub active_tag: usize,
+    pub prev_tag: usize,
+    pub tags: Vec<TagDump>,
+}
+
+#[derive(Serialize)]
+pub struct TagDump {
+    pub name: String,
+    pub layout: String,
+    pub symbol: String,
+    pub main_count: usize,
+    pub main_factor: f32,
+    pub mirrored: bool,
+    pub focused: Option<u64>,
+    pub windows: Vec<WindowDump>,
+}
+
+#[derive(Serialize)]
+pub struct WindowDump {
+    pub id: u64,
+    pub app_id: String,
+    pub title: String,
+    pub geometry: Option<Rect>,
+    pub floating: bool,
+    pub fullscreen: bool,
+    pub focused: bool,
+    pub urgent: bool,
+    pub screencast: bool,
+}
+
+impl Tag {
+    fn dump(&self, name: &str, geo: Rectangle<i32, Logical>, windows: &Windows) -> TagDump {
+        let windows = self
+            .focus_stack
+            .iter()
+            .filter_map(|&id| {
+                let we = windows.get(id)?;
+                let geometry = if we.fullscreen {
+                    Some(geo)
+                } else if we.floating {
+                    Some(we.float_geo)
+                } else {
+                    self.layout.position_of(id)
+                };
+                Some(WindowDump {
+                    id: window_key(id),
+                    app_id: we.app_id.clone(),
+                    title: we.title.clone(),
+                    geometry: geometry.map(rect),
+                    floating: we.floating,
+                    fullscreen: we.fullscreen,
+                    focused: we.focused,
+                    urgent: we.urgent,
+                    screencast: we.screencasts > 0,
+                })
+            })
+            .collect();
+
+        TagDump {
+            name: name.to_string(),
+            layout: self.layout.name().to_string(),
+            symbol: self.layout.symbol().to_string(),
+            main_count: self.layout.main_count,
+            main_factor: self.layout.main_factor,
+            mirrored: self.layout.mirrored,
+            focused: self.focused_id().map(window_key),
+            windows,
+        }
+    }
+}
+
+impl Monitor {
+    pub fn dump(&self, windows: &Windows) -> MonitorDump {
+        let geo = self.geometry();
+        let tags = self
+            .tags
+            .iter()
+            .enumerate()
+            .map(|(i, t)| {
+                let name = self.settings.tags.get(i).map_or("", String::as_str);
+                t.dump(name, geo, windows)
+            })
+            .collect();
+
+        MonitorDump {
+            name: self.output.name(),
+            geometry: rect(geo),
+            active_tag: self.active_tag,
+            prev_tag: self.prev_tag,
+            tags,
+        }
+    }
+}
+
+impl State {
+    pub fn dump(&self) -> StateDump {
+        StateDump {
+            active_monitor: self.active_monitor,
+            focused: self.windows.focused.map(window_key),
+            locked: self.locked,
+            monitors: self
+                .monitors
+                .iter()
+                .map(|m| m.dump(&self.windows))
+                .collect(),
+        }
+    }
+
+    pub fn dump_to(&self, path: Option<&Path>) {
+        let json = match serde_json::to_string_pretty(&self.dump()) {
+            Ok(json) => json,
+            Err(e) => {
+                warn!("failed to serialize state: {e}");
+                return;
+            }
+        };
+        match path {
+            Some(p) => match std::fs::write(p, json) {
+                Ok(()) => info!("state dumped to {}", p.display()),
+                Err(e) => warn!("failed to write {}: {e}", p.display()),
+            },
+            None => info!("state: {json}"),
+        }
+    }
+}
@@ -16,7 +16,7 @@ use super::dwl_ipc_protocol::{
 };
 use crate::{
     Monotile,
-    config::{self, Action},
+    config::{self, Action, LayoutMode},
     shell::Monitor,
 };
 
@@ -42,7 +42,11 @@ fn send_snapshot(handle: &ZdwlIpcOutputV2, snap: &TagSnapshot, mon: &Monitor, ac
         let focused = (snap.focused_tags >> i) & 1;
         handle.tag(i as u32, state, clients, focused);
     }
-    handle.layout(0);
+    let layout = LayoutMode::ALL
+        .iter()
+        .position(|&m| m == mon.tag().layout.mode)
+        .unwrap_or(0);
+    handle.layout(layout as u32);
     handle.layout_symbol(snap.layout_symbol.clone());
     handle.title(snap.title.clone());
     handle.appid(snap.app_id.clone());
@@ -101,7 +105,9 @@ impl GlobalDispatch<ZdwlIpcManagerV2, DwlIpcManagerData> for Monotile {
     ) {
         let mgr = data_init.init(resource, ());
         mgr.tags(data.tag_count);
-        mgr.layout("tile".to_string());
+        for mode in LayoutMode::ALL {
+            mgr.layout(mode.name().to_string());
+        }
     }
 }
 
@@ -160,8 +166,11 @@ impl Dispatch<ZdwlIpcOutputV2, ()> for Monotile {
                     monotile.handle_action(Action::ToggleTag(tag));
                 }
             }
-            // TODO: implement layout switching
-            Request::SetLayout { .. } => {}
+            Request::SetLayout { index } => {
+                if let Some(&mode) = LayoutMode::ALL.get(index as usize) {
+                    monotile.handle_action(Action::SetLayout(mode));
+                }
+            }
             Request::Release => {}
         }
     }
@@ -1,5 +1,7 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
+#[cfg(feature = "dump")]
+mod dump;
 mod dwl_ipc;
 mod dwl_ipc_protocol;
 mod monotile_ipc;
@@ -36,6 +38,8 @@ pub struct TagSnapshot {
 
     pub layout_name: String,
     pub layout_symbol: String,
+    // per tag, the active one included
+    pub tag_symbols: Vec<String>,
 
     pub title: String,
     pub app_id: String,
@@ -84,6 +88,11 @@ impl Monitor {
 
             layout_name: tag.layout.name().to_string(),
             layout_symbol: tag.layout.symbol().to_string(),
+            tag_symbols: self
+                .tags
+                .iter()
+                .map(|t| t.layout.symbol().to_string())
+                .collect(),
 
             title,
             app_id,
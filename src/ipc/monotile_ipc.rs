@@ -11,7 +11,7 @@ use wayland_server::{
 use super::TagSnapshot;
 use super::monotile_ipc_protocol::{
     zmonotile_control_v1::ZmonotileControlV1,
-    zmonotile_output_status_v1::ZmonotileOutputStatusV1,
+    zmonotile_output_status_v1::{self as output_status, ZmonotileOutputStatusV1},
     zmonotile_seat_control_v1::{self as proto, ZmonotileSeatControlV1},
     zmonotile_seat_status_v1::ZmonotileSeatStatusV1,
     zmonotile_status_manager_v1::ZmonotileStatusManagerV1,
@@ -29,8 +29,8 @@ pub struct MonotileIpcState {
 
 impl MonotileIpcState {
     pub fn new(dh: &DisplayHandle) -> Self {
-        dh.create_global::<Monotile, ZmonotileStatusManagerV1, _>(1, ());
-        dh.create_global::<Monotile, ZmonotileControlV1, _>(1, ());
+        dh.create_global::<Monotile, ZmonotileStatusManagerV1, _>(2, ());
+        dh.create_global::<Monotile, ZmonotileControlV1, _>(4, ());
         Self {
             outputs: HashMap::new(),
             seats: Vec::new(),
@@ -75,6 +75,12 @@ fn send_output_status(h: &ZmonotileOutputStatusV1, snap: &TagSnapshot) {
     h.urgent_tags(snap.urgent_tags);
     h.layout(snap.layout_name.clone(), snap.layout_symbol.clone());
     h.screencast(snap.screencast as u32);
+    // clients bound at version 1 never learned about tag_layout
+    if h.version() >= output_status::EVT_TAG_LAYOUT_SINCE {
+        for (i, symbol) in snap.tag_symbols.iter().enumerate() {
+            h.tag_layout(i as u32, symbol.clone());
+        }
+    }
 }
 
 fn send_seat_status(h: &ZmonotileSeatStatusV1, snap: &TagSnapshot, output: &Output) {
@@ -143,9 +149,10 @@ impl Dispatch<ZmonotileStatusManagerV1, ()> for Monotile {
 
                 // TODO: get monitor from seat when multiseat is implemented
                 // send initial state
-                let mon = monotile.state.mon();
-                let snap = mon.snapshot(&monotile.state.windows, &monotile.state.screencopy);
-                send_seat_status(&handle, &snap, &mon.output);
+                if let Some(mon) = monotile.state.mon() {
+                    let snap = mon.snapshot(&monotile.state.windows, &monotile.state.screencopy);
+                    send_seat_status(&handle, &snap, &mon.output);
+                }
             }
             Request::Destroy => {}
         }
@@ -284,6 +291,22 @@ impl Dispatch<ZmonotileSeatControlV1, ()> for Monotile {
             Request::SetMainCount { count } => Action::SetMainCount(count as usize),
             Request::AdjustMainRatio { delta } => Action::AdjustMainRatio(delta as f32),
             Request::SetMainRatio { ratio } => Action::SetMainRatio(ratio as f32),
+            Request::CycleLayout => Action::CycleLayout,
+            Request::ShowOsd { text } => Action::ShowOsd(text),
+            Request::Screenshot {
+                name,
+                x,
+                y,
+                width,
+                height,
+                cursor,
+                window,
+            } => Action::Screenshot {
+                name: (!name.is_empty()).then_some(name),
+                region: (width > 0 && height > 0).then_some((x, y, width, height)),
+                cursor: cursor != 0,
+                window: window != 0,
+            },
             Request::Destroy => return,
         };
         monotile.handle_action(action);
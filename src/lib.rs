@@ -4,9 +4,11 @@ pub mod backend;
 pub mod config;
 pub mod grabs;
 pub mod handlers;
+pub mod idle;
 pub mod input;
 pub mod ipc;
 pub mod render;
+pub mod session;
 pub mod shell;
 pub mod spawn;
 pub mod state;
@@ -2,10 +2,12 @@
 
 use smithay::{
     backend::renderer::{
-        element::Kind,
-        gles::{GlesPixelProgram, Uniform, element::PixelShaderElement},
+        element::{Element, Id, Kind, RenderElement, UnderlyingStorage},
+        gles::{GlesError, GlesPixelProgram, Uniform, element::PixelShaderElement},
+        glow::{GlowFrame, GlowRenderer},
+        utils::{CommitCounter, DamageSet, OpaqueRegions},
     },
-    utils::{Logical, Rectangle},
+    utils::{Buffer, Logical, Physical, Rectangle, Scale, Size, Transform, user_data::UserDataMap},
 };
 
 fn pieces(
@@ -51,6 +53,7 @@ fn uniforms(
     color: [f32; 4],
     offset: (f32, f32),
     scale: f32,
+    feather: f32,
 ) -> Vec<Uniform<'static>> {
     let outer_size = (
         (win.size.w + 2 * border_width) as f32,
@@ -63,6 +66,7 @@ fn uniforms(
         Uniform::new("border_color", color),
         Uniform::new("piece_offset", offset),
         Uniform::new("scale", scale),
+        Uniform::new("feather", feather),
     ]
 }
 
@@ -73,6 +77,7 @@ pub fn create_elements(
     border_width: i32,
     color: [f32; 4],
     scale: f32,
+    feather: f32,
 ) -> Vec<PixelShaderElement> {
     let (rects, outer_r) = pieces(win, border_width, radius);
     let ox = win.loc.x - border_width;
@@ -89,9 +94,254 @@ pub fn create_elements(
                 rect,
                 None,
                 1.0,
-                uniforms(win, border_width, outer_r, color, offset, scale),
+                uniforms(win, border_width, outer_r, color, offset, scale, feather),
                 Kind::Unspecified,
             )
         })
         .collect()
 }
+
+// the pieces relative to the outer origin, and the straight edges of them
+// which are opaque when the color is
+fn ring(
+    win: Rectangle<i32, Logical>,
+    width: i32,
+    radius: f32,
+) -> (Vec<Rectangle<i32, Logical>>, Vec<Rectangle<i32, Logical>>) {
+    let (rects, _) = pieces(win, width, radius);
+    let (ox, oy) = (win.loc.x - width, win.loc.y - width);
+    let (mut ring, mut edges) = (Vec::new(), Vec::new());
+    // corners and edges alternate, only the corners are shaded
+    for (i, (rx, ry, rw, rh)) in rects.into_iter().enumerate() {
+        if rw <= 0 || rh <= 0 {
+            continue;
+        }
+        let r = Rectangle::new((rx - ox, ry - oy).into(), (rw, rh).into());
+        ring.push(r);
+        if i % 2 == 1 {
+            edges.push(r);
+        }
+    }
+    (ring, edges)
+}
+
+// the parts of damage on the ring, in physical coordinates relative to the
+// outer origin like the ring at scale
+fn clip_to_ring(
+    damage: &[Rectangle<i32, Physical>],
+    ring: &[Rectangle<i32, Logical>],
+    scale: Scale<f64>,
+) -> Vec<Rectangle<i32, Physical>> {
+    let ring: Vec<Rectangle<i32, Physical>> = ring
+        .iter()
+        .map(|r| r.to_f64().to_physical_precise_up(scale))
+        .collect();
+    damage
+        .iter()
+        .flat_map(|d| ring.iter().filter_map(move |r| d.intersection(*r)))
+        .collect()
+}
+
+// the whole border as one element over the window, damaged, drawn and opaque
+// only where the ring is
+#[derive(Debug, Clone)]
+pub struct BorderRing {
+    inner: PixelShaderElement,
+    // outer size, to tell the scale of the rect it is drawn to
+    size: Size<i32, Logical>,
+    ring: Vec<Rectangle<i32, Logical>>,
+    opaque: Vec<Rectangle<i32, Logical>>,
+}
+
+impl BorderRing {
+    pub fn new(
+        shader: &GlesPixelProgram,
+        win: Rectangle<i32, Logical>,
+        radius: f32,
+        border_width: i32,
+        color: [f32; 4],
+        scale: f32,
+        feather: f32,
+    ) -> Self {
+        let outer_r = if radius != 0.0 { radius + border_width as f32 } else { 0.0 };
+        let rect = Rectangle::new(
+            (win.loc.x - border_width, win.loc.y - border_width).into(),
+            (win.size.w + 2 * border_width, win.size.h + 2 * border_width).into(),
+        );
+        let inner = PixelShaderElement::new(
+            shader.clone(),
+            rect,
+            None,
+            1.0,
+            uniforms(
+                win,
+                border_width,
+                outer_r,
+                color,
+                (0.0, 0.0),
+                scale,
+                feather,
+            ),
+            Kind::Unspecified,
+        );
+        let (ring, edges) = ring(win, border_width, radius);
+        let opaque = if color[3] >= 1.0 { edges } else { Vec::new() };
+        Self {
+            inner,
+            size: rect.size,
+            ring,
+            opaque,
+        }
+    }
+}
+
+impl Element for BorderRing {
+    fn id(&self) -> &Id {
+        self.inner.id()
+    }
+    fn current_commit(&self) -> CommitCounter {
+        self.inner.current_commit()
+    }
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.inner.geometry(scale)
+    }
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.inner.src()
+    }
+    fn transform(&self) -> Transform {
+        self.inner.transform()
+    }
+    fn alpha(&self) -> f32 {
+        self.inner.alpha()
+    }
+    fn kind(&self) -> Kind {
+        self.inner.kind()
+    }
+
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        commit: Option<CommitCounter>,
+    ) -> DamageSet<i32, Physical> {
+        let damage: Vec<_> = self.inner.damage_since(scale, commit).into_iter().collect();
+        clip_to_ring(&damage, &self.ring, scale)
+            .into_iter()
+            .collect()
+    }
+
+    fn opaque_regions(&self, scale: Scale<f64>) -> OpaqueRegions<i32, Physical> {
+        let opaque: Vec<Rectangle<i32, Physical>> = self
+            .opaque
+            .iter()
+            .map(|r| r.to_f64().to_physical_precise_down(scale))
+            .collect();
+        OpaqueRegions::from_slice(&opaque)
+    }
+}
+
+impl RenderElement<GlowRenderer> for BorderRing {
+    fn draw(
+        &self,
+        frame: &mut GlowFrame<'_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque: &[Rectangle<i32, Physical>],
+        cache: Option<&UserDataMap>,
+    ) -> Result<(), GlesError> {
+        // the shader never runs over the window, whatever else got damaged
+        let scale = Scale::from((
+            dst.size.w as f64 / self.size.w.max(1) as f64,
+            dst.size.h as f64 / self.size.h.max(1) as f64,
+        ));
+        let damage = clip_to_ring(damage, &self.ring, scale);
+        if damage.is_empty() {
+            return Ok(());
+        }
+        RenderElement::<GlowRenderer>::draw(&self.inner, frame, src, dst, &damage, opaque, cache)
+    }
+
+    fn underlying_storage(&self, _: &mut GlowRenderer) -> Option<UnderlyingStorage<'_>> {
+        None
+    }
+}
+
+// rounded rectangle filled with color
+pub fn filled_element(
+    shader: &GlesPixelProgram,
+    rect: Rectangle<i32, Logical>,
+    radius: f32,
+    color: [f32; 4],
+    alpha: f32,
+    scale: f32,
+    feather: f32,
+) -> PixelShaderElement {
+    PixelShaderElement::new(
+        shader.clone(),
+        rect,
+        None,
+        alpha,
+        uniforms(rect, 0, radius, color, (0.0, 0.0), scale, feather),
+        Kind::Unspecified,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // what the single element leaves out of its outer rect
+    fn uncovered(
+        win: Rectangle<i32, Logical>,
+        width: i32,
+        ring: &[Rectangle<i32, Logical>],
+    ) -> Vec<Rectangle<i32, Logical>> {
+        let outer = Rectangle::from_size(win.size + (2 * width, 2 * width).into());
+        Rectangle::subtract_rects_many([outer], ring.iter().copied())
+    }
+
+    #[test]
+    fn square_ring_damages_only_the_border() {
+        let win = Rectangle::new((10, 20).into(), (100, 50).into());
+        let (ring, edges) = ring(win, 2, 0.0);
+        let inside = Rectangle::new((2, 2).into(), win.size);
+        let left = uncovered(win, 2, &ring);
+        assert!(left.iter().all(|r| inside.contains_rect(*r)));
+        let area: i32 = left.iter().map(|r| r.size.w * r.size.h).sum();
+        assert_eq!(area, 100 * 50, "the whole window is left out");
+        assert_eq!(edges, ring, "without corners all of it is opaque");
+    }
+
+    #[test]
+    fn drawing_is_clipped_to_the_ring() {
+        let win = Rectangle::new((10, 20).into(), (100, 50).into());
+        let (ring, _) = ring(win, 2, 0.0);
+        let scale = Scale::from(2.0);
+        // a repaint of the whole element, as for a translucent window
+        let all = Rectangle::from_size((208, 108).into());
+        let drawn = clip_to_ring(&[all], &ring, scale);
+        let window = Rectangle::new((4, 4).into(), (200, 100).into());
+        assert!(drawn.iter().all(|r| r.intersection(window).is_none()));
+        let left = Rectangle::subtract_rects_many([all], drawn);
+        assert!(left.iter().all(|r| window.contains_rect(*r)));
+        let area: i32 = left.iter().map(|r| r.size.w * r.size.h).sum();
+        assert_eq!(area, 200 * 100, "all but the window is drawn");
+        assert!(clip_to_ring(&[window], &ring, scale).is_empty());
+    }
+
+    #[test]
+    fn rounded_ring_keeps_the_corners_out_of_the_opaque_region() {
+        let win = Rectangle::new((10, 20).into(), (300, 200).into());
+        let (ring, edges) = ring(win, 4, 8.0);
+        assert_eq!(ring.len(), 8, "the pieces create_elements draws");
+        let inside = Rectangle::new((4, 4).into(), win.size);
+        // corners reach into the window to cover the rounding, the rest of it is left out
+        assert!(
+            uncovered(win, 4, &ring)
+                .iter()
+                .all(|r| inside.contains_rect(*r))
+        );
+        assert_eq!(edges.len(), 4);
+        assert!(edges.iter().all(|e| e.intersection(inside).is_none()));
+    }
+}
@@ -39,14 +39,24 @@ pub struct Clipped {
 impl Clipped {
     pub fn wrap(
         inner: Clippable,
-        program: &GlesTexProgram,
+        program: Option<&GlesTexProgram>,
         geo: Rectangle<i32, Logical>,
         radius: f32,
         scale: Scale<f64>,
+        feather: f32,
     ) -> MonotileElement {
-        let phys: Rectangle<i32, Physical> = geo.to_f64().to_physical_precise_round(scale);
-        if radius > 0.0 || !phys.contains_rect(inner.geometry(scale)) {
-            MonotileElement::Clipped(Self::new(inner, program.clone(), geo, radius, scale))
+        // without the clip shader surfaces are drawn unclipped
+        if let Some(program) = program
+            && needs_clip(geo, inner.geometry(scale), radius, scale)
+        {
+            MonotileElement::Clipped(Self::new(
+                inner,
+                program.clone(),
+                geo,
+                radius,
+                scale,
+                feather,
+            ))
         } else {
             // If clipping is not necessary, return the plain Surface/Texture variant
             // to keep buffer storage and direct scanout intact
@@ -63,6 +73,7 @@ impl Clipped {
         geo: Rectangle<i32, Logical>,
         radius: f32,
         scale: Scale<f64>,
+        feather: f32,
     ) -> Self {
         let geo_f = geo.to_f64();
         let elem = inner.geometry(scale);
@@ -75,18 +86,25 @@ impl Clipped {
         let offset_x = (elem.loc.x - phys.loc.x) as f32 / phys_w;
         let offset_y = (elem.loc.y - phys.loc.y) as f32 / phys_h;
 
+        // texture UV is in buffer space, undo the buffer transform around the center
+        let [c0, c1] = transform_cols(inner.transform());
+        let (sx, sy) = (elem_w / phys_w, elem_h / phys_h);
+        let tx = c0[0] * offset_x + c1[0] * offset_y + 0.5 - 0.5 * (c0[0] + c1[0]);
+        let ty = c0[1] * offset_x + c1[1] * offset_y + 0.5 - 0.5 * (c0[1] + c1[1]);
+
         // Column-major 3x3: maps texture UV to [0,1] geo space
         #[rustfmt::skip]
         let mat: [f32; 9] = [
-            elem_w / phys_w, 0.0,             0.0,
-            0.0,             elem_h / phys_h, 0.0,
-            offset_x,        offset_y,        1.0,
+            c0[0] * sx, c0[1] * sx, 0.0,
+            c1[0] * sy, c1[1] * sy, 0.0,
+            tx,         ty,         1.0,
         ];
 
         let uniforms = vec![
             Uniform::new("geo_size", (geo.size.w as f32, geo.size.h as f32)),
             Uniform::new("inner_radius", radius),
             Uniform::new("scale", scale.x as f32),
+            Uniform::new("feather", feather),
             Uniform::new(
                 "input_to_geo",
                 UniformValue::Matrix3x3 {
@@ -112,6 +130,66 @@ impl Clipped {
     }
 }
 
+// square elements inside geo, e.g. a tile filled by its window, skip the shader
+fn needs_clip(
+    geo: Rectangle<i32, Logical>,
+    elem: Rectangle<i32, Physical>,
+    radius: f32,
+    scale: Scale<f64>,
+) -> bool {
+    let phys: Rectangle<i32, Physical> = geo.to_f64().to_physical_precise_round(scale);
+    radius > 0.0 || !phys.contains_rect(elem)
+}
+
+// opaque regions cut to the clip rect and the rounded corners, relative to the
+// element at loc
+fn clip_opaque(
+    opaque: impl Iterator<Item = Rectangle<i32, Physical>>,
+    clip: Rectangle<i32, Physical>,
+    geo: Rectangle<f64, Logical>,
+    radius: f32,
+    scale: Scale<f64>,
+    loc: Point<i32, Physical>,
+) -> Vec<Rectangle<i32, Physical>> {
+    let clipped = opaque.filter_map(|r| r.intersection(clip));
+    // rounding up even an empty corner at a fractional position would cut a pixel
+    if radius <= 0.0 {
+        return clipped.collect();
+    }
+
+    let r = radius as f64;
+    let g = geo;
+    let corners = [
+        g.loc,
+        Point::from((g.loc.x + g.size.w - r, g.loc.y)),
+        Point::from((g.loc.x + g.size.w - r, g.loc.y + g.size.h - r)),
+        Point::from((g.loc.x, g.loc.y + g.size.h - r)),
+    ]
+    .into_iter()
+    .map(|p| {
+        let mut c: Rectangle<i32, Physical> =
+            Rectangle::new(p, Size::from((r, r))).to_physical_precise_up(scale);
+        c.loc -= loc;
+        c
+    });
+    Rectangle::subtract_rects_many(clipped, corners)
+}
+
+// columns of the 2x2 map from buffer texture coordinates to the surface,
+// a _90 buffer is shown turned clockwise so its top left lands top right
+fn transform_cols(t: Transform) -> [[f32; 2]; 2] {
+    match t {
+        Transform::Normal => [[1.0, 0.0], [0.0, 1.0]],
+        Transform::_90 => [[0.0, 1.0], [-1.0, 0.0]],
+        Transform::_180 => [[-1.0, 0.0], [0.0, -1.0]],
+        Transform::_270 => [[0.0, -1.0], [1.0, 0.0]],
+        Transform::Flipped => [[-1.0, 0.0], [0.0, 1.0]],
+        Transform::Flipped90 => [[0.0, 1.0], [1.0, 0.0]],
+        Transform::Flipped180 => [[1.0, 0.0], [0.0, -1.0]],
+        Transform::Flipped270 => [[0.0, -1.0], [-1.0, 0.0]],
+    }
+}
+
 impl Element for Clipped {
     fn id(&self) -> &Id {
         self.inner.id()
@@ -149,32 +227,15 @@ impl Element for Clipped {
     }
 
     fn opaque_regions(&self, scale: Scale<f64>) -> OpaqueRegions<i32, Physical> {
-        let clip = self.clip_rect(scale);
-        let clipped = self
-            .inner
-            .opaque_regions(scale)
-            .into_iter()
-            .filter_map(|r| r.intersection(clip));
-
-        // subtract corner areas
-        let r = self.radius as f64;
-        let g = self.geo;
-        let loc = self.geometry(scale).loc;
-        let corners = [
-            g.loc,
-            Point::from((g.loc.x + g.size.w - r, g.loc.y)),
-            Point::from((g.loc.x + g.size.w - r, g.loc.y + g.size.h - r)),
-            Point::from((g.loc.x, g.loc.y + g.size.h - r)),
-        ]
-        .into_iter()
-        .map(|p| {
-            let mut c: Rectangle<i32, Physical> =
-                Rectangle::new(p, Size::from((r, r))).to_physical_precise_up(scale);
-            c.loc -= loc;
-            c
-        });
-
-        OpaqueRegions::from_slice(&Rectangle::subtract_rects_many(clipped, corners))
+        let opaque = clip_opaque(
+            self.inner.opaque_regions(scale).into_iter(),
+            self.clip_rect(scale),
+            self.geo,
+            self.radius,
+            scale,
+            self.geometry(scale).loc,
+        );
+        OpaqueRegions::from_slice(&opaque)
     }
 }
 
@@ -203,3 +264,73 @@ impl RenderElement<GlowRenderer> for Clipped {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_filled_by_its_window_is_fully_opaque() {
+        // a fractional scale puts the tile at fractional physical positions
+        let scale = Scale::from(1.5);
+        let tile = Rectangle::<i32, Logical>::new((3, 5).into(), (401, 301).into());
+        let phys: Rectangle<i32, Physical> = tile.to_f64().to_physical_precise_round(scale);
+        assert!(!needs_clip(tile, phys, 0.0, scale), "takes the plain path");
+
+        let local = Rectangle::from_size(phys.size);
+        let opaque = clip_opaque(
+            [local].into_iter(),
+            local,
+            tile.to_f64(),
+            0.0,
+            scale,
+            phys.loc,
+        );
+        assert_eq!(opaque, vec![local]);
+    }
+
+    #[test]
+    fn rounded_corners_are_not_opaque() {
+        let scale = Scale::from(1.0);
+        let tile = Rectangle::<i32, Logical>::new((0, 0).into(), (100, 100).into());
+        let phys: Rectangle<i32, Physical> = tile.to_f64().to_physical_precise_round(scale);
+        assert!(needs_clip(tile, phys, 8.0, scale));
+
+        let opaque = clip_opaque(
+            [phys].into_iter(),
+            phys,
+            tile.to_f64(),
+            8.0,
+            scale,
+            phys.loc,
+        );
+        let area: i32 = opaque.iter().map(|r| r.size.w * r.size.h).sum();
+        assert_eq!(area, 100 * 100 - 4 * 8 * 8);
+    }
+
+    // where a texture coordinate lands in the unit square, as the matrix in new() puts it
+    fn place(t: Transform, uv: (f32, f32)) -> (f32, f32) {
+        let [c0, c1] = transform_cols(t);
+        let x = c0[0] * uv.0 + c1[0] * uv.1 + 0.5 - 0.5 * (c0[0] + c1[0]);
+        let y = c0[1] * uv.0 + c1[1] * uv.1 + 0.5 - 0.5 * (c0[1] + c1[1]);
+        (x, y)
+    }
+
+    #[test]
+    fn transformed_buffers_put_their_origin_in_the_right_corner() {
+        let cases = [
+            (Transform::Normal, (0.0, 0.0)),
+            (Transform::_90, (1.0, 0.0)),
+            (Transform::_180, (1.0, 1.0)),
+            (Transform::_270, (0.0, 1.0)),
+            (Transform::Flipped, (1.0, 0.0)),
+            (Transform::Flipped90, (0.0, 0.0)),
+            (Transform::Flipped180, (0.0, 1.0)),
+            (Transform::Flipped270, (1.0, 1.0)),
+        ];
+        for (t, corner) in cases {
+            assert_eq!(place(t, (0.0, 0.0)), corner, "{t:?}");
+            assert_eq!(place(t, (0.5, 0.5)), (0.5, 0.5), "{t:?} keeps the center");
+        }
+    }
+}
@@ -16,7 +16,7 @@ use smithay::{
     },
     input::pointer::{CursorIcon, CursorImageStatus, CursorImageSurfaceData},
     reexports::wayland_server::{Resource, protocol::wl_surface::WlSurface},
-    utils::{Logical, Point, Scale, Transform},
+    utils::{Logical, Point, Scale, Size, Transform},
     wayland::compositor,
 };
 
@@ -30,6 +30,8 @@ static FALLBACK_CURSOR_DATA: &[u8] = include_bytes!("../../resources/cursor.rgba
 struct Cursor {
     buffer: MemoryRenderBuffer,
     hotspot: Point<i32, Logical>,
+    // the image is loaded at output resolution and drawn at this size
+    size: Size<i32, Logical>,
 }
 
 struct DndIcon {
@@ -41,69 +43,101 @@ pub struct CursorManager {
     pub status: CursorImageStatus,
     pub override_icon: Option<CursorIcon>,
     dnd_icon: Option<DndIcon>,
-    pub scale: f32,
     pub hotspot: Point<i32, Logical>,
+    pub theme_name: String,
     pub size: u32,
     theme: CursorTheme,
-    cache: HashMap<String, Cursor>,
+    // by name and image size in pixels, one entry per output scale in use
+    cache: HashMap<(String, u32), Cursor>,
+    fallback: Cursor,
 }
 
 impl CursorManager {
-    pub fn new(scale: f32) -> Self {
-        let name = std::env::var("XCURSOR_THEME").unwrap_or("default".into());
-        let size = std::env::var("XCURSOR_SIZE").unwrap_or("24".into());
-        let size = size.parse().unwrap_or(24);
-
-        let theme = CursorTheme::load(&name);
+    pub fn new(theme: &str, size: u32) -> Self {
+        let buffer = MemoryRenderBuffer::from_slice(
+            FALLBACK_CURSOR_DATA,
+            Fourcc::Argb8888,
+            (64, 64),
+            1,
+            Transform::Normal,
+            None,
+        );
         let mut cursor_manager = Self {
             status: CursorImageStatus::default_named(),
             override_icon: None,
             dnd_icon: None,
-            scale,
             hotspot: Point::default(),
-            theme,
+            theme_name: String::new(),
             size,
+            theme: CursorTheme::load(theme),
             cache: HashMap::new(),
+            fallback: Cursor {
+                buffer,
+                hotspot: (1, 1).into(),
+                size: (64, 64).into(),
+            },
         };
+        cursor_manager.reload(theme, size);
+        cursor_manager
+    }
 
-        if !cursor_manager.load_icon(CursorIcon::Default) {
-            warn!("failed to load xcursor theme, using fallback");
-            let buffer = MemoryRenderBuffer::from_slice(
-                FALLBACK_CURSOR_DATA,
-                Fourcc::Argb8888,
-                (64, 64),
-                1,
-                Transform::Normal,
-                None,
-            );
-            cursor_manager.cache.insert(
-                "default".to_string(),
-                Cursor {
-                    buffer,
-                    hotspot: (1, 1).into(),
-                },
-            );
+    // switch theme or size, dropping every image loaded so far
+    pub fn reload(&mut self, theme: &str, size: u32) {
+        self.cache.clear();
+        self.size = size;
+        self.theme_name = theme.to_string();
+        self.theme = CursorTheme::load(theme);
+        if load_xcursor(&self.theme, "default", size, 1.0).is_some() {
+            return;
+        }
+        if theme != "default" {
+            warn!("cursor theme {theme} not found, using default");
+            self.theme_name = "default".to_string();
+            self.theme = CursorTheme::load("default");
+            if load_xcursor(&self.theme, "default", size, 1.0).is_some() {
+                return;
+            }
+        }
+        warn!("failed to load xcursor theme, using fallback");
+    }
+
+    // the named cursor currently shown, if any
+    pub fn current_icon(&self) -> Option<CursorIcon> {
+        match &self.status {
+            _ if self.override_icon.is_some() => self.override_icon,
+            CursorImageStatus::Named(icon) => Some(*icon),
+            _ => None,
         }
-        cursor_manager
     }
 
-    fn load_icon(&mut self, icon: CursorIcon) -> bool {
-        let name = icon.name();
-        if self.cache.contains_key(name) {
+    fn load_icon(&mut self, icon: CursorIcon, scale: f64) -> bool {
+        let key = (icon.name().to_string(), pixel_size(self.size, scale));
+        if self.cache.contains_key(&key) {
             return true;
         }
-        if let Some(cached) = load_xcursor(&self.theme, name, self.size) {
-            self.cache.insert(name.to_string(), cached);
+        if let Some(cached) = load_xcursor(&self.theme, &key.0, self.size, scale) {
+            self.cache.insert(key, cached);
             return true;
         }
         false
     }
 
-    fn get_icon(&mut self, icon: CursorIcon) -> &Cursor {
-        if !self.load_icon(icon) {
-            return &self.cache["default"];
+    fn get_icon(&mut self, icon: CursorIcon, scale: f64) -> &Cursor {
+        let icon = if self.load_icon(icon, scale) {
+            icon
+        } else if self.load_icon(CursorIcon::Default, scale) {
+            CursorIcon::Default
+        } else {
+            return &self.fallback;
+        };
+        &self.cache[&(icon.name().to_string(), pixel_size(self.size, scale))]
+    }
+
+    // load the current cursor ahead of the next frame
+    pub fn preload(&mut self, scale: f64) {
+        if let Some(icon) = self.current_icon() {
+            self.get_icon(icon, scale);
         }
-        &self.cache[icon.name()]
     }
 
     pub fn set_dnd_icon(&mut self, surface: Option<WlSurface>) {
@@ -145,6 +179,7 @@ impl CursorManager {
         &mut self,
         renderer: &mut GlowRenderer,
         pos: Point<f64, Logical>,
+        output_scale: f64,
     ) -> Vec<MonotileElement> {
         if let CursorImageStatus::Surface(s) = &self.status
             && !s.is_alive()
@@ -156,25 +191,11 @@ impl CursorManager {
         {
             self.dnd_icon = None;
         }
-        let scale = Scale::from(self.scale as f64);
+        let scale = Scale::from(output_scale);
 
         let (hotspot, mut elems) = if let Some(icon) = self.override_icon {
-            let cached = self.get_icon(icon);
-            let hotspot = cached.hotspot;
-            let loc = (pos - hotspot.to_f64()).to_physical_precise_round(scale);
-            let elems = match MemoryRenderBufferRenderElement::from_buffer(
-                renderer,
-                loc,
-                &cached.buffer,
-                None,
-                None,
-                None,
-                Kind::Cursor,
-            ) {
-                Ok(elem) => vec![MonotileElement::Memory(elem)],
-                Err(_) => vec![],
-            };
-            (hotspot, elems)
+            let cached = self.get_icon(icon, output_scale);
+            named_elements(renderer, cached, pos, scale)
         } else {
             self.status_elements(renderer, pos, scale)
         };
@@ -204,22 +225,8 @@ impl CursorManager {
         match &self.status {
             CursorImageStatus::Hidden => (Point::default(), vec![]),
             CursorImageStatus::Named(icon) => {
-                let cached = self.get_icon(*icon);
-                let hotspot = cached.hotspot;
-                let loc = (pos - hotspot.to_f64()).to_physical_precise_round(scale);
-                let elems = match MemoryRenderBufferRenderElement::from_buffer(
-                    renderer,
-                    loc,
-                    &cached.buffer,
-                    None,
-                    None,
-                    None,
-                    Kind::Cursor,
-                ) {
-                    Ok(elem) => vec![MonotileElement::Memory(elem)],
-                    Err(_) => vec![],
-                };
-                (hotspot, elems)
+                let cached = self.get_icon(*icon, scale.x);
+                named_elements(renderer, cached, pos, scale)
             }
             CursorImageStatus::Surface(surface) => {
                 let hotspot = compositor::with_states(surface, |states| {
@@ -244,14 +251,43 @@ impl CursorManager {
     }
 }
 
-fn load_xcursor(theme: &CursorTheme, name: &str, size: u32) -> Option<Cursor> {
+fn named_elements(
+    renderer: &mut GlowRenderer,
+    cached: &Cursor,
+    pos: Point<f64, Logical>,
+    scale: Scale<f64>,
+) -> (Point<i32, Logical>, Vec<MonotileElement>) {
+    let hotspot = cached.hotspot;
+    let loc = (pos - hotspot.to_f64()).to_physical_precise_round(scale);
+    let elems = match MemoryRenderBufferRenderElement::from_buffer(
+        renderer,
+        loc,
+        &cached.buffer,
+        None,
+        None,
+        Some(cached.size),
+        Kind::Cursor,
+    ) {
+        Ok(elem) => vec![MonotileElement::Memory(elem)],
+        Err(_) => vec![],
+    };
+    (hotspot, elems)
+}
+
+// image size that covers size logical pixels on an output with this scale
+fn pixel_size(size: u32, scale: f64) -> u32 {
+    (size as f64 * scale).round().max(1.0) as u32
+}
+
+fn load_xcursor(theme: &CursorTheme, name: &str, size: u32, scale: f64) -> Option<Cursor> {
     let path = theme.load_icon(name)?;
     let data = std::fs::read(path).ok()?;
 
     let images = parse_xcursor(&data)?;
+    let pixels = pixel_size(size, scale) as i32;
     let nearest = images
         .iter()
-        .min_by_key(|img| (size as i32 - img.size as i32).abs())?;
+        .min_by_key(|img| (pixels - img.size as i32).abs())?;
     let (w, h) = (nearest.width, nearest.height);
     let img = images
         .into_iter()
@@ -265,8 +301,12 @@ fn load_xcursor(theme: &CursorTheme, name: &str, size: u32) -> Option<Cursor> {
         Transform::Normal,
         None,
     );
+    // the nearest image is stretched so the cursor keeps its logical size
+    let ratio = img.size as f64 / pixels as f64 * scale;
+    let logical = |v: u32| (v as f64 / ratio).round() as i32;
     Some(Cursor {
         buffer,
-        hotspot: (img.xhot as i32, img.yhot as i32).into(),
+        hotspot: (logical(img.xhot), logical(img.yhot)).into(),
+        size: (logical(w), logical(h)).into(),
     })
 }
@@ -0,0 +1,77 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use smithay::{
+    backend::renderer::{
+        element::{
+            Kind,
+            memory::{MemoryRenderBuffer, MemoryRenderBufferRenderElement},
+        },
+        glow::GlowRenderer,
+    },
+    utils::{Logical, Point, Scale},
+};
+
+use super::{MonotileElement, font};
+use crate::shell::{Monitor, WindowId, Windows};
+
+// home row first
+const HINT_KEYS: &str = "asdfghjklqwertyuiopzxcvbnm";
+// logical pixels per font pixel
+const HINT_PX: i32 = 3;
+const HINT_FG: [u8; 4] = [0xff, 0xff, 0xff, 0xff];
+const HINT_BG: [u8; 4] = [0x20, 0x20, 0x20, 0xe0];
+
+#[derive(Debug)]
+pub struct Hint {
+    pub key: char,
+    pub window: WindowId,
+    label: MemoryRenderBuffer,
+}
+
+impl Monitor {
+    // label every visible window, the next key press picks one
+    pub fn show_hints(&mut self) {
+        let scale = self.output.current_scale().fractional_scale().ceil() as i32;
+        let ids = self.tag().window_ids();
+        self.hints = HINT_KEYS
+            .chars()
+            .zip(ids)
+            .map(|(key, window)| Hint {
+                key,
+                window,
+                label: font::label(&key.to_string(), HINT_PX * scale, HINT_FG, HINT_BG, scale),
+            })
+            .collect();
+    }
+}
+
+// labels centered on their windows
+pub fn elements(
+    renderer: &mut GlowRenderer,
+    mon: &Monitor,
+    windows: &Windows,
+    scale: Scale<f64>,
+) -> Vec<MonotileElement> {
+    let mut elems = Vec::new();
+    for hint in &mon.hints {
+        let Some(rect) = mon.window_rect(windows, hint.window) else {
+            continue;
+        };
+        let size = Point::<i32, Logical>::from((font::GLYPH_W + 2, font::GLYPH_H + 2));
+        let size = size.upscale(HINT_PX);
+        let loc = rect.loc + (rect.size.to_point() - size).downscale(2);
+        match MemoryRenderBufferRenderElement::from_buffer(
+            renderer,
+            loc.to_physical_precise_round(scale),
+            &hint.label,
+            None,
+            None,
+            None,
+            Kind::Unspecified,
+        ) {
+            Ok(elem) => elems.push(MonotileElement::Memory(elem)),
+            Err(err) => tracing::warn!(?err, "failed to upload hint label"),
+        }
+    }
+    elems
+}
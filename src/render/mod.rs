@@ -3,17 +3,25 @@
 mod border;
 pub mod clipped_surface;
 pub mod cursor;
+mod font;
+mod hints;
+mod osd;
+mod screenshot;
 mod shaders;
+mod stats;
+mod switcher;
+pub mod text;
 pub mod window;
 
 use std::borrow::BorrowMut;
+use std::path::Path;
 use std::time::Duration;
 
 use smithay::{
     backend::{
         allocator::Fourcc,
         renderer::{
-            Bind, BufferType, Color32F, ExportMem, Offscreen, buffer_type,
+            Bind, BufferType, Color32F, ExportMem, Offscreen, Renderer, buffer_type,
             damage::OutputDamageTracker,
             element::{
                 Kind, RenderElement,
@@ -23,8 +31,8 @@ use smithay::{
                 texture::TextureRenderElement,
             },
             gles::{
-                GlesPixelProgram, GlesRenderer, GlesTexProgram, GlesTexture, UniformName,
-                UniformType, element::PixelShaderElement,
+                GlesError, GlesPixelProgram, GlesRenderer, GlesTexProgram, GlesTexture,
+                UniformName, UniformType, element::PixelShaderElement,
             },
             glow::GlowRenderer,
         },
@@ -36,6 +44,13 @@ use smithay::{
     wayland::{dmabuf::get_dmabuf, shell::wlr_layer::Layer, shm::with_buffer_contents_mut},
 };
 
+pub use border::BorderRing;
+pub use hints::Hint;
+pub use osd::Osd;
+pub use screenshot::{Screenshot, capture as capture_screenshots, screenshot_path};
+pub use stats::RenderStats;
+pub use switcher::Switcher;
+pub use text::TextElement;
 pub use window::RenderStep;
 
 use crate::{
@@ -51,59 +66,104 @@ render_elements! {
     Texture=TextureRenderElement<GlesTexture>,
     Clipped=Clipped,
     Decoration=PixelShaderElement,
+    Border=BorderRing,
     Memory=MemoryRenderBufferRenderElement<GlowRenderer>,
+    Text=TextElement,
 }
 
 #[derive(Debug)]
 pub struct Shaders {
     pub rect: GlesPixelProgram,
-    pub shadow: GlesPixelProgram,
-    pub clip: GlesTexProgram,
+    // None if the driver rejects the shader, windows go without
+    pub shadow: Option<GlesPixelProgram>,
+    pub clip: Option<GlesTexProgram>,
 }
 
-pub fn compile_shaders(renderer: &mut GlowRenderer) -> Shaders {
+// files in dir named like the built-in shaders replace them, see `shaders`
+// in the config. A shader the driver rejects loses its effect, only failing
+// to compile even the plain rectangle is an error.
+pub fn compile_shaders(
+    renderer: &mut GlowRenderer,
+    dir: Option<&Path>,
+) -> Result<Shaders, GlesError> {
     let gles: &mut GlesRenderer = renderer.borrow_mut();
 
-    let rect = gles
-        .compile_custom_pixel_shader(
-            shaders::ROUNDED_RECT_FRAG,
-            &[
-                UniformName::new("outer_size", UniformType::_2f),
-                UniformName::new("border_width", UniformType::_1f),
-                UniformName::new("border_color", UniformType::_4f),
-                UniformName::new("outer_radius", UniformType::_1f),
-                UniformName::new("piece_offset", UniformType::_2f),
-                UniformName::new("scale", UniformType::_1f),
-            ],
-        )
-        .expect("rounded rectangle shader");
-    let shadow = gles
-        .compile_custom_pixel_shader(
-            shaders::SHADOW_FRAG,
-            &[
-                UniformName::new("win_size", UniformType::_2f),
-                UniformName::new("win_offset", UniformType::_2f),
-                UniformName::new("outer_radius", UniformType::_1f),
-                UniformName::new("shadow_box_size", UniformType::_2f),
-                UniformName::new("shadow_box_offset", UniformType::_2f),
-                UniformName::new("shadow_sigma", UniformType::_1f),
-                UniformName::new("shadow_color", UniformType::_4f),
-                UniformName::new("scale", UniformType::_1f),
-            ],
-        )
-        .expect("shadow shader");
-    let clip = gles
-        .compile_custom_texture_shader(
-            shaders::CLIPPED_SURFACE_FRAG,
-            &[
-                UniformName::new("geo_size", UniformType::_2f),
-                UniformName::new("inner_radius", UniformType::_1f),
-                UniformName::new("scale", UniformType::_1f),
-                UniformName::new("input_to_geo", UniformType::Matrix3x3),
-            ],
-        )
-        .expect("clip shader");
-    Shaders { rect, shadow, clip }
+    let uniforms = [
+        UniformName::new("outer_size", UniformType::_2f),
+        UniformName::new("border_width", UniformType::_1f),
+        UniformName::new("border_color", UniformType::_4f),
+        UniformName::new("outer_radius", UniformType::_1f),
+        UniformName::new("piece_offset", UniformType::_2f),
+        UniformName::new("scale", UniformType::_1f),
+        UniformName::new("feather", UniformType::_1f),
+    ];
+    let rect = compile_override(
+        dir,
+        "rounded_rect.frag",
+        shaders::ROUNDED_RECT_FRAG,
+        |src| gles.compile_custom_pixel_shader(src, &uniforms),
+    );
+    let rect = match rect {
+        Some(rect) => rect,
+        None => gles.compile_custom_pixel_shader(shaders::PLAIN_RECT_FRAG, &uniforms)?,
+    };
+    let uniforms = [
+        UniformName::new("win_size", UniformType::_2f),
+        UniformName::new("win_offset", UniformType::_2f),
+        UniformName::new("outer_radius", UniformType::_1f),
+        UniformName::new("shadow_box_size", UniformType::_2f),
+        UniformName::new("shadow_box_offset", UniformType::_2f),
+        UniformName::new("shadow_sigma", UniformType::_1f),
+        UniformName::new("shadow_color", UniformType::_4f),
+        UniformName::new("scale", UniformType::_1f),
+    ];
+    let shadow = compile_override(dir, "shadow.frag", shaders::SHADOW_FRAG, |src| {
+        gles.compile_custom_pixel_shader(src, &uniforms)
+    });
+    let uniforms = [
+        UniformName::new("geo_size", UniformType::_2f),
+        UniformName::new("inner_radius", UniformType::_1f),
+        UniformName::new("scale", UniformType::_1f),
+        UniformName::new("feather", UniformType::_1f),
+        UniformName::new("input_to_geo", UniformType::Matrix3x3),
+    ];
+    let clip = compile_override(
+        dir,
+        "clipped_surface.frag",
+        shaders::CLIPPED_SURFACE_FRAG,
+        |src| gles.compile_custom_texture_shader(src, &uniforms),
+    );
+    Ok(Shaders { rect, shadow, clip })
+}
+
+// the shader from dir/file if it exists and compiles, the built-in one
+// otherwise, None if that fails too
+fn compile_override<T, E: std::fmt::Debug>(
+    dir: Option<&Path>,
+    file: &str,
+    builtin: &str,
+    mut compile: impl FnMut(&str) -> Result<T, E>,
+) -> Option<T> {
+    if let Some(path) = dir.map(|d| d.join(file)) {
+        match std::fs::read_to_string(&path) {
+            Ok(src) => match compile(&src) {
+                Ok(program) => {
+                    tracing::info!("shader: {}", path.display());
+                    return Some(program);
+                }
+                Err(err) => tracing::warn!(?err, "{}: failed to compile", path.display()),
+            },
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => tracing::warn!("{}: {err}", path.display()),
+        }
+    }
+    match compile(builtin) {
+        Ok(program) => Some(program),
+        Err(err) => {
+            tracing::error!(?err, "{file}: built-in shader failed to compile");
+            None
+        }
+    }
 }
 
 pub struct RenderCtx<'a> {
@@ -112,6 +172,10 @@ pub struct RenderCtx<'a> {
     pub(crate) layout: &'a Layout,
     pub(crate) scale: Scale<f64>,
     pub(crate) elems: Vec<MonotileElement>,
+    // the output's clear color behind everything
+    pub(crate) background: [f32; 4],
+    // shade over windows with an open modal dialog
+    pub(crate) modal_dim: f32,
     output: &'a Output,
 }
 
@@ -183,17 +247,52 @@ pub fn popup_elements(
     elems
 }
 
+// what one frame of an output depends on besides its monitor
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OutputFrame<'a> {
+    pub locked: bool,
+    // screensaver fade over everything
+    pub idle_dim: f32,
+    // the active monitor of several, gets the active_output outline
+    pub active: bool,
+    // drawn at this scale instead of the output's, for a duplicate
+    pub scale: Option<f64>,
+    // on the active output while SwitchWindow is held
+    pub switcher: Option<&'a Switcher>,
+    // ToggleInputInhibit, a banner on every output until it's turned off
+    pub input_inhibited: bool,
+}
+
+#[cfg_attr(
+    feature = "profiling",
+    tracing::instrument(
+        level = "trace",
+        skip_all,
+        fields(output = %mon.output.name(), windows = windows.len()),
+    )
+)]
 pub fn output_elements(
     renderer: &mut GlowRenderer,
     mon: &mut Monitor,
     windows: &mut Windows,
     shaders: &Shaders,
     config: &Config,
-    locked: bool,
+    frame: OutputFrame<'_>,
 ) -> Vec<MonotileElement> {
+    let OutputFrame {
+        locked,
+        idle_dim,
+        active,
+        ..
+    } = frame;
     let output = &mon.output;
-    let out_scale = output.current_scale().fractional_scale();
+    let out_scale = frame
+        .scale
+        .unwrap_or_else(|| output.current_scale().fractional_scale());
     let scale = Scale::from(out_scale);
+    if let Err(err) = renderer.upscale_filter(mon.settings.scale_filter.into()) {
+        tracing::warn!(?err, "failed to set scale filter");
+    }
 
     let mut ctx = RenderCtx {
         renderer,
@@ -201,9 +300,27 @@ pub fn output_elements(
         layout: &config.layout,
         scale,
         elems: Vec::with_capacity(windows.len() * 20 + 32),
+        background: mon.background(),
+        modal_dim: config.modal.dim,
         output,
     };
 
+    // the screensaver fade covers everything, the lock surface too
+    if idle_dim > 0.0 {
+        let rect = Rectangle::from_size(mon.geometry().size);
+        let black = [0.0, 0.0, 0.0, 1.0];
+        let fade = border::filled_element(
+            &shaders.rect,
+            rect,
+            0.0,
+            black,
+            idle_dim,
+            out_scale as f32,
+            config.layout.corner_feather,
+        );
+        ctx.elems.push(MonotileElement::Decoration(fade));
+    }
+
     if let Some(lock) = &mon.lock_surface {
         ctx.surface_tree(lock.wl_surface(), (0, 0).into(), Kind::Unspecified);
         return ctx.elems;
@@ -212,11 +329,43 @@ pub fn output_elements(
         return ctx.elems;
     }
 
+    if active && let Some(color) = config.active_output.border {
+        let width = config.active_output.width;
+        let size = mon.geometry().size;
+        // the border is drawn around the rect, keep it on the output
+        let inner = Rectangle::new(
+            (width, width).into(),
+            (size.w - 2 * width, size.h - 2 * width).into(),
+        );
+        let pieces = border::create_elements(
+            &shaders.rect,
+            inner,
+            0.0,
+            width,
+            color.0,
+            out_scale as f32,
+            config.layout.corner_feather,
+        );
+        ctx.elems
+            .extend(pieces.into_iter().map(MonotileElement::Decoration));
+    }
+
+    if let Some(switcher) = frame.switcher {
+        switcher::elements(&mut ctx, mon, switcher, windows);
+    }
+    stats::elements(&mut ctx, mon);
+    let hints = hints::elements(ctx.renderer, mon, windows, scale);
+    ctx.elems.extend(hints);
+    osd::elements(&mut ctx, mon);
+    if frame.input_inhibited {
+        osd::inhibit_banner(&mut ctx, mon);
+    }
+
     let view = mon.views.front();
+    let fullscreen = view.and_then(|v| v.fullscreen);
 
-    if let Some(we) = view
-        .and_then(|v| v.fullscreen)
-        .and_then(|id| windows.get(id))
+    if let Some(we) = fullscreen.and_then(|id| windows.get(id))
+        && !we.fake_fullscreen
     {
         let geo = mon.geometry();
         ctx.layer_popups(&[Layer::Overlay]);
@@ -224,6 +373,15 @@ pub fn output_elements(
 
         let content = we.render_content(ctx.renderer, geo.loc, ctx.scale, Kind::ScanoutCandidate);
         ctx.elems.extend(content);
+    } else if let Some(id) = fullscreen
+        && let Some(geo) = mon.window_rect(windows, id)
+        && let Some(we) = windows.get_mut(id)
+    {
+        // fake fullscreen, the window alone in the layout area
+        ctx.layer_popups(&[Layer::Overlay, Layer::Top, Layer::Bottom, Layer::Background]);
+        ctx.layers(&[Layer::Overlay, Layer::Top]);
+        we.render_elements(&mut ctx, geo, true);
+        ctx.layers(&[Layer::Bottom, Layer::Background]);
     } else {
         ctx.layer_popups(&[Layer::Overlay, Layer::Top, Layer::Bottom, Layer::Background]);
         ctx.layers(&[Layer::Overlay, Layer::Top]);
@@ -238,7 +396,27 @@ pub fn output_elements(
     ctx.elems
 }
 
+// time between frames at the output's current refresh rate, 60Hz if unknown
+pub fn frame_interval(output: &Output) -> Duration {
+    let refresh = output
+        .current_mode()
+        .map(|m| m.refresh)
+        .filter(|&r| r > 0)
+        .unwrap_or(60_000);
+    // refresh is in mHz
+    Duration::from_nanos(1_000_000_000_000 / refresh as u64)
+}
+
 impl State {
+    // shortest frame interval of any output, for animations spanning all of them
+    pub fn frame_interval(&self) -> Duration {
+        self.monitors
+            .iter()
+            .map(|m| frame_interval(&m.output))
+            .min()
+            .unwrap_or(Duration::from_micros(16_667))
+    }
+
     pub fn send_frame_callbacks(&mut self, output: &Output, throttle: Option<Duration>) {
         let Some((idx, _)) = self.monitors.by_output(output) else {
             return;
@@ -254,10 +432,23 @@ impl State {
                 Some(output.clone())
             });
         }
-        for id in self.monitors[idx].tag().window_ids() {
-            if let Some(we) = self.windows.get_mut(id)
-                && we.buffer_committed
-            {
+        let visible = self.monitors[idx].unoccluded_window_ids(&self.windows);
+        let mut ids = self.monitors[idx].tag().window_ids();
+        // captured windows keep drawing for the stream, covered or on another tag
+        let cast: Vec<_> = self
+            .windows
+            .live()
+            .filter(|we| we.monitor == idx && we.screencasts > 0 && !ids.contains(&we.id))
+            .map(|we| we.id)
+            .collect();
+        ids.extend(cast);
+        for id in ids {
+            let Some(we) = self.windows.get_mut(id) else {
+                continue;
+            };
+            we.flush_pending_configure();
+            // occluded windows keep buffer_committed and resume once uncovered
+            if we.buffer_committed && (visible.contains(&id) || we.screencasts > 0) {
                 we.buffer_committed = false;
                 we.window
                     .send_frame(output, elapsed, throttle, |_, _| Some(output.clone()));
@@ -339,3 +530,45 @@ fn damage_to_buffer(
             .collect()
     })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // compiles anything but sources containing "bad", returns the source
+    fn compile(src: &str) -> Result<String, ()> {
+        if src.contains("bad") { Err(()) } else { Ok(src.to_string()) }
+    }
+
+    #[test]
+    fn shader_files_replace_the_builtin_unless_they_fail() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("shadow.frag"), "custom").unwrap();
+        std::fs::write(dir.path().join("rounded_rect.frag"), "bad").unwrap();
+        let dir = Some(dir.path());
+
+        let shadow = compile_override(dir, "shadow.frag", "builtin", compile);
+        assert_eq!(shadow.as_deref(), Some("custom"));
+        let rect = compile_override(dir, "rounded_rect.frag", "builtin", compile);
+        assert_eq!(rect.as_deref(), Some("builtin"), "rejected file");
+        let clip = compile_override(dir, "clipped_surface.frag", "builtin", compile);
+        assert_eq!(clip.as_deref(), Some("builtin"), "no file");
+        let none = compile_override(None, "shadow.frag", "builtin", compile);
+        assert_eq!(none.as_deref(), Some("builtin"), "no directory");
+    }
+
+    #[test]
+    fn rejected_builtin_shader_disables_its_effect() {
+        let shadow = compile_override(None, "shadow.frag", "bad builtin", compile);
+        assert!(shadow.is_none(), "no panic, the effect is left out");
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("shadow.frag"), "custom").unwrap();
+        let shadow = compile_override(Some(dir.path()), "shadow.frag", "bad builtin", compile);
+        assert_eq!(
+            shadow.as_deref(),
+            Some("custom"),
+            "a working file still helps"
+        );
+    }
+}
@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::time::{Duration, Instant};
+
+use smithay::{
+    desktop::layer_map_for_output,
+    reexports::calloop::timer::{TimeoutAction, Timer},
+    utils::{Logical, Point, Rectangle},
+};
+
+use super::{MonotileElement, RenderCtx, border, frame_interval, text};
+use crate::{
+    Monotile,
+    config::{LayoutMode, OsdTrigger},
+    shell::Monitor,
+};
+
+const FADE: Duration = Duration::from_millis(200);
+const PADDING: i32 = 12;
+const RADIUS: f32 = 8.0;
+const FG: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const BG: [f32; 4] = [0.1, 0.1, 0.1, 0.85];
+const INHIBITED: &str = "input disabled";
+
+// transient label on a monitor
+#[derive(Debug)]
+pub struct Osd {
+    pub text: String,
+    // output local center, the middle of the output if unset
+    pub center: Option<Point<i32, Logical>>,
+    shown: Instant,
+    duration: Duration,
+}
+
+impl Osd {
+    // opacity, None once faded out
+    fn alpha(&self) -> Option<f32> {
+        let elapsed = self.shown.elapsed();
+        let fading = elapsed.checked_sub(self.duration)?;
+        (fading < FADE).then(|| 1.0 - fading.as_secs_f32() / FADE.as_secs_f32())
+    }
+}
+
+impl Monotile {
+    // the state an action changed, on the active monitor
+    pub fn show_trigger_osd(&mut self, trigger: OsdTrigger) {
+        let Some(mon) = self.state.mon() else {
+            return;
+        };
+        let layout = &mon.tag().layout;
+        let text = match trigger {
+            OsdTrigger::Layout => layout.name().to_string(),
+            OsdTrigger::MainRatio | OsdTrigger::MainCount => {
+                let ratio = layout.main_factor * 100.0;
+                format!("{ratio:.0}% / {}", layout.main_count)
+            }
+            OsdTrigger::Tag => mon.settings.tags[mon.active_tag].clone(),
+        };
+        let split = matches!(trigger, OsdTrigger::MainRatio | OsdTrigger::MainCount);
+        let center = (split && self.state.config.osd.at_split).then(|| split_center(mon));
+        self.show_osd_at(text, center);
+    }
+
+    // show text on the active monitor, replacing the current osd
+    pub fn show_osd(&mut self, text: String) {
+        self.show_osd_at(text, None);
+    }
+
+    fn show_osd_at(&mut self, text: String, center: Option<Point<i32, Logical>>) {
+        let duration = Duration::from_millis(self.state.config.osd.duration);
+        if duration.is_zero() {
+            return;
+        }
+        let idx = self.state.active_monitor;
+        let Some(mon) = self.state.monitors.get_mut(idx) else {
+            return;
+        };
+        mon.osd = Some(Osd {
+            text,
+            center,
+            shown: Instant::now(),
+            duration,
+        });
+        let output = mon.output.clone();
+        self.backend.schedule_render(&output);
+
+        if let Some(token) = mon.osd_timer.take() {
+            self.state.loop_handle.remove(token);
+        }
+        let timer = Timer::from_duration(duration);
+        let token = self
+            .state
+            .loop_handle
+            .insert_source(timer, move |_, _, mt| {
+                let Some((idx, _)) = mt.state.monitors.by_output(&output) else {
+                    return TimeoutAction::Drop;
+                };
+                let mon = &mut mt.state.monitors[idx];
+                let done = mon.osd.as_ref().is_none_or(|o| o.alpha().is_none());
+                if done {
+                    mon.osd = None;
+                    mon.osd_timer = None;
+                }
+                mt.backend.schedule_render(&output);
+                if done {
+                    return TimeoutAction::Drop;
+                }
+                // one repaint per frame while fading out
+                TimeoutAction::ToDuration(frame_interval(&output))
+            });
+        match token {
+            Ok(token) => self.state.monitors[idx].osd_timer = Some(token),
+            Err(err) => tracing::warn!(?err, "failed to schedule osd timeout"),
+        }
+    }
+}
+
+// middle of the boundary between the main and stack areas
+fn split_center(mon: &Monitor) -> Point<i32, Logical> {
+    let area = layer_map_for_output(&mon.output).non_exclusive_zone();
+    let layout = &mon.tag().layout;
+    let x = match layout.mode {
+        // the main column is centered
+        LayoutMode::CenteredMaster => area.size.w / 2,
+        _ if layout.mirrored => (area.size.w as f32 * (1.0 - layout.main_factor)) as i32,
+        _ => (area.size.w as f32 * layout.main_factor) as i32,
+    };
+    area.loc + Point::from((x, area.size.h / 2))
+}
+
+pub fn elements(ctx: &mut RenderCtx, mon: &Monitor) {
+    let Some(osd) = &mon.osd else {
+        return;
+    };
+    let Some(alpha) = osd.alpha() else {
+        return;
+    };
+    let area = mon.geometry().size;
+    let center = osd.center.unwrap_or((area.w / 2, area.h / 2).into());
+    label(ctx, &osd.text, center, alpha);
+}
+
+// stays at the top of every output while input is inhibited
+pub fn inhibit_banner(ctx: &mut RenderCtx, mon: &Monitor) {
+    let (_, h) = text::measure(INHIBITED);
+    let area = layer_map_for_output(&mon.output).non_exclusive_zone();
+    let center = area.loc + Point::from((area.size.w / 2, PADDING + h / 2 + PADDING));
+    label(ctx, INHIBITED, center, 1.0);
+}
+
+// text in a rounded box centered on an output local point
+fn label(ctx: &mut RenderCtx, msg: &str, center: Point<i32, Logical>, alpha: f32) {
+    let (w, h) = text::measure(msg);
+    let size = (w + 2 * PADDING, h + 2 * PADDING);
+    let rect: Rectangle<i32, Logical> = Rectangle::new(
+        (center.x - size.0 / 2, center.y - size.1 / 2).into(),
+        size.into(),
+    );
+
+    let label_loc = rect.loc + (PADDING, PADDING).into();
+    let label = text::draw(ctx.renderer, msg, label_loc, FG, alpha, ctx.scale);
+    ctx.elems.extend(label);
+
+    let scale = ctx.scale.x as f32;
+    let bg = border::filled_element(
+        &ctx.shaders.rect,
+        rect,
+        RADIUS,
+        BG,
+        alpha,
+        scale,
+        ctx.layout.corner_feather,
+    );
+    ctx.elems.push(MonotileElement::Decoration(bg));
+}
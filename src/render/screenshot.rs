@@ -0,0 +1,201 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use smithay::{
+    backend::{
+        allocator::Fourcc,
+        renderer::{
+            Bind, Color32F, ExportMem, Offscreen,
+            damage::OutputDamageTracker,
+            element::{Kind, RenderElement},
+            gles::GlesTexture,
+            glow::GlowRenderer,
+        },
+    },
+    output::{Output, WeakOutput},
+    utils::{Buffer as BufferCoords, Logical, Physical, Rectangle, Size, Transform},
+};
+use tracing::{info, warn};
+
+use crate::{shell::WindowId, state::State};
+
+#[derive(Debug)]
+pub struct Screenshot {
+    pub output: WeakOutput,
+    pub path: PathBuf,
+    // logical, relative to the output or the window
+    pub region: Option<Rectangle<i32, Logical>>,
+    pub cursor: bool,
+    // capture only this window's surface tree
+    pub window: Option<WindowId>,
+}
+
+// name inside dir, None unless it is a plain file name, so a client can not
+// write outside the screenshot directory
+pub fn screenshot_path(dir: &Path, name: Option<&str>) -> Option<PathBuf> {
+    let name = match name {
+        Some(name) => name.to_string(),
+        None => {
+            let ms = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis());
+            format!("screenshot-{ms}.png")
+        }
+    };
+    let plain = Path::new(&name)
+        .file_name()
+        .is_some_and(|f| f == name.as_str());
+    (plain && !name.starts_with('.')).then(|| dir.join(name))
+}
+
+// pixels read back from the GPU, encoded and written off the render loop
+struct Capture {
+    pixels: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+// writes the screenshots queued for this output, the first cursor_count elements are the cursor
+pub fn capture<E: RenderElement<GlowRenderer>>(
+    renderer: &mut GlowRenderer,
+    state: &mut State,
+    output: &Output,
+    output_elems: &[E],
+    cursor_count: usize,
+    background: impl Into<Color32F> + Copy,
+) {
+    let weak = output.downgrade();
+    let (shots, rest): (Vec<_>, Vec<_>) = std::mem::take(&mut state.screenshots)
+        .into_iter()
+        .partition(|s| s.output == weak);
+    state.screenshots = rest;
+
+    for shot in shots {
+        let result = match shot.window {
+            Some(id) => capture_window(renderer, state, id, &shot),
+            None => capture_output(
+                renderer,
+                output,
+                output_elems,
+                cursor_count,
+                background,
+                &shot,
+            ),
+        };
+        let capture = match result {
+            Ok(capture) => capture,
+            Err(e) => {
+                warn!("screenshot failed: {e:#}");
+                continue;
+            }
+        };
+        // png encoding takes longer than a frame on large outputs
+        let path = shot.path;
+        std::thread::spawn(move || match save_png(&path, &capture) {
+            Ok(()) => info!("screenshot saved to {}", path.display()),
+            Err(e) => warn!("screenshot failed: {e:#}"),
+        });
+    }
+}
+
+fn capture_output<E: RenderElement<GlowRenderer>>(
+    renderer: &mut GlowRenderer,
+    output: &Output,
+    elems: &[E],
+    cursor_count: usize,
+    background: impl Into<Color32F> + Copy,
+    shot: &Screenshot,
+) -> anyhow::Result<Capture> {
+    let mode = output.current_mode().context("output has no mode")?;
+    let scale = output.current_scale().fractional_scale();
+    // render upright, independent of the output transform
+    let size = output.current_transform().transform_size(mode.size);
+    let elems = if shot.cursor { elems } else { &elems[cursor_count..] };
+    read_pixels(renderer, elems, background, size, scale, shot)
+}
+
+fn capture_window(
+    renderer: &mut GlowRenderer,
+    state: &mut State,
+    id: WindowId,
+    shot: &Screenshot,
+) -> anyhow::Result<Capture> {
+    let we = state.windows.get(id).context("window is gone")?;
+    let mon = state
+        .monitors
+        .get(we.monitor)
+        .context("window has no monitor")?;
+    let scale = mon.output.current_scale().fractional_scale();
+    let size = we
+        .window
+        .geometry()
+        .size
+        .to_f64()
+        .to_physical(scale)
+        .to_i32_round();
+
+    let mut elems = we.render_content(renderer, (0, 0).into(), scale.into(), Kind::Unspecified);
+    if shot.cursor
+        && let Some(ptr) = state.seat.get_pointer()
+    {
+        let window_loc = mon
+            .window_rect(&state.windows, id)
+            .map(|r| r.loc)
+            .unwrap_or_default();
+        let local_pos = ptr.current_location() - window_loc.to_f64();
+        elems.splice(0..0, state.cursor.elements(renderer, local_pos, scale));
+    }
+    read_pixels(renderer, &elems, Color32F::TRANSPARENT, size, scale, shot)
+}
+
+fn read_pixels<E: RenderElement<GlowRenderer>>(
+    renderer: &mut GlowRenderer,
+    elems: &[E],
+    background: impl Into<Color32F> + Copy,
+    size: Size<i32, Physical>,
+    scale: f64,
+    shot: &Screenshot,
+) -> anyhow::Result<Capture> {
+    let mut tracker = OutputDamageTracker::new(size, scale, Transform::Normal);
+    let buffer_size: Size<i32, BufferCoords> = (size.w, size.h).into();
+    let full = Rectangle::from_size(buffer_size);
+    let rect = match shot.region {
+        Some(r) => {
+            let r = r.to_f64().to_physical(scale).to_i32_round();
+            Rectangle::new((r.loc.x, r.loc.y).into(), (r.size.w, r.size.h).into())
+                .intersection(full)
+                .context("region is outside of the capture")?
+        }
+        None => full,
+    };
+
+    let mut tex: GlesTexture = renderer.create_buffer(Fourcc::Abgr8888, buffer_size)?;
+    let mut fb = renderer.bind(&mut tex)?;
+    tracker.render_output(renderer, &mut fb, 0, elems, background)?;
+    let mapping = renderer.copy_framebuffer(&fb, rect, Fourcc::Abgr8888)?;
+    let pixels = renderer.map_texture(&mapping)?;
+    Ok(Capture {
+        pixels: pixels.to_vec(),
+        width: rect.size.w as u32,
+        height: rect.size.h as u32,
+    })
+}
+
+fn save_png(path: &Path, capture: &Capture) -> anyhow::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    let file = File::create(path).with_context(|| format!("creating {}", path.display()))?;
+    let mut enc = png::Encoder::new(BufWriter::new(file), capture.width, capture.height);
+    enc.set_color(png::ColorType::Rgba);
+    enc.set_depth(png::BitDepth::Eight);
+    enc.write_header()?.write_image_data(&capture.pixels)?;
+    Ok(())
+}
@@ -28,6 +28,7 @@ uniform float tint;
 uniform vec2  geo_size;
 uniform float inner_radius;
 uniform float scale;
+uniform float feather;
 uniform mat3  input_to_geo;
 
 float rounding_alpha(vec2 p, vec2 sz, float r, float half_px) {
@@ -49,7 +50,8 @@ float rounding_alpha(vec2 p, vec2 sz, float r, float half_px) {
 }
 
 void main() {
-    float half_px = 0.5 / scale;
+    // smoothstep is undefined for an empty edge, 0 and below are a hard one
+    float half_px = 0.5 * max(feather, 1e-3) / scale;
     vec3 geo = input_to_geo * vec3(v_coords, 1.0);
 
     vec4 color = texture2D(tex, v_coords);
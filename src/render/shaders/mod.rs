@@ -3,3 +3,5 @@
 pub const ROUNDED_RECT_FRAG: &str = include_str!("rounded_rect.frag");
 pub const SHADOW_FRAG: &str = include_str!("shadow.frag");
 pub const CLIPPED_SURFACE_FRAG: &str = include_str!("clipped_surface.frag");
+// used when ROUNDED_RECT_FRAG fails to compile
+pub const PLAIN_RECT_FRAG: &str = include_str!("plain_rect.frag");
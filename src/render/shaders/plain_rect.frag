@@ -0,0 +1,34 @@
+// SPDX-License-Identifier: GPL-3.0-only
+// Square cornered stand-in for rounded_rect.frag on drivers rejecting it
+
+precision mediump float;
+
+uniform float alpha;
+uniform vec2 size;
+varying vec2 v_coords;
+
+#ifdef DEBUG_FLAGS
+uniform float tint;
+#endif
+
+uniform vec2  outer_size;
+uniform float border_width;
+uniform vec4  border_color;
+uniform vec2  piece_offset;
+
+void main() {
+    vec2 px = v_coords * size + piece_offset;
+
+    // border_width == 0 creates a filled rect
+    bool inner = border_width > 0.0
+        && all(greaterThanEqual(px, vec2(border_width)))
+        && all(lessThanEqual(px, outer_size - vec2(border_width)));
+    vec4 color = inner ? vec4(0.0) : border_color * alpha;
+
+#ifdef DEBUG_FLAGS
+    if (tint == 1.0)
+        color = vec4(0.0, 0.2, 0.0, 0.2) + color * 0.8;
+#endif
+
+    gl_FragColor = color;
+}
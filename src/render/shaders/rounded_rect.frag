@@ -17,6 +17,7 @@ uniform float outer_radius;
 uniform vec4  border_color;
 uniform vec2  piece_offset;
 uniform float scale;
+uniform float feather;
 
 float rounding_alpha(vec2 p, vec2 sz, float r, float half_px) {
     if (r <= 0.0) return 1.0;
@@ -37,7 +38,8 @@ float rounding_alpha(vec2 p, vec2 sz, float r, float half_px) {
 }
 
 void main() {
-    float half_px = 0.5 / scale;
+    // smoothstep is undefined for an empty edge, 0 and below are a hard one
+    float half_px = 0.5 * max(feather, 1e-3) / scale;
     vec2 px = v_coords * size + piece_offset;
 
     // outer shape
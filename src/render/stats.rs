@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// frame rate and timing overlay for debugging rendering performance
+
+use std::time::{Duration, Instant};
+
+use smithay::{
+    reexports::calloop::timer::{TimeoutAction, Timer},
+    utils::{Logical, Rectangle},
+};
+
+use super::{MonotileElement, RenderCtx, border, text};
+use crate::{Monotile, shell::Monitor};
+
+// how often the numbers change, the overlay repaints at least this often
+const UPDATE: Duration = Duration::from_millis(500);
+const MARGIN: i32 = 8;
+const PADDING: i32 = 6;
+const RADIUS: f32 = 4.0;
+const FG: [f32; 4] = [0.6, 1.0, 0.6, 1.0];
+const BG: [f32; 4] = [0.0, 0.0, 0.0, 0.75];
+
+#[derive(Debug)]
+pub struct RenderStats {
+    // frames rendered since the text was last updated
+    frames: u32,
+    since: Instant,
+    text: String,
+}
+
+impl Default for RenderStats {
+    fn default() -> Self {
+        Self {
+            frames: 0,
+            since: Instant::now(),
+            text: "- fps".to_string(),
+        }
+    }
+}
+
+impl RenderStats {
+    // how long a frame took to build and submit
+    pub fn record(&mut self, took: Duration, elements: usize, windows: usize) {
+        self.frames += 1;
+        let elapsed = self.since.elapsed();
+        if elapsed < UPDATE {
+            return;
+        }
+        let fps = self.frames as f32 / elapsed.as_secs_f32();
+        let ms = took.as_secs_f32() * 1000.0;
+        self.text = format!("{fps:.0} fps  {ms:.2} ms  {elements} elements  {windows} windows");
+        self.frames = 0;
+        self.since = Instant::now();
+    }
+}
+
+impl Monotile {
+    // show or hide the overlay on every monitor
+    pub fn toggle_render_stats(&mut self) {
+        let show = self.state.monitors.iter().all(|m| m.stats.is_none());
+        for mon in self.state.monitors.iter_mut() {
+            mon.stats = show.then(RenderStats::default);
+        }
+        self.backend.schedule_render_all();
+
+        if let Some(token) = self.state.stats_timer.take() {
+            self.state.loop_handle.remove(token);
+        }
+        if !show {
+            return;
+        }
+        // keep the numbers current while nothing else causes a repaint
+        let timer = Timer::from_duration(UPDATE);
+        let token = self.state.loop_handle.insert_source(timer, |_, _, mt| {
+            let outputs: Vec<_> = mt
+                .state
+                .monitors
+                .iter()
+                .filter(|m| m.stats.is_some())
+                .map(|m| m.output.clone())
+                .collect();
+            for output in &outputs {
+                mt.backend.schedule_render(output);
+            }
+            TimeoutAction::ToDuration(UPDATE)
+        });
+        match token {
+            Ok(token) => self.state.stats_timer = Some(token),
+            Err(err) => tracing::warn!(?err, "failed to schedule render stats updates"),
+        }
+    }
+}
+
+// top left corner of the output, above everything else
+pub fn elements(ctx: &mut RenderCtx, mon: &Monitor) {
+    let Some(stats) = &mon.stats else {
+        return;
+    };
+    let (w, h) = text::measure(&stats.text);
+    let rect: Rectangle<i32, Logical> = Rectangle::new(
+        (MARGIN, MARGIN).into(),
+        (w + 2 * PADDING, h + 2 * PADDING).into(),
+    );
+
+    let label_loc = rect.loc + (PADDING, PADDING).into();
+    let label = text::draw(ctx.renderer, &stats.text, label_loc, FG, 1.0, ctx.scale);
+    ctx.elems.extend(label);
+
+    let scale = ctx.scale.x as f32;
+    let bg = border::filled_element(
+        &ctx.shaders.rect,
+        rect,
+        RADIUS,
+        BG,
+        1.0,
+        scale,
+        ctx.layout.corner_feather,
+    );
+    ctx.elems.push(MonotileElement::Decoration(bg));
+}
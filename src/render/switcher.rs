@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// window switcher: the focus stack listed on the active output while the
+// SwitchWindow modifiers are held, releasing them focuses the selection
+
+use smithay::utils::{Logical, Rectangle};
+
+use super::{MonotileElement, RenderCtx, border, text};
+use crate::{
+    Monotile,
+    shell::{Monitor, WindowId, Windows},
+};
+
+const PADDING: i32 = 12;
+const ROW_GAP: i32 = 4;
+const RADIUS: f32 = 8.0;
+// longest title shown, in chars
+const MAX_TITLE: usize = 60;
+const FG: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+const BG: [f32; 4] = [0.1, 0.1, 0.1, 0.9];
+const SELECTED: [f32; 4] = [0.27, 0.52, 0.53, 1.0];
+
+#[derive(Debug)]
+pub struct Switcher {
+    // most recently focused first
+    pub windows: Vec<WindowId>,
+    pub selected: usize,
+}
+
+impl Switcher {
+    // starting delta steps away from the focused window
+    pub fn new(windows: Vec<WindowId>, delta: i32) -> Self {
+        let mut switcher = Self {
+            windows,
+            selected: 0,
+        };
+        switcher.advance(delta);
+        switcher
+    }
+
+    pub fn advance(&mut self, delta: i32) {
+        let n = self.windows.len() as i32;
+        if n > 0 {
+            self.selected = (self.selected as i32 + delta).rem_euclid(n) as usize;
+        }
+    }
+
+    pub fn selection(&self) -> Option<WindowId> {
+        self.windows.get(self.selected).copied()
+    }
+}
+
+impl Monotile {
+    // open the switcher or move its selection
+    pub fn switch_window(&mut self, delta: i32) {
+        if let Some(switcher) = &mut self.state.switcher {
+            switcher.advance(delta);
+        } else {
+            let Some(mon) = self.state.mon() else {
+                return;
+            };
+            let windows = mon.tag().focus_stack.clone();
+            if windows.len() < 2 {
+                return;
+            }
+            self.state.switcher = Some(Switcher::new(windows, delta));
+        }
+        if let Some(mon) = self.state.mon() {
+            self.backend.schedule_render(&mon.output);
+        }
+    }
+
+    // close the switcher, focusing its selection unless cancelled
+    pub fn finish_switch(&mut self, commit: bool) {
+        let Some(switcher) = self.state.switcher.take() else {
+            return;
+        };
+        if let Some(id) = switcher.selection().filter(|_| commit)
+            && self.state.windows.live().any(|we| we.id == id)
+            && let Some(mon) = self.state.mon_mut()
+            && mon.tag().contains(id)
+        {
+            mon.tag_mut().raise(id);
+            self.set_focus(Some(id));
+        }
+        self.recompute_layout(self.state.active_monitor);
+    }
+}
+
+fn label(windows: &Windows, id: WindowId) -> Option<String> {
+    let we = windows.get(id)?;
+    let title = if we.title.is_empty() { &we.app_id } else { &we.title };
+    Some(title.chars().take(MAX_TITLE).collect())
+}
+
+// a row per window in the middle of the output, the selection highlighted
+pub fn elements(ctx: &mut RenderCtx, mon: &Monitor, switcher: &Switcher, windows: &Windows) {
+    let rows: Vec<(bool, String)> = switcher
+        .windows
+        .iter()
+        .enumerate()
+        .filter_map(|(i, &id)| Some((i == switcher.selected, label(windows, id)?)))
+        .collect();
+    if rows.is_empty() {
+        return;
+    }
+    let row_h = rows
+        .iter()
+        .map(|(_, t)| text::measure(t).1)
+        .max()
+        .unwrap_or(0);
+    let text_w = rows
+        .iter()
+        .map(|(_, t)| text::measure(t).0)
+        .max()
+        .unwrap_or(0);
+    let n = rows.len() as i32;
+    let size = (
+        text_w + 2 * PADDING,
+        n * (row_h + 2 * ROW_GAP) + 2 * PADDING - 2 * ROW_GAP,
+    );
+    let area = mon.geometry().size;
+    let rect: Rectangle<i32, Logical> = Rectangle::new(
+        ((area.w - size.0) / 2, (area.h - size.1) / 2).into(),
+        size.into(),
+    );
+
+    let scale = ctx.scale.x as f32;
+    let mut y = rect.loc.y + PADDING;
+    for (selected, title) in &rows {
+        let loc = (rect.loc.x + PADDING, y).into();
+        ctx.elems
+            .extend(text::draw(ctx.renderer, title, loc, FG, 1.0, ctx.scale));
+        if *selected {
+            let row = Rectangle::new(
+                (rect.loc.x + PADDING / 2, y - ROW_GAP).into(),
+                (size.0 - PADDING, row_h + 2 * ROW_GAP).into(),
+            );
+            let hl = border::filled_element(
+                &ctx.shaders.rect,
+                row,
+                RADIUS / 2.0,
+                SELECTED,
+                1.0,
+                scale,
+                ctx.layout.corner_feather,
+            );
+            ctx.elems.push(MonotileElement::Decoration(hl));
+        }
+        y += row_h + 2 * ROW_GAP;
+    }
+    let bg = border::filled_element(
+        &ctx.shaders.rect,
+        rect,
+        RADIUS,
+        BG,
+        1.0,
+        scale,
+        ctx.layout.corner_feather,
+    );
+    ctx.elems.push(MonotileElement::Decoration(bg));
+}
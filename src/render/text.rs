@@ -0,0 +1,267 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// text labels for compositor drawn overlays. With the text feature glyphs are
+// rasterized from a system TTF font, otherwise the built-in bitmap font is used.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use smithay::{
+    backend::renderer::{
+        element::{
+            Element, Id, Kind, RenderElement, UnderlyingStorage,
+            memory::{MemoryRenderBuffer, MemoryRenderBufferRenderElement},
+        },
+        gles::GlesError,
+        glow::{GlowFrame, GlowRenderer},
+        utils::{CommitCounter, DamageSet, OpaqueRegions},
+    },
+    utils::{
+        Buffer, Logical, Physical, Point, Rectangle, Scale, Transform, user_data::UserDataMap,
+    },
+};
+
+use super::MonotileElement;
+
+// logical height of a line of text
+pub const TEXT_SIZE: i32 = 16;
+// rendered labels kept around so unchanged text is not uploaded every frame
+const CACHE_SIZE: usize = 64;
+
+type CacheKey = (String, [u8; 4], i32);
+
+thread_local! {
+    static CACHE: RefCell<HashMap<CacheKey, MemoryRenderBuffer>> = RefCell::new(HashMap::new());
+}
+
+// text with its top left corner at pos, color is rgba
+pub fn draw(
+    renderer: &mut GlowRenderer,
+    text: &str,
+    pos: Point<i32, Logical>,
+    color: [f32; 4],
+    alpha: f32,
+    scale: Scale<f64>,
+) -> Option<MonotileElement> {
+    let buffer_scale = scale.x.ceil() as i32;
+    let rgba = color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    let key = (text.to_string(), rgba, buffer_scale);
+    let buffer = CACHE.with_borrow_mut(|cache| {
+        if cache.len() >= CACHE_SIZE && !cache.contains_key(&key) {
+            cache.clear();
+        }
+        cache
+            .entry(key)
+            .or_insert_with(|| rasterize(text, rgba, buffer_scale))
+            .clone()
+    });
+    match MemoryRenderBufferRenderElement::from_buffer(
+        renderer,
+        pos.to_physical_precise_round(scale),
+        &buffer,
+        Some(alpha),
+        None,
+        None,
+        Kind::Unspecified,
+    ) {
+        Ok(elem) => Some(MonotileElement::Text(TextElement(elem))),
+        Err(err) => {
+            tracing::warn!(?err, "failed to upload text");
+            None
+        }
+    }
+}
+
+// a rendered label, its own element kind so text can be told apart from
+// other memory buffers
+#[derive(Debug)]
+pub struct TextElement(MemoryRenderBufferRenderElement<GlowRenderer>);
+
+impl Element for TextElement {
+    fn id(&self) -> &Id {
+        self.0.id()
+    }
+    fn current_commit(&self) -> CommitCounter {
+        self.0.current_commit()
+    }
+    fn geometry(&self, scale: Scale<f64>) -> Rectangle<i32, Physical> {
+        self.0.geometry(scale)
+    }
+    fn src(&self) -> Rectangle<f64, Buffer> {
+        self.0.src()
+    }
+    fn transform(&self) -> Transform {
+        self.0.transform()
+    }
+    fn alpha(&self) -> f32 {
+        self.0.alpha()
+    }
+    fn kind(&self) -> Kind {
+        self.0.kind()
+    }
+    fn damage_since(
+        &self,
+        scale: Scale<f64>,
+        commit: Option<CommitCounter>,
+    ) -> DamageSet<i32, Physical> {
+        self.0.damage_since(scale, commit)
+    }
+    fn opaque_regions(&self, scale: Scale<f64>) -> OpaqueRegions<i32, Physical> {
+        self.0.opaque_regions(scale)
+    }
+}
+
+impl RenderElement<GlowRenderer> for TextElement {
+    fn draw(
+        &self,
+        frame: &mut GlowFrame<'_, '_>,
+        src: Rectangle<f64, Buffer>,
+        dst: Rectangle<i32, Physical>,
+        damage: &[Rectangle<i32, Physical>],
+        opaque: &[Rectangle<i32, Physical>],
+        cache: Option<&UserDataMap>,
+    ) -> Result<(), GlesError> {
+        RenderElement::<GlowRenderer>::draw(&self.0, frame, src, dst, damage, opaque, cache)
+    }
+
+    fn underlying_storage(&self, renderer: &mut GlowRenderer) -> Option<UnderlyingStorage<'_>> {
+        self.0.underlying_storage(renderer)
+    }
+}
+
+// logical size of the text as drawn
+pub fn measure(text: &str) -> (i32, i32) {
+    #[cfg(feature = "text")]
+    if let Some(w) = ttf::measure(text) {
+        return (w, TEXT_SIZE);
+    }
+    let (px, w) = bitmap_metrics(text);
+    (w * px, (super::font::GLYPH_H + 2) * px)
+}
+
+fn rasterize(text: &str, rgba: [u8; 4], scale: i32) -> MemoryRenderBuffer {
+    #[cfg(feature = "text")]
+    if let Some(buffer) = ttf::rasterize(text, rgba, scale) {
+        return buffer;
+    }
+    let (px, _) = bitmap_metrics(text);
+    super::font::label(text, px * scale, rgba, [0; 4], scale)
+}
+
+// font pixel size and width in font pixels of the bitmap rendering
+fn bitmap_metrics(text: &str) -> (i32, i32) {
+    use super::font::{GLYPH_H, GLYPH_W};
+    let rows = GLYPH_H + 2;
+    let px = ((TEXT_SIZE + rows / 2) / rows).max(1);
+    let w = text.chars().count() as i32 * (GLYPH_W + 1) + 1;
+    (px, w)
+}
+
+#[cfg(feature = "text")]
+mod ttf {
+    use std::sync::OnceLock;
+
+    use smithay::{
+        backend::{allocator::Fourcc, renderer::element::memory::MemoryRenderBuffer},
+        utils::Transform,
+    };
+
+    use super::TEXT_SIZE;
+
+    const FONT_PATHS: &[&str] = &[
+        "/usr/share/fonts/TTF/DejaVuSans.ttf",
+        "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+        "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+        "/usr/share/fonts/noto/NotoSans-Regular.ttf",
+        "/usr/share/fonts/truetype/noto/NotoSans-Regular.ttf",
+    ];
+
+    fn font() -> Option<&'static fontdue::Font> {
+        static FONT: OnceLock<Option<fontdue::Font>> = OnceLock::new();
+        FONT.get_or_init(|| {
+            let font = FONT_PATHS.iter().find_map(|path| {
+                let bytes = std::fs::read(path).ok()?;
+                fontdue::Font::from_bytes(bytes, fontdue::FontSettings::default()).ok()
+            });
+            if font.is_none() {
+                tracing::warn!("no TTF font found, using the bitmap font");
+            }
+            font
+        })
+        .as_ref()
+    }
+
+    pub fn measure(text: &str) -> Option<i32> {
+        let font = font()?;
+        let size = TEXT_SIZE as f32;
+        let w: f32 = text
+            .chars()
+            .map(|c| font.metrics(c, size).advance_width)
+            .sum();
+        Some(w.ceil() as i32)
+    }
+
+    pub fn rasterize(text: &str, rgba: [u8; 4], scale: i32) -> Option<MemoryRenderBuffer> {
+        let font = font()?;
+        let size = (TEXT_SIZE * scale) as f32;
+        let ascent = font.horizontal_line_metrics(size)?.ascent.round() as i32;
+        let advance: f32 = text
+            .chars()
+            .map(|c| font.metrics(c, size).advance_width)
+            .sum();
+        // whole logical pixels so the buffer size divides by the scale
+        let w = (advance / scale as f32).ceil() as i32 * scale;
+        let h = TEXT_SIZE * scale;
+
+        // premultiplied argb, coverage scales every channel
+        let mut data = vec![0u8; (w * h * 4) as usize];
+        let [r, g, b, a] = rgba.map(u32::from);
+        let mut pen = 0.0f32;
+        for c in text.chars() {
+            let (m, coverage) = font.rasterize(c, size);
+            let x0 = pen.round() as i32 + m.xmin;
+            let y0 = ascent - m.height as i32 - m.ymin;
+            for (i, &cov) in coverage.iter().enumerate() {
+                let (x, y) = (x0 + (i % m.width) as i32, y0 + (i / m.width) as i32);
+                if cov == 0 || x < 0 || y < 0 || x >= w || y >= h {
+                    continue;
+                }
+                let cov = u32::from(cov) * a / 255;
+                let idx = ((y * w + x) * 4) as usize;
+                let px = [b * cov / 255, g * cov / 255, r * cov / 255, cov];
+                for (dst, src) in data[idx..idx + 4].iter_mut().zip(px) {
+                    *dst = (*dst).max(src as u8);
+                }
+            }
+            pen += m.advance_width;
+        }
+        Some(MemoryRenderBuffer::from_slice(
+            &data,
+            Fourcc::Argb8888,
+            (w, h),
+            scale,
+            Transform::Normal,
+            None,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_grow_with_their_text_on_one_line_height() {
+        let (short_w, short_h) = measure("ab");
+        let (long_w, long_h) = measure("abcd efgh");
+        assert!(long_w > short_w, "{long_w} <= {short_w}");
+        assert_eq!(short_h, long_h);
+        assert!(short_h >= TEXT_SIZE);
+    }
+
+    #[test]
+    fn bitmap_font_leaves_a_pixel_between_glyphs() {
+        let (px, w) = bitmap_metrics("abc");
+        assert_eq!(px, 2, "9 font rows fill about TEXT_SIZE");
+        assert_eq!(w, 3 * (crate::render::font::GLYPH_W + 1) + 1);
+    }
+}
@@ -18,7 +18,7 @@ use smithay::{
 };
 
 use super::{
-    MonotileElement, RenderCtx, border,
+    BorderRing, MonotileElement, RenderCtx, border,
     clipped_surface::{Clippable, Clipped},
     popup_elements,
 };
@@ -33,11 +33,15 @@ pub enum RenderStep {
         width: i32,
         color: [f32; 4],
         elements: Vec<PixelShaderElement>,
+        // single_pass_borders draws this instead of the pieces
+        ring: Option<BorderRing>,
     },
     WindowSurface {
-        fill: [f32; 4],
+        // None follows the output background
+        fill: Option<[f32; 4]>,
         radius: f32,
-        background: Option<PixelShaderElement>,
+        // with the color it was built for
+        background: Option<([f32; 4], PixelShaderElement)>,
     },
     Shadow {
         softness: i32,
@@ -56,9 +60,10 @@ impl RenderStep {
                 width: *width,
                 color: color.0,
                 elements: Vec::new(),
+                ring: None,
             },
             config::RenderStep::WindowSurface { fill, radius } => Self::WindowSurface {
-                fill: fill.0,
+                fill: fill.map(|c| c.0),
                 radius: *radius,
                 background: None,
             },
@@ -79,7 +84,10 @@ impl RenderStep {
 
     fn clear(&mut self) {
         match self {
-            Self::Border { elements, .. } => elements.clear(),
+            Self::Border { elements, ring, .. } => {
+                elements.clear();
+                *ring = None;
+            }
             Self::WindowSurface { background, .. } => *background = None,
             Self::Shadow { element, .. } => *element = None,
         }
@@ -99,19 +107,36 @@ impl RenderStep {
                 width,
                 color,
                 elements,
+                ring,
             } => {
-                if elements.is_empty() {
-                    *elements = border::create_elements(
-                        &ctx.shaders.rect,
-                        win_geo,
-                        radius,
-                        *width,
-                        *color,
-                        scale_f32,
-                    );
-                }
-                for d in elements.iter() {
-                    ctx.elems.push(MonotileElement::Decoration(d.clone()));
+                if ctx.layout.single_pass_borders {
+                    let ring = ring.get_or_insert_with(|| {
+                        BorderRing::new(
+                            &ctx.shaders.rect,
+                            win_geo,
+                            radius,
+                            *width,
+                            *color,
+                            scale_f32,
+                            ctx.layout.corner_feather,
+                        )
+                    });
+                    ctx.elems.push(MonotileElement::Border(ring.clone()));
+                } else {
+                    if elements.is_empty() {
+                        *elements = border::create_elements(
+                            &ctx.shaders.rect,
+                            win_geo,
+                            radius,
+                            *width,
+                            *color,
+                            scale_f32,
+                            ctx.layout.corner_feather,
+                        );
+                    }
+                    for d in elements.iter() {
+                        ctx.elems.push(MonotileElement::Decoration(d.clone()));
+                    }
                 }
             }
             RenderStep::WindowSurface {
@@ -123,15 +148,21 @@ impl RenderStep {
                 for clippable in content.drain(..) {
                     ctx.elems.push(Clipped::wrap(
                         clippable,
-                        &ctx.shaders.clip,
+                        ctx.shaders.clip.as_ref(),
                         win_geo,
                         clip_r,
                         ctx.scale,
+                        ctx.layout.corner_feather,
                     ));
                 }
-                if !surface_fills_win {
-                    let bg = background.get_or_insert_with(|| {
-                        PixelShaderElement::new(
+                let color = fill.unwrap_or(ctx.background);
+                // a transparent fill lets the output background show through
+                if !surface_fills_win && color[3] > 0.0 {
+                    if background.as_ref().is_some_and(|(c, _)| *c != color) {
+                        *background = None;
+                    }
+                    let (_, bg) = background.get_or_insert_with(|| {
+                        let elem = PixelShaderElement::new(
                             ctx.shaders.rect.clone(),
                             win_geo,
                             None,
@@ -143,12 +174,14 @@ impl RenderStep {
                                 ),
                                 Uniform::new("border_width", 0.0f32),
                                 Uniform::new("outer_radius", clip_r),
-                                Uniform::new("border_color", *fill),
+                                Uniform::new("border_color", color),
                                 Uniform::new("piece_offset", (0.0f32, 0.0f32)),
                                 Uniform::new("scale", scale_f32),
+                                Uniform::new("feather", ctx.layout.corner_feather),
                             ],
                             Kind::Unspecified,
-                        )
+                        );
+                        (color, elem)
                     });
                     ctx.elems.push(MonotileElement::Decoration(bg.clone()));
                 } else {
@@ -162,6 +195,9 @@ impl RenderStep {
                 color,
                 element,
             } => {
+                let Some(program) = &ctx.shaders.shadow else {
+                    return;
+                };
                 let sigma = *softness as f32 / 2.0;
                 let blur = (sigma * 3.0).ceil() as i32;
                 let pad_x = blur + *spread + offset.0.abs();
@@ -172,7 +208,7 @@ impl RenderStep {
                 );
                 let shadow = element.get_or_insert_with(|| {
                     PixelShaderElement::new(
-                        ctx.shaders.shadow.clone(),
+                        program.clone(),
                         rect,
                         None,
                         1.0,
@@ -218,6 +254,15 @@ impl WindowElement {
         (wl.alive() && has_buffer).then_some(wl)
     }
 
+    // drop everything tied to the current renderer
+    pub fn release_textures(&mut self) {
+        for step in self.render_steps.values_mut() {
+            step.clear();
+        }
+        self.last_texture = None;
+        self.texture_dirty = true;
+    }
+
     fn sync_render_cache(&mut self, win_geo: Rectangle<i32, Logical>) {
         if win_geo != self.cache_geo {
             for step in self.render_steps.values_mut() {
@@ -292,7 +337,9 @@ impl WindowElement {
             return;
         }
 
-        let surface_fills_win = live.is_some() && self.window.geometry().size == win_geo.size;
+        // undecorated windows get no background behind the surface either
+        let surface_fills_win =
+            !self.decorated || live.is_some() && self.window.geometry().size == win_geo.size;
 
         if let Some(wl) = &live {
             let popups = popup_elements(ctx.renderer, wl, win_geo.loc, ctx.scale);
@@ -335,16 +382,44 @@ impl WindowElement {
 impl View {
     pub fn render_elements(&self, ctx: &mut RenderCtx, windows: &mut Windows) {
         let lone = self.tiled.len() == 1;
+        let modal_parents = windows.modal_parents();
         for &id in self.floating.iter().rev() {
+            let blocked = modal_parents.contains(&id);
             if let Some(we) = windows.get_mut(id) {
                 let geo = we.float_geo;
+                if blocked {
+                    modal_shade(ctx, geo, we.radius);
+                }
                 we.render_elements(ctx, geo, false);
             }
         }
         for tile in self.tiled.iter().rev() {
+            let blocked = modal_parents.contains(&tile.id);
             if let Some(we) = windows.get_mut(tile.id) {
-                we.render_elements(ctx, tile.rect, lone);
+                let rect = we.committed_rect(tile.rect);
+                if blocked {
+                    modal_shade(ctx, rect, we.radius);
+                }
+                we.render_elements(ctx, rect, lone);
             }
         }
     }
 }
+
+// darkens a window while its modal dialog is open
+fn modal_shade(ctx: &mut RenderCtx, rect: Rectangle<i32, Logical>, radius: f32) {
+    if ctx.modal_dim <= 0.0 {
+        return;
+    }
+    let (color, scale) = ([0.0, 0.0, 0.0, 1.0], ctx.scale.x as f32);
+    let shade = border::filled_element(
+        &ctx.shaders.rect,
+        rect,
+        radius,
+        color,
+        ctx.modal_dim,
+        scale,
+        ctx.layout.corner_feather,
+    );
+    ctx.elems.push(MonotileElement::Decoration(shade));
+}
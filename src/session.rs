@@ -0,0 +1,157 @@
+// SPDX-License-Identifier: GPL-3.0-only
+
+// which tags windows were on, saved so relaunched apps return to them
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    path::PathBuf,
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+use smithay::reexports::calloop::timer::{TimeoutAction, Timer};
+use tracing::{info, warn};
+
+use crate::{Monotile, state::State};
+
+// how often the session is written, so it survives a crash
+pub const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    pub output: String,
+    pub tags: Vec<usize>,
+}
+
+// placements by app_id, one per window
+type Entries = BTreeMap<String, Vec<Entry>>;
+
+#[derive(Debug)]
+pub struct Session {
+    path: PathBuf,
+    // saved placements no relaunched window has claimed yet, oldest first
+    restore: HashMap<String, VecDeque<Entry>>,
+}
+
+impl Session {
+    // None without $XDG_STATE_HOME or $HOME, there is nowhere to keep it
+    pub fn default_path() -> Option<PathBuf> {
+        Some(dirs::state_dir()?.join("monotile/session.ron"))
+    }
+
+    // a missing or unreadable file starts an empty session
+    pub fn load(path: PathBuf) -> Self {
+        let restore = match std::fs::read_to_string(&path) {
+            Ok(text) => match ron::from_str::<Entries>(&text) {
+                Ok(entries) => {
+                    info!("session: {}", path.display());
+                    entries
+                        .into_iter()
+                        .map(|(app_id, e)| (app_id, e.into()))
+                        .collect()
+                }
+                Err(e) => {
+                    warn!("{}: {e}", path.display());
+                    HashMap::new()
+                }
+            },
+            Err(_) => HashMap::new(),
+        };
+        Self { path, restore }
+    }
+
+    // placement for a newly mapped window, each saved one is used once
+    pub fn take(&mut self, app_id: &str) -> Option<Entry> {
+        let queue = self.restore.get_mut(app_id)?;
+        let entry = queue.pop_front();
+        if queue.is_empty() {
+            self.restore.remove(app_id);
+        }
+        entry
+    }
+
+    // unclaimed placements are kept until their app is launched again
+    fn save(&self, mut entries: Entries) {
+        for (app_id, queue) in &self.restore {
+            entries
+                .entry(app_id.clone())
+                .or_default()
+                .extend(queue.iter().cloned());
+        }
+        let text = match ron::ser::to_string_pretty(&entries, Default::default()) {
+            Ok(text) => text,
+            Err(e) => {
+                warn!("session: {e}");
+                return;
+            }
+        };
+        if let Some(dir) = self.path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        if let Err(e) = std::fs::write(&self.path, text) {
+            warn!("{}: {e}", self.path.display());
+        }
+    }
+}
+
+impl State {
+    pub fn save_session(&self) {
+        let Some(session) = &self.session else {
+            return;
+        };
+        let mut entries = Entries::new();
+        for we in self.windows.live() {
+            let Some(mon) = self.monitors.get(we.monitor) else {
+                continue;
+            };
+            let tags: Vec<usize> = (0..mon.tags.len())
+                .filter(|&t| mon.tags[t].contains(we.id))
+                .collect();
+            if we.app_id.is_empty() || tags.is_empty() {
+                continue;
+            }
+            let output = mon.output.name();
+            entries
+                .entry(we.app_id.clone())
+                .or_default()
+                .push(Entry { output, tags });
+        }
+        session.save(entries);
+    }
+
+    // opens or drops the session when the config toggles it
+    pub fn update_session(&mut self) {
+        if self.config.session == self.session.is_some() {
+            return;
+        }
+        if !self.config.session {
+            self.session = None;
+            return;
+        }
+        match Session::default_path() {
+            Some(path) => self.session = Some(Session::load(path)),
+            None => warn!("session: no state directory, not saving"),
+        }
+    }
+}
+
+impl Monotile {
+    // periodic saves while the session is enabled
+    pub fn arm_session_timer(&mut self) {
+        if let Some(token) = self.state.session_timer.take() {
+            self.state.loop_handle.remove(token);
+        }
+        if self.state.session.is_none() {
+            return;
+        }
+        let timer = Timer::from_duration(SAVE_INTERVAL);
+        let token = self.state.loop_handle.insert_source(timer, |_, _, mt| {
+            mt.state.save_session();
+            TimeoutAction::ToDuration(SAVE_INTERVAL)
+        });
+        match token {
+            Ok(token) => self.state.session_timer = Some(token),
+            Err(err) => warn!(?err, "failed to schedule session saves"),
+        }
+    }
+}
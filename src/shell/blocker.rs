@@ -81,7 +81,7 @@ fn serial_acked(surface: &WlSurface, serial: Serial) -> bool {
     })
 }
 
-fn serial_committed(surface: &WlSurface, serial: Serial) -> bool {
+pub(super) fn serial_committed(surface: &WlSurface, serial: Serial) -> bool {
     with_states(surface, |states| {
         states
             .cached_state
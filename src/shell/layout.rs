@@ -1,8 +1,10 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
+use std::time::{Duration, Instant};
+
 use smithay::utils::{Logical, Rectangle};
 
-use crate::config::{self, Rel};
+use crate::config::{self, Direction, LayoutMode, Rel};
 
 use super::{Tile, WindowId};
 
@@ -10,8 +12,17 @@ use super::{Tile, WindowId};
 pub struct TilingLayout {
     pub main_count: usize,
     pub main_factor: f32,
+    pub mode: LayoutMode,
+    // main area on the right instead of the left
+    pub mirrored: bool,
     pub config: config::Layout,
     tiles: Vec<Tile>,
+    // main window displaced by the last zoom
+    zoomed_from: Option<WindowId>,
+    // slot of the last closed tile, reused by the next add
+    freed_slot: Option<(usize, Instant)>,
+    // slots of tiles that were floated, taken again when they tile
+    detached: Vec<(WindowId, usize)>,
 }
 
 impl Default for TilingLayout {
@@ -19,19 +30,27 @@ impl Default for TilingLayout {
         Self {
             main_count: config::TileConfig::default().main_count,
             main_factor: config::TileConfig::default().main_factor,
+            mode: LayoutMode::default(),
+            mirrored: false,
             config: config::Layout::default(),
             tiles: Vec::new(),
+            zoomed_from: None,
+            freed_slot: None,
+            detached: Vec::new(),
         }
     }
 }
 
 impl TilingLayout {
     pub fn name(&self) -> &str {
-        "tile"
+        self.mode.name()
     }
 
     pub fn symbol(&self) -> &str {
-        "[]="
+        match self.mode {
+            LayoutMode::Tile if self.mirrored => "=[]",
+            mode => mode.symbol(),
+        }
     }
 
     pub fn tiles(&self) -> &[Tile] {
@@ -55,16 +74,55 @@ impl TilingLayout {
     }
 
     pub fn add(&mut self, id: WindowId) {
-        if !self.contains(id) {
-            self.tiles.push(Tile {
-                id,
-                rect: Rectangle::default(),
-            });
+        if self.contains(id) {
+            return;
+        }
+        let tile = Tile {
+            id,
+            rect: Rectangle::default(),
+            weight: 1.0,
+        };
+        if let Some(pos) = self.detached.iter().position(|&(d, _)| d == id) {
+            let (_, idx) = self.detached.remove(pos);
+            self.tiles.insert(idx.min(self.tiles.len()), tile);
+            return;
+        }
+        let timeout = Duration::from_millis(self.config.reuse_slot_timeout);
+        match self.freed_slot.take() {
+            Some((idx, at)) if at.elapsed() < timeout => {
+                self.tiles.insert(idx.min(self.tiles.len()), tile);
+            }
+            _ => self.tiles.push(tile),
         }
     }
 
     pub fn remove(&mut self, id: WindowId) {
         self.tiles.retain(|t| t.id != id);
+        self.detached.retain(|&(d, _)| d != id);
+    }
+
+    // new takes the tile of old
+    pub fn replace(&mut self, old: WindowId, new: WindowId) {
+        self.remove(new);
+        if let Some(tile) = self.tiles.iter_mut().find(|t| t.id == old) {
+            tile.id = new;
+        }
+    }
+
+    // like remove, but the window returns to the same slot when added again
+    pub fn detach(&mut self, id: WindowId) {
+        if let Some(idx) = self.tiles.iter().position(|t| t.id == id) {
+            self.tiles.remove(idx);
+            self.detached.push((id, idx));
+        }
+    }
+
+    // like remove, but remembers the slot for a replacement window
+    pub fn close(&mut self, id: WindowId) {
+        if let Some(idx) = self.tiles.iter().position(|t| t.id == id) {
+            self.tiles.remove(idx);
+            self.freed_slot = Some((idx, Instant::now()));
+        }
     }
 
     pub fn retain(&mut self, mut keep: impl FnMut(WindowId) -> bool) {
@@ -75,15 +133,23 @@ impl TilingLayout {
         self.tiles.iter().map(|t| t.id)
     }
 
+    // index of the tile rel to cur, None past the ends when not wrapping
+    fn step(&self, cur: usize, to: Rel) -> Option<usize> {
+        let n = self.tiles.len();
+        let wrap = self.config.focus_wrap;
+        match to {
+            Rel::Next if cur + 1 < n => Some(cur + 1),
+            Rel::Next => wrap.then_some(0),
+            Rel::Prev if cur > 0 => Some(cur - 1),
+            Rel::Prev => wrap.then_some(n - 1),
+            Rel::First => Some(0),
+            Rel::Last => Some(n - 1),
+        }
+    }
+
     pub fn target(&self, from: WindowId, to: Rel) -> Option<WindowId> {
         let cur = self.tiles.iter().position(|t| t.id == from)?;
-        let n = self.tiles.len();
-        let idx = match to {
-            Rel::Next => (cur + 1) % n,
-            Rel::Prev => (cur + n - 1) % n,
-            Rel::First => 0,
-            Rel::Last => n - 1,
-        };
+        let idx = self.step(cur, to)?;
         Some(self.tiles[idx].id)
     }
 
@@ -91,18 +157,36 @@ impl TilingLayout {
         let Some(cur) = self.tiles.iter().position(|t| t.id == from) else {
             return;
         };
-        let n = self.tiles.len();
-        let target = match to {
-            Rel::Next => (cur + 1) % n,
-            Rel::Prev => (cur + n - 1) % n,
-            Rel::First => 0,
-            Rel::Last => n - 1,
+        let Some(target) = self.step(cur, to) else {
+            return;
         };
         if cur != target {
             self.tiles.swap(cur, target);
         }
     }
 
+    // dwm zoom: promote to main, zooming the main again swaps the displaced
+    // window back, or the next one once that is main itself or gone
+    pub fn zoom(&mut self, id: WindowId) {
+        let Some(cur) = self.tiles.iter().position(|t| t.id == id) else {
+            return;
+        };
+        let target = if cur == 0 {
+            let prev = self
+                .zoomed_from
+                .and_then(|p| self.tiles.iter().position(|t| t.id == p))
+                .filter(|&p| p != 0);
+            match prev.or((self.tiles.len() > 1).then_some(1)) {
+                Some(idx) => idx,
+                None => return,
+            }
+        } else {
+            0
+        };
+        self.zoomed_from = Some(self.tiles[0].id);
+        self.tiles.swap(cur, target);
+    }
+
     pub fn adjust_main_factor(&mut self, delta: f32) {
         self.main_factor = (self.main_factor + delta).clamp(0.1, 0.9);
     }
@@ -111,6 +195,66 @@ impl TilingLayout {
         self.main_factor = ratio.clamp(0.1, 0.9);
     }
 
+    // back to the configured split and equal tile heights
+    pub fn balance(&mut self) {
+        self.main_factor = self.config.tile.main_factor;
+        for tile in &mut self.tiles {
+            tile.weight = 1.0;
+        }
+    }
+
+    // balance and the configured main count
+    pub fn reset(&mut self) {
+        self.balance();
+        self.main_count = self.config.tile.main_count;
+    }
+
+    // Left/Right move the main/stack split, Up/Down shrink or grow the tile by
+    // delta of its column height
+    pub fn resize_tile(&mut self, id: WindowId, dir: Direction, delta: f32) {
+        match dir {
+            // the split moves the way of the key, mirrored or not
+            Direction::Left if self.mirrored => self.adjust_main_factor(delta),
+            Direction::Right if self.mirrored => self.adjust_main_factor(-delta),
+            Direction::Left => self.adjust_main_factor(-delta),
+            Direction::Right => self.adjust_main_factor(delta),
+            Direction::Up => self.grow_tile(id, -delta),
+            Direction::Down => self.grow_tile(id, delta),
+        }
+    }
+
+    fn grow_tile(&mut self, id: WindowId, delta: f32) {
+        let Some(idx) = self.tiles.iter().position(|t| t.id == id) else {
+            return;
+        };
+        let column = self.column_of(idx);
+        if column.len() < 2 {
+            return;
+        }
+        let total: f32 = column.iter().map(|&i| self.tiles[i].weight).sum();
+        let weight = self.tiles[idx].weight;
+        let share = (weight / total + delta).clamp(0.05, 0.95);
+        // the other tiles keep their proportions
+        self.tiles[idx].weight = share * (total - weight) / (1.0 - share);
+    }
+
+    // indices of the tiles stacked in the same column as idx
+    fn column_of(&self, idx: usize) -> Vec<usize> {
+        let count = self.tiles.len();
+        let main_count = self.main_count.min(count);
+        let stack_count = count - main_count;
+        match self.mode {
+            // every window has its own split
+            LayoutMode::Spiral => vec![idx],
+            _ if idx < main_count => (0..main_count).collect(),
+            // stack windows alternate between the two side columns
+            LayoutMode::CenteredMaster if stack_count > 1 => (main_count..count)
+                .filter(|i| (i - main_count) % 2 == (idx - main_count) % 2)
+                .collect(),
+            _ => (main_count..count).collect(),
+        }
+    }
+
     pub fn adjust_main_count(&mut self, delta: i32) {
         self.main_count = (self.main_count as i32 + delta).max(1) as usize;
     }
@@ -119,6 +263,11 @@ impl TilingLayout {
         self.main_count = count.max(1);
     }
 
+    // area covered by fake fullscreen windows
+    pub fn work_area(&self, area: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        inset(area, self.config.outer_gap)
+    }
+
     pub fn recompute(&mut self, area: Rectangle<i32, Logical>) {
         let rects = self.compute_rects(self.tiles.len(), area);
         for (tile, rect) in self.tiles.iter_mut().zip(rects) {
@@ -137,60 +286,178 @@ impl TilingLayout {
 
         let main_count = self.main_count.min(count);
         let stack_count = count - main_count;
+        // rects for tiles not added yet use the default weight
+        let weights: Vec<f32> = (0..count)
+            .map(|i| self.tiles.get(i).map_or(1.0, |t| t.weight))
+            .collect();
 
         let disable_gaps = self.config.smart_gaps && count == 1;
         let outer = if disable_gaps { 0 } else { self.config.outer_gap };
         let inner = if disable_gaps { 0 } else { self.config.inner_gap };
 
-        let usable = if outer == 0 {
-            area
-        } else {
-            Rectangle {
-                loc: (area.loc.x + outer, area.loc.y + outer).into(),
-                size: (area.size.w - 2 * outer, area.size.h - 2 * outer).into(),
+        let usable = inset(area, outer);
+
+        let mut rects = match self.mode {
+            LayoutMode::Spiral => self.spiral_rects(count, usable, inner),
+            _ if stack_count == 0 => Self::stack_rects(&weights, usable, inner),
+            // a single stack window gets a plain two column split
+            LayoutMode::CenteredMaster if stack_count > 1 => {
+                self.centered_rects(&weights, main_count, usable, inner)
             }
+            _ => self.tile_rects(&weights, main_count, usable, inner),
         };
+        if self.mirrored {
+            let right = 2 * usable.loc.x + usable.size.w;
+            for rect in &mut rects {
+                rect.loc.x = right - rect.loc.x - rect.size.w;
+            }
+        }
+        rects
+    }
 
-        if stack_count == 0 {
-            Self::stack_rects(count, usable, inner)
-        } else {
-            let half = inner / 2;
-            let mw = (usable.size.w as f32 * self.main_factor) as i32;
-            let main_area = Rectangle {
-                loc: usable.loc,
-                size: (mw - half, usable.size.h).into(),
-            };
-            let stack_area = Rectangle {
-                loc: (usable.loc.x + mw + inner - half, usable.loc.y).into(),
-                size: (usable.size.w - mw - inner + half, usable.size.h).into(),
+    fn tile_rects(
+        &self,
+        weights: &[f32],
+        main_count: usize,
+        usable: Rectangle<i32, Logical>,
+        inner: i32,
+    ) -> Vec<Rectangle<i32, Logical>> {
+        let half = inner / 2;
+        let mw = (usable.size.w as f32 * self.main_factor) as i32;
+        let main_area = Rectangle {
+            loc: usable.loc,
+            size: (mw - half, usable.size.h).into(),
+        };
+        let stack_area = Rectangle {
+            loc: (usable.loc.x + mw + inner - half, usable.loc.y).into(),
+            size: (usable.size.w - mw - inner + half, usable.size.h).into(),
+        };
+        let (main, stack) = weights.split_at(main_count);
+        let mut rects = Self::stack_rects(main, main_area, inner);
+        rects.extend(Self::stack_rects(stack, stack_area, inner));
+        rects
+    }
+
+    // main column in the middle, stack windows alternate right and left of it
+    fn centered_rects(
+        &self,
+        weights: &[f32],
+        main_count: usize,
+        usable: Rectangle<i32, Logical>,
+        inner: i32,
+    ) -> Vec<Rectangle<i32, Logical>> {
+        let (x, y, h) = (usable.loc.x, usable.loc.y, usable.size.h);
+        let mw = (usable.size.w as f32 * self.main_factor) as i32;
+        let sides = usable.size.w - mw - 2 * inner;
+        let left_w = sides / 2;
+        let right_w = sides - left_w;
+
+        let left = Rectangle::new((x, y).into(), (left_w, h).into());
+        let main = Rectangle::new((x + left_w + inner, y).into(), (mw, h).into());
+        let right = Rectangle::new((x + left_w + mw + 2 * inner, y).into(), (right_w, h).into());
+
+        let (main_weights, stack) = weights.split_at(main_count);
+        let right_weights: Vec<f32> = stack.iter().copied().step_by(2).collect();
+        let left_weights: Vec<f32> = stack.iter().copied().skip(1).step_by(2).collect();
+        let mut rights = Self::stack_rects(&right_weights, right, inner).into_iter();
+        let mut lefts = Self::stack_rects(&left_weights, left, inner).into_iter();
+
+        let mut rects = Self::stack_rects(main_weights, main, inner);
+        for i in 0..stack.len() {
+            let side = if i % 2 == 0 { &mut rights } else { &mut lefts };
+            rects.extend(side.next());
+        }
+        rects
+    }
+
+    // fibonacci spiral: each window takes part of the remaining area, the
+    // split side rotates left, top, right, bottom
+    fn spiral_rects(
+        &self,
+        count: usize,
+        area: Rectangle<i32, Logical>,
+        gap: i32,
+    ) -> Vec<Rectangle<i32, Logical>> {
+        let mut rects = Vec::with_capacity(count);
+        let mut rest = area;
+        for i in 0..count - 1 {
+            let factor = if i == 0 { self.main_factor } else { 0.5 };
+            let half = gap / 2;
+            let (loc, size) = (rest.loc, rest.size);
+            let (win, next) = if i % 2 == 0 {
+                let w = (size.w as f32 * factor) as i32;
+                let (win_x, next_x) = if i % 4 == 0 {
+                    (loc.x, loc.x + w + gap - half)
+                } else {
+                    (loc.x + size.w - w + half, loc.x)
+                };
+                (
+                    Rectangle::new((win_x, loc.y).into(), (w - half, size.h).into()),
+                    Rectangle::new(
+                        (next_x, loc.y).into(),
+                        (size.w - w - gap + half, size.h).into(),
+                    ),
+                )
+            } else {
+                let h = (size.h as f32 * factor) as i32;
+                let (win_y, next_y) = if i % 4 == 1 {
+                    (loc.y, loc.y + h + gap - half)
+                } else {
+                    (loc.y + size.h - h + half, loc.y)
+                };
+                (
+                    Rectangle::new((loc.x, win_y).into(), (size.w, h - half).into()),
+                    Rectangle::new(
+                        (loc.x, next_y).into(),
+                        (size.w, size.h - h - gap + half).into(),
+                    ),
+                )
             };
-            let mut rects = Self::stack_rects(main_count, main_area, inner);
-            rects.extend(Self::stack_rects(stack_count, stack_area, inner));
-            rects
+            rects.push(win);
+            rest = next;
         }
+        rects.push(rest);
+        rects
     }
 
+    // one column, heights proportional to the weights
     fn stack_rects(
-        count: usize,
+        weights: &[f32],
         area: Rectangle<i32, Logical>,
         gap: i32,
     ) -> Vec<Rectangle<i32, Logical>> {
+        let count = weights.len();
         if count == 0 {
             return vec![];
         }
         let gap_total = gap * (count as i32 - 1);
-        let h = (area.size.h - gap_total) / count as i32;
-        (0..count)
-            .map(|i| {
-                let y = area.loc.y + i as i32 * (h + gap);
+        let free = (area.size.h - gap_total) as f32;
+        let total: f32 = weights.iter().sum();
+        let mut y = area.loc.y;
+        weights
+            .iter()
+            .enumerate()
+            .map(|(i, weight)| {
                 let remaining_h = area.loc.y + area.size.h - y;
-                let h = if i == count - 1 { remaining_h } else { h };
-                Rectangle::new((area.loc.x, y).into(), (area.size.w, h).into())
+                let h = if i == count - 1 { remaining_h } else { (free * weight / total) as i32 };
+                let rect = Rectangle::new((area.loc.x, y).into(), (area.size.w, h).into());
+                y += h + gap;
+                rect
             })
             .collect()
     }
 }
 
+fn inset(area: Rectangle<i32, Logical>, gap: i32) -> Rectangle<i32, Logical> {
+    if gap == 0 {
+        return area;
+    }
+    Rectangle {
+        loc: (area.loc.x + gap, area.loc.y + gap).into(),
+        size: (area.size.w - 2 * gap, area.size.h - 2 * gap).into(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -222,6 +489,27 @@ mod tests {
         assert_eq!(l.ids().collect::<Vec<_>>(), vec![a, b]);
     }
 
+    #[test]
+    fn add_reuses_closed_slot_when_enabled() {
+        let v = ids(4);
+        let mut l = TilingLayout::default();
+        for &id in &v[..3] {
+            l.add(id);
+        }
+        l.close(v[1]);
+        l.add(v[3]);
+        assert_eq!(l.ids().collect::<Vec<_>>(), vec![v[0], v[2], v[3]]);
+
+        let mut l = TilingLayout::default();
+        l.config.reuse_slot_timeout = 60_000;
+        for &id in &v[..3] {
+            l.add(id);
+        }
+        l.close(v[1]);
+        l.add(v[3]);
+        assert_eq!(l.ids().collect::<Vec<_>>(), vec![v[0], v[3], v[2]]);
+    }
+
     #[test]
     fn target_cycles_with_rel() {
         let mut l = TilingLayout::default();
@@ -236,6 +524,40 @@ mod tests {
         assert_eq!(l.target(v[1], Rel::Last), Some(v[2]));
     }
 
+    #[test]
+    fn target_stops_at_the_ends_without_wrap() {
+        let mut l = TilingLayout::default();
+        l.config.focus_wrap = false;
+        let v = ids(3);
+        for &id in &v {
+            l.add(id);
+        }
+        assert_eq!(l.target(v[2], Rel::Next), None);
+        assert_eq!(l.target(v[0], Rel::Prev), None);
+        assert_eq!(l.target(v[1], Rel::Next), Some(v[2]));
+        assert_eq!(l.target(v[1], Rel::Prev), Some(v[0]));
+    }
+
+    #[test]
+    fn swap_at_the_ends_wraps_only_when_enabled() {
+        let v = ids(3);
+        let mut l = TilingLayout::default();
+        for &id in &v {
+            l.add(id);
+        }
+        l.swap(v[2], Rel::Next);
+        assert_eq!(l.ids().collect::<Vec<_>>(), vec![v[2], v[1], v[0]]);
+
+        let mut l = TilingLayout::default();
+        l.config.focus_wrap = false;
+        for &id in &v {
+            l.add(id);
+        }
+        l.swap(v[2], Rel::Next);
+        l.swap(v[0], Rel::Prev);
+        assert_eq!(l.ids().collect::<Vec<_>>(), v);
+    }
+
     #[test]
     fn swap_reorders_tiles() {
         let mut l = TilingLayout::default();
@@ -247,6 +569,53 @@ mod tests {
         assert_eq!(l.ids().collect::<Vec<_>>(), vec![v[1], v[0], v[2]]);
     }
 
+    #[test]
+    fn zoom_promotes_and_toggles_back() {
+        let mut l = TilingLayout::default();
+        let v = ids(4);
+        for &id in &v {
+            l.add(id);
+        }
+        l.zoom(v[2]);
+        assert_eq!(l.ids().collect::<Vec<_>>(), vec![v[2], v[1], v[0], v[3]]);
+        l.zoom(v[2]);
+        assert_eq!(l.ids().collect::<Vec<_>>(), v);
+    }
+
+    #[test]
+    fn zoom_main_without_history_swaps_next() {
+        let mut l = TilingLayout::default();
+        let v = ids(3);
+        for &id in &v {
+            l.add(id);
+        }
+        l.zoom(v[0]);
+        assert_eq!(l.ids().collect::<Vec<_>>(), vec![v[1], v[0], v[2]]);
+
+        let mut single = TilingLayout::default();
+        single.add(v[0]);
+        single.zoom(v[0]);
+        assert_eq!(single.ids().collect::<Vec<_>>(), vec![v[0]]);
+    }
+
+    #[test]
+    fn zoom_main_that_was_displaced_promotes_the_next() {
+        let mut l = TilingLayout::default();
+        let v = ids(3);
+        for &id in &v {
+            l.add(id);
+        }
+        l.zoom(v[2]);
+        // the displaced window gets back to main some other way
+        l.swap(v[0], Rel::First);
+        assert_eq!(l.ids().collect::<Vec<_>>(), vec![v[0], v[1], v[2]]);
+
+        l.zoom(v[0]);
+        assert_eq!(l.ids().collect::<Vec<_>>(), vec![v[1], v[0], v[2]]);
+        l.zoom(v[1]);
+        assert_eq!(l.ids().collect::<Vec<_>>(), v, "and back again");
+    }
+
     #[test]
     fn swap_self_is_noop() {
         let mut l = TilingLayout::default();
@@ -281,6 +650,24 @@ mod tests {
         assert_eq!(l.main_count, 4);
     }
 
+    #[test]
+    fn detached_tile_returns_to_its_slot() {
+        let mut l = TilingLayout::default();
+        let v = ids(3);
+        for &id in &v {
+            l.add(id);
+        }
+        l.detach(v[1]);
+        l.add(v[1]);
+        assert_eq!(l.ids().collect::<Vec<_>>(), v);
+
+        // removed windows forget it
+        l.detach(v[0]);
+        l.remove(v[0]);
+        l.add(v[0]);
+        assert_eq!(l.ids().collect::<Vec<_>>(), vec![v[1], v[2], v[0]]);
+    }
+
     const W: i32 = 1000;
     const H: i32 = 800;
 
@@ -427,6 +814,172 @@ mod tests {
         }
     }
 
+    #[test]
+    fn zero_outer_gap_keeps_inner_gaps() {
+        let mut l = with_main(1, 0.5);
+        l.config.outer_gap = 0;
+        l.config.inner_gap = 10;
+        let rects = l.compute_rects(3, area());
+
+        assert_eq!(rects[0].loc, (0, 0).into(), "main touches the edge");
+        assert_eq!(rects[0].size.h, H, "main spans the full height");
+        assert_eq!(rects[1].loc.y, 0, "stack touches the top edge");
+        assert_eq!(
+            rects[1].loc.x + rects[1].size.w,
+            W,
+            "stack touches the right edge"
+        );
+        assert_eq!(
+            rects[2].loc.y + rects[2].size.h,
+            H,
+            "stack touches the bottom edge"
+        );
+        assert_eq!(
+            rects[1].loc.x - (rects[0].loc.x + rects[0].size.w),
+            10,
+            "main and stack are separated by the inner gap",
+        );
+        assert_eq!(
+            rects[2].loc.y - (rects[1].loc.y + rects[1].size.h),
+            10,
+            "stack windows are separated by the inner gap",
+        );
+    }
+
+    fn spiral() -> TilingLayout {
+        let mut l = with_main(1, 0.5);
+        l.mode = LayoutMode::Spiral;
+        l
+    }
+
+    #[test]
+    fn spiral_rotates_splits() {
+        let rects = spiral().compute_rects(4, area());
+        assert_eq!(rects.len(), 4, "should produce 4 rects");
+        assert!(rects[1].loc.x > rects[0].loc.x, "second is right of first");
+        assert!(rects[2].loc.y > rects[1].loc.y, "third is below second");
+        assert!(rects[3].loc.x < rects[2].loc.x, "fourth is left of third");
+        assert_eq!(rects[0].size.h, rects[1].size.h, "first split is vertical");
+    }
+
+    #[test]
+    fn spiral_covers_area_without_overlap() {
+        let c = config::Layout::default();
+        let outer = c.outer_gap;
+        let inner = c.inner_gap;
+        for count in 1..=7 {
+            let rects = spiral().compute_rects(count, area());
+            assert_eq!(rects.len(), count, "count={count}");
+            for i in 0..rects.len() {
+                for j in (i + 1)..rects.len() {
+                    let overlap = rects[i].intersection(rects[j]);
+                    assert!(
+                        overlap.is_none() || overlap.unwrap().is_empty(),
+                        "count={count} rects {i} and {j} overlap",
+                    );
+                }
+            }
+            let covered: i32 = rects.iter().map(|r| r.size.w * r.size.h).sum();
+            let usable = (W - 2 * outer) * (H - 2 * outer);
+            assert!(covered <= usable, "count={count} exceeds usable area");
+        }
+
+        // left half, then top right, then the bottom right quarter split
+        // right to left, with one inner gap between each pair
+        assert_eq!((outer, inner), (2, 4), "expected rects assume the defaults");
+        let rects = spiral().compute_rects(4, area());
+        let expected: [Rectangle<i32, Logical>; 4] = [
+            Rectangle::new((2, 2).into(), (496, 796).into()),
+            Rectangle::new((502, 2).into(), (496, 396).into()),
+            Rectangle::new((752, 402).into(), (246, 396).into()),
+            Rectangle::new((502, 402).into(), (246, 396).into()),
+        ];
+        assert_eq!(rects, expected);
+    }
+
+    #[test]
+    fn centered_master_puts_main_in_the_middle() {
+        let mut l = with_main(1, 0.5);
+        l.mode = LayoutMode::CenteredMaster;
+        let rects = l.compute_rects(5, area());
+        assert_eq!(rects.len(), 5, "should produce 5 rects");
+
+        let main = rects[0];
+        assert!(
+            rects[1].loc.x > main.loc.x,
+            "first stack window is right of main"
+        );
+        assert!(
+            rects[2].loc.x < main.loc.x,
+            "second stack window is left of main"
+        );
+        assert_eq!(rects[1].loc.x, rects[3].loc.x, "right column shares x");
+        assert_eq!(rects[2].loc.x, rects[4].loc.x, "left column shares x");
+        assert_eq!(
+            main.loc.x - (rects[2].loc.x + rects[2].size.w),
+            rects[1].loc.x - (main.loc.x + main.size.w),
+            "main is centered between the columns",
+        );
+
+        for count in 1..=6 {
+            let rects = l.compute_rects(count, area());
+            for i in 0..rects.len() {
+                for j in (i + 1)..rects.len() {
+                    let overlap = rects[i].intersection(rects[j]);
+                    assert!(
+                        overlap.is_none() || overlap.unwrap().is_empty(),
+                        "count={count} rects {i} and {j} overlap",
+                    );
+                }
+                let r = rects[i];
+                assert!(
+                    r.loc.x >= 0 && r.loc.x + r.size.w <= W,
+                    "count={count} rect {i} exceeds area: {r:?}",
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn balance_restores_configured_split() {
+        let mut l = with_main(1, 0.5);
+        l.config.tile.main_factor = 0.6;
+        l.adjust_main_factor(0.2);
+        l.balance();
+        assert_eq!(l.main_factor, 0.6);
+        assert_eq!(l.main_count, 1, "main count is left alone");
+    }
+
+    #[test]
+    fn resize_tile_shares_its_column_by_weight() {
+        let mut l = with_main(1, 0.5);
+        l.config.inner_gap = 0;
+        l.config.outer_gap = 0;
+        let v = ids(3);
+        for &id in &v {
+            l.add(id);
+        }
+        l.resize_tile(v[1], Direction::Down, 0.25);
+        l.recompute(area());
+        let h = |l: &TilingLayout, id| l.position_of(id).unwrap().size.h;
+        assert_eq!((h(&l, v[1]), h(&l, v[2])), (600, 200));
+        assert_eq!(h(&l, v[0]), H, "main column is untouched");
+
+        l.resize_tile(v[2], Direction::Right, 0.1);
+        assert_eq!(l.main_factor, 0.6, "left and right move the split");
+
+        l.balance();
+        l.recompute(area());
+        assert_eq!((h(&l, v[1]), h(&l, v[2])), (400, 400));
+    }
+
+    #[test]
+    fn layout_mode_cycles() {
+        assert_eq!(LayoutMode::Tile.next(), LayoutMode::Spiral);
+        assert_eq!(LayoutMode::Spiral.next(), LayoutMode::CenteredMaster);
+        assert_eq!(LayoutMode::CenteredMaster.next(), LayoutMode::Tile);
+    }
+
     #[test]
     fn all_rects_fit_within_area() {
         for count in 1..=6 {
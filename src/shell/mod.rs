@@ -9,13 +9,27 @@ mod window;
 
 pub use blocker::LayoutBlocker;
 pub use layout::TilingLayout;
-pub use monitor::{Monitor, MonitorSettings, Monitors};
+pub use monitor::{Monitor, MonitorSettings, Monitors, Relation};
 pub use tag::Tag;
 pub use view::{Tile, View, Views};
-pub use window::{Placement, ToplevelSurfaceExt, Unmapped, WindowElement, Windows};
+pub use window::{Flash, Placement, ToplevelSurfaceExt, Unmapped, WindowElement, Windows};
 
 use slotmap::new_key_type;
+use smithay::utils::{Logical, Point, Rectangle};
 
 new_key_type! {
     pub struct WindowId;
 }
+
+// where rect goes to lie inside area, at the area's start on an axis it is
+// larger than the area on
+pub fn clamp_into(
+    rect: Rectangle<i32, Logical>,
+    area: Rectangle<i32, Logical>,
+) -> Point<i32, Logical> {
+    let clamp =
+        |v: i32, start: i32, len: i32, size: i32| v.clamp(start, (start + len - size).max(start));
+    let x = clamp(rect.loc.x, area.loc.x, area.size.w, rect.size.w);
+    let y = clamp(rect.loc.y, area.loc.y, area.size.h, rect.size.h);
+    (x, y).into()
+}
@@ -2,19 +2,25 @@
 
 use derive_more::{Deref, DerefMut};
 use smithay::{
-    desktop::layer_map_for_output,
+    desktop::{WindowSurfaceType, layer_map_for_output},
     output::{Output, Scale},
-    reexports::wayland_server::{backend::GlobalId, protocol::wl_surface::WlSurface},
-    utils::{Logical, Point, Rectangle, Size, Transform},
+    reexports::{
+        calloop::RegistrationToken,
+        wayland_server::{backend::GlobalId, protocol::wl_surface::WlSurface},
+    },
+    utils::{Logical, Physical, Point, Rectangle, Transform},
     wayland::{
         session_lock::LockSurface,
         shell::wlr_layer::{KeyboardInteractivity, Layer},
     },
 };
 
-use crate::config::{self, ModeConfig};
+use crate::{
+    config::{self, Direction, ModeConfig},
+    render::{Hint, Osd, RenderStats},
+};
 
-use super::{Tag, View, Views, WindowId, Windows};
+use super::{Tag, View, Views, WindowId, Windows, clamp_into};
 
 #[derive(Debug)]
 pub struct MonitorSettings {
@@ -23,7 +29,12 @@ pub struct MonitorSettings {
     pub pos: Point<i32, Logical>,
     pub mode: Option<ModeConfig>,
     pub transform: Option<Transform>,
+    pub scale_filter: config::ScaleFilter,
     pub background: [f32; 4],
+    // per tag overrides, indexed like tags
+    pub tag_backgrounds: Vec<[f32; 4]>,
+    // output shown in place of this one's tags, from the output rules
+    pub duplicate: Option<String>,
 }
 
 impl Default for MonitorSettings {
@@ -34,7 +45,10 @@ impl Default for MonitorSettings {
             pos: Point::default(),
             mode: None,
             transform: None,
+            scale_filter: config::ScaleFilter::default(),
             background: [0.0; 4],
+            tag_backgrounds: Vec::new(),
+            duplicate: None,
         }
     }
 }
@@ -61,7 +75,12 @@ impl MonitorSettings {
             }
             s.mode = rule.mode.or(s.mode);
             s.transform = rule.transform.map(Transform::from).or(s.transform);
+            s.scale_filter = rule.scale_filter.unwrap_or(s.scale_filter);
             s.background = rule.background.map_or(s.background, |c| c.0);
+            if let Some(bgs) = &rule.tag_backgrounds {
+                s.tag_backgrounds = bgs.iter().map(|c| c.0).collect();
+            }
+            s.duplicate = rule.duplicate.clone().or(s.duplicate);
         }
         if s.tags.is_empty() {
             s.tags = config::default_tags();
@@ -70,6 +89,13 @@ impl MonitorSettings {
     }
 }
 
+// link between a dialog and the window it belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Relation {
+    Parent,
+    Child,
+}
+
 #[derive(Debug)]
 pub struct Monitor {
     pub output: Output,
@@ -82,6 +108,16 @@ pub struct Monitor {
     pub lock_surface: Option<LockSurface>,
     pub views: Views,
     pub output_geo: Rectangle<i32, Logical>,
+    // window labels while picking a window by key
+    pub hints: Vec<Hint>,
+    pub osd: Option<Osd>,
+    // hides the osd, each output fades its own
+    pub osd_timer: Option<RegistrationToken>,
+    // frame rate overlay, ToggleRenderStats
+    pub stats: Option<RenderStats>,
+    // output shown in place of own tags, the rule's unless DuplicateOutput
+    // toggled it since
+    pub duplicate: Option<String>,
 }
 
 impl Monitor {
@@ -95,10 +131,12 @@ impl Monitor {
         tags.resize_with(settings.tags.len(), Tag::default);
         for tag in &mut tags {
             tag.layout.config = layout.clone();
+            tag.layout.mode = layout.default;
         }
         let mut mon = Self {
             output,
             global,
+            duplicate: settings.duplicate.clone(),
             settings,
             tags,
             active_tag: 0,
@@ -107,11 +145,22 @@ impl Monitor {
             lock_surface: None,
             views: Views::default(),
             output_geo: Rectangle::default(),
+            hints: Vec::new(),
+            osd: None,
+            osd_timer: None,
+            stats: None,
         };
         mon.refresh_geometry();
         mon
     }
 
+    pub fn background(&self) -> [f32; 4] {
+        let bgs = &self.settings.tag_backgrounds;
+        bgs.get(self.active_tag)
+            .copied()
+            .unwrap_or(self.settings.background)
+    }
+
     pub fn tag(&self) -> &Tag {
         &self.tags[self.active_tag]
     }
@@ -120,30 +169,62 @@ impl Monitor {
         &mut self.tags[self.active_tag]
     }
 
-    pub fn map(&mut self, ws: &mut Windows, id: WindowId, tags: Option<Vec<usize>>) {
-        let area = layer_map_for_output(&self.output).non_exclusive_zone();
+    // pointer is output local, for FloatPlacement::UnderMouse
+    pub fn map(
+        &mut self,
+        ws: &mut Windows,
+        id: WindowId,
+        tags: Option<Vec<usize>>,
+        pointer: Point<i32, Logical>,
+    ) {
+        // tags this output does not have fall back to the active one
+        let tags: Vec<usize> = tags
+            .into_iter()
+            .flatten()
+            .filter(|&t| t < self.tags.len())
+            .collect();
+
         let we = &mut ws[id];
+        let area = layer_map_for_output(&self.output).non_exclusive_zone();
+        let tag = &self.tags[tags.first().copied().unwrap_or(self.active_tag)];
+        if we.float_geo.size.w <= 0 || we.float_geo.size.h <= 0 {
+            we.float_geo.size = tag.float_size(area.size);
+        }
+        if we.float_geo.loc == Point::default() {
+            let size = we.float_geo.size;
+            we.float_geo.loc = tag.place_float(area, size, pointer);
+        }
+        if tags.is_empty() {
+            self.tag_mut().add(id);
+        }
+        for t in tags {
+            self.tags[t].add(id);
+        }
+    }
 
-        let has_pos = we.float_geo.loc != Point::default();
-        let Size { w, h, .. } = we.float_geo.size;
-        let x = if has_pos { we.float_geo.loc.x } else { area.loc.x + (area.size.w - w) / 2 };
-        let y = if has_pos { we.float_geo.loc.y } else { area.loc.y + (area.size.h - h) / 2 };
-        we.float_geo.loc = (x, y).into();
+    // configured split and main count on every tag
+    pub fn reset_layouts(&mut self) {
+        for tag in &mut self.tags {
+            tag.layout.reset();
+        }
+    }
 
-        if let Some(tags) = tags {
-            for t in tags {
-                if t < self.tags.len() {
-                    self.tags[t].add(id);
-                }
-            }
-        } else {
-            self.tag_mut().add(id);
+    // main area on the other side, for the active tag only
+    pub fn toggle_mirror(&mut self) {
+        let layout = &mut self.tag_mut().layout;
+        layout.mirrored = !layout.mirrored;
+    }
+
+    // new takes the place of old on every tag holding it, for swallowing
+    pub fn replace_window(&mut self, old: WindowId, new: WindowId) {
+        for tag in &mut self.tags {
+            tag.replace(old, new);
         }
     }
 
     pub fn unmap(&mut self, id: WindowId) {
         for tag in &mut self.tags {
-            tag.remove(id);
+            tag.close(id);
         }
     }
 
@@ -188,10 +269,39 @@ impl Monitor {
         self.active_tag = tag;
     }
 
+    // focus the xdg parent or the most recently focused child of id on the
+    // active tag, returns the newly focused window
+    pub fn focus_related(&mut self, ws: &Windows, id: WindowId, rel: Relation) -> Option<WindowId> {
+        let parent_of = |id: WindowId| {
+            let parent = ws.get(id)?.window.toplevel()?.parent()?;
+            ws.find_by_surface(&parent)
+        };
+        let tag = self.tag_mut();
+        let target = match rel {
+            Relation::Parent => parent_of(id).filter(|&p| tag.contains(p))?,
+            Relation::Child => tag
+                .focus_stack
+                .iter()
+                .copied()
+                .find(|&c| parent_of(c) == Some(id))?,
+        };
+        tag.promote(target);
+        tag.raise(target);
+        Some(target)
+    }
+
     pub fn toggle_prev_tag(&mut self) {
         std::mem::swap(&mut self.active_tag, &mut self.prev_tag);
     }
 
+    // leave an emptied tag for the previous one, unless that is empty too
+    pub fn view_prev_if_empty(&mut self) {
+        let prev = self.tags.get(self.prev_tag);
+        if self.tag().focus_stack.is_empty() && prev.is_some_and(|t| !t.focus_stack.is_empty()) {
+            self.toggle_prev_tag();
+        }
+    }
+
     pub fn geometry(&self) -> Rectangle<i32, Logical> {
         self.output_geo
     }
@@ -201,15 +311,43 @@ impl Monitor {
         self.output_geo = Rectangle::new((0, 0).into(), size.to_logical(1));
     }
 
+    // geometry in the global compositor space
+    pub fn global_geometry(&self) -> Rectangle<i32, Logical> {
+        Rectangle::new(self.output.current_location(), self.output_geo.size)
+    }
+
     pub fn window_rect(&self, ws: &Windows, id: WindowId) -> Option<Rectangle<i32, Logical>> {
         let we = ws.get(id)?;
-        if we.fullscreen {
+        if we.fake_fullscreen {
+            let area = layer_map_for_output(&self.output).non_exclusive_zone();
+            Some(self.tag().layout.work_area(area))
+        } else if we.fullscreen {
             Some(self.geometry())
         } else if we.floating {
             Some(we.float_geo)
         } else {
-            self.tag().layout.position_of(id)
+            self.tag().layout.position_of(id).map(|r| we.locked_rect(r))
+        }
+    }
+
+    // visible windows minus those fully covered by an opaque window stacked above
+    pub fn unoccluded_window_ids(&self, ws: &Windows) -> Vec<WindowId> {
+        let mut covers: Vec<Rectangle<i32, Logical>> = Vec::new();
+        let mut ids = Vec::new();
+        for id in self.tag().window_ids().into_iter().rev() {
+            let Some(rect) = self.window_rect(ws, id) else {
+                continue;
+            };
+            if covers.iter().any(|c| c.contains_rect(rect)) {
+                continue;
+            }
+            ids.push(id);
+            if ws.get(id).is_some_and(|we| we.is_opaque()) {
+                covers.push(rect);
+            }
         }
+        ids.reverse();
+        ids
     }
 
     pub fn recompute_layout(&mut self, ws: &mut Windows) {
@@ -221,6 +359,42 @@ impl Monitor {
         self.views.push_back(view);
     }
 
+    // pull floating windows that lie entirely outside the usable area back into it
+    pub fn rescue_offscreen(&self, ws: &mut Windows) {
+        let area = layer_map_for_output(&self.output).non_exclusive_zone();
+        for id in self.window_ids() {
+            let Some(we) = ws.get_mut(id) else {
+                continue;
+            };
+            if !we.floating || area.overlaps(we.float_geo) {
+                continue;
+            }
+            we.float_geo.loc = clamp_into(we.float_geo, area);
+        }
+    }
+
+    // windows KillTag closes, or with keep those KillOthers closes: the tiled
+    // ones other than keep
+    pub fn close_targets(&self, ws: &Windows, keep: Option<WindowId>) -> Vec<WindowId> {
+        let tiled = |id| ws.get(id).is_some_and(|we| !we.floating);
+        self.tag()
+            .window_ids()
+            .into_iter()
+            .filter(|&id| keep.is_none_or(|k| k != id && tiled(id)))
+            .collect()
+    }
+
+    // ask the close_targets to close like Close does for one, returns how many
+    pub fn close_windows(&self, ws: &Windows, keep: Option<WindowId>) -> usize {
+        let ids = self.close_targets(ws, keep);
+        for &id in &ids {
+            if let Some(tl) = ws.get(id).and_then(|we| we.window.toplevel()) {
+                tl.send_close();
+            }
+        }
+        ids.len()
+    }
+
     pub fn window_ids(&self) -> Vec<WindowId> {
         self.tags
             .iter()
@@ -229,17 +403,28 @@ impl Monitor {
             .collect()
     }
 
-    pub fn update_exclusive_layer(&mut self) {
+    // true if the layer holding the keyboard changed
+    pub fn update_exclusive_layer(&mut self) -> bool {
         let map = layer_map_for_output(&self.output);
-        self.exclusive_layer = None;
-        for l in [Layer::Overlay, Layer::Top] {
-            for s in map.layers_on(l).rev() {
-                if s.cached_state().keyboard_interactivity == KeyboardInteractivity::Exclusive {
-                    self.exclusive_layer = Some(s.wl_surface().clone());
-                    return;
-                }
-            }
-        }
+        let exclusive = [Layer::Overlay, Layer::Top].into_iter().find_map(|l| {
+            map.layers_on(l)
+                .rev()
+                .find(|s| {
+                    s.cached_state().keyboard_interactivity == KeyboardInteractivity::Exclusive
+                })
+                .map(|s| s.wl_surface().clone())
+        });
+        let changed = exclusive != self.exclusive_layer;
+        self.exclusive_layer = exclusive;
+        changed
+    }
+
+    // the layer surface owning surface, if it takes the keyboard when clicked
+    pub fn on_demand_layer(&self, surface: &WlSurface) -> Option<WlSurface> {
+        let map = layer_map_for_output(&self.output);
+        let layer = map.layer_for_surface(surface, WindowSurfaceType::ALL)?;
+        let interactivity = layer.cached_state().keyboard_interactivity;
+        (interactivity == KeyboardInteractivity::OnDemand).then(|| layer.wl_surface().clone())
     }
 }
 
@@ -251,6 +436,123 @@ impl Monitors {
         self.iter().enumerate().find(|(_, m)| m.output == *output)
     }
 
+    // monitor under a point in the global compositor space, duplicates take
+    // no input as they only show their source
+    pub fn at(&self, pos: Point<f64, Logical>) -> Option<usize> {
+        (0..self.len())
+            .find(|&i| !self.is_duplicate(i) && self[i].global_geometry().to_f64().contains(pos))
+    }
+
+    // monitor entered when the pointer leaves monitor idx for global pos and
+    // where it lands: the one under pos, else one sharing the crossed edge
+    // with pos slid along the seam so differing heights don't trap it
+    pub fn across(
+        &self,
+        idx: usize,
+        pos: Point<f64, Logical>,
+    ) -> Option<(usize, Point<f64, Logical>)> {
+        if let Some(i) = self.at(pos).filter(|&i| i != idx) {
+            return Some((i, pos));
+        }
+        let from = self.get(idx)?.global_geometry();
+        let (right, bottom) = (from.loc.x + from.size.w, from.loc.y + from.size.h);
+        let dist = |p: Point<f64, Logical>| (p.x - pos.x).powi(2) + (p.y - pos.y).powi(2);
+        self.iter()
+            .enumerate()
+            .filter(|&(i, _)| i != idx && !self.is_duplicate(i))
+            .map(|(i, m)| (i, m.global_geometry()))
+            .filter(|(_, r)| {
+                (pos.x >= right as f64 && r.loc.x == right)
+                    || (pos.x < from.loc.x as f64 && r.loc.x + r.size.w == from.loc.x)
+                    || (pos.y >= bottom as f64 && r.loc.y == bottom)
+                    || (pos.y < from.loc.y as f64 && r.loc.y + r.size.h == from.loc.y)
+            })
+            .map(|(i, r)| {
+                // constrain reaches the far edge, which belongs to the next output
+                let inner = Rectangle::new(r.loc, r.size - (1, 1).into());
+                (i, pos.constrain(inner.to_f64()))
+            })
+            .min_by(|a, b| dist(a.1).total_cmp(&dist(b.1)))
+    }
+
+    // nearest monitor beyond the given side of monitor idx, by global output
+    // positions, skipping duplicates
+    pub fn in_direction(&self, idx: usize, dir: Direction) -> Option<usize> {
+        self.nearest(idx, dir, |i| !self.is_duplicate(i))
+    }
+
+    // like in_direction, duplicates included
+    pub fn output_in_direction(&self, idx: usize, dir: Direction) -> Option<usize> {
+        self.nearest(idx, dir, |_| true)
+    }
+
+    fn nearest(&self, idx: usize, dir: Direction, keep: impl Fn(usize) -> bool) -> Option<usize> {
+        let center = |r: Rectangle<i32, Logical>| r.loc + r.size.downscale(2).to_point();
+        let from = self.get(idx)?.global_geometry();
+        let c = center(from);
+        self.iter()
+            .enumerate()
+            .filter(|&(i, _)| i != idx && keep(i))
+            .map(|(i, m)| (i, m.global_geometry()))
+            .filter(|(_, r)| match dir {
+                Direction::Left => r.loc.x + r.size.w <= from.loc.x,
+                Direction::Right => r.loc.x >= from.loc.x + from.size.w,
+                Direction::Up => r.loc.y + r.size.h <= from.loc.y,
+                Direction::Down => r.loc.y >= from.loc.y + from.size.h,
+            })
+            .min_by_key(|&(_, r)| {
+                let d = center(r) - c;
+                d.x * d.x + d.y * d.y
+            })
+            .map(|(i, _)| i)
+    }
+
+    // the monitor idx duplicates and the scale that fits it onto idx's mode,
+    // None if it shows its own tags
+    pub fn duplicate_source(&self, idx: usize) -> Option<(usize, f64)> {
+        let target = self.get(idx)?;
+        let name = target.duplicate.as_ref()?;
+        let src = self
+            .iter()
+            .position(|m| m.output.name() == *name && m.duplicate.is_none())
+            .filter(|&i| i != idx)?;
+        let from = self[src].geometry().size;
+        let to = target.output.current_mode()?.size;
+        let scale = (to.w as f64 / from.w as f64).min(to.h as f64 / from.h as f64);
+        (scale > 0.0).then_some((src, scale))
+    }
+
+    pub fn is_duplicate(&self, idx: usize) -> bool {
+        self.duplicate_source(idx).is_some()
+    }
+
+    // the monitor whose tags output idx shows, idx itself unless a duplicate
+    pub fn showing(&self, idx: usize) -> usize {
+        self.duplicate_source(idx).map_or(idx, |(src, _)| src)
+    }
+
+    // where the source starts on duplicate idx, centered with bars on the
+    // sides that don't fit the aspect ratio
+    pub fn letterbox(&self, idx: usize) -> Point<i32, Physical> {
+        let Some((src, scale)) = self.duplicate_source(idx) else {
+            return Point::default();
+        };
+        let Some(mode) = self[idx].output.current_mode() else {
+            return Point::default();
+        };
+        let shown = self[src].geometry().size.to_f64().to_physical(scale);
+        let (w, h) = (mode.size.w as f64 - shown.w, mode.size.h as f64 - shown.h);
+        Point::from((w / 2.0, h / 2.0)).to_i32_round()
+    }
+
+    // outputs showing monitor idx, repainted whenever it is
+    pub fn duplicates_of(&self, idx: usize) -> Vec<Output> {
+        (0..self.len())
+            .filter(|&i| self.duplicate_source(i).is_some_and(|(src, _)| src == idx))
+            .map(|i| self[i].output.clone())
+            .collect()
+    }
+
     pub fn contains_window(&self, id: WindowId) -> bool {
         self.iter().any(|m| m.views.iter().any(|v| v.contains(id)))
     }
@@ -286,6 +588,10 @@ impl Monitors {
             mon.tags.resize_with(new_len, Tag::default);
             mon.active_tag = mon.active_tag.min(new_len - 1);
             mon.prev_tag = mon.prev_tag.min(new_len - 1);
+            // a DuplicateOutput toggle holds until the rule itself changes
+            if s.duplicate != mon.settings.duplicate {
+                mon.duplicate = s.duplicate.clone();
+            }
             mon.settings = s;
         }
     }
@@ -294,7 +600,7 @@ impl Monitors {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::config::{Color, OutputMatch, OutputRule, OutputTransform};
+    use crate::config::{Color, OutputMatch, OutputRule, OutputTransform, ScaleFilter};
 
     fn rule(name: Option<&str>) -> OutputRule {
         OutputRule {
@@ -318,15 +624,26 @@ mod tests {
         r.scale = Some(2.0);
         r.pos = Some((100, 200));
         r.transform = Some(OutputTransform::_90);
+        r.scale_filter = Some(ScaleFilter::Nearest);
         r.background = Some(Color([1.0, 0.0, 0.0, 1.0]));
 
         let s = MonitorSettings::resolve(&[r], "DP-1", "Dell", "U2720Q", "ABC123");
         assert!(matches!(s.scale, Some(Scale::Fractional(v)) if v == 2.0));
         assert_eq!(s.pos, Point::from((100, 200)));
         assert_eq!(s.transform, Some(Transform::_90));
+        assert_eq!(s.scale_filter, ScaleFilter::Nearest);
         assert_eq!(s.background, [1.0, 0.0, 0.0, 1.0]);
     }
 
+    #[test]
+    fn tag_backgrounds_resolve() {
+        let mut r = rule(None);
+        r.tag_backgrounds = Some(vec![Color([0.0, 0.0, 1.0, 1.0])]);
+
+        let s = MonitorSettings::resolve(&[r], "DP-1", "Dell", "U2720Q", "ABC123");
+        assert_eq!(s.tag_backgrounds, vec![[0.0, 0.0, 1.0, 1.0]]);
+    }
+
     #[test]
     fn name_filter_skips_non_matching() {
         let mut r = rule(Some("HDMI-A-1"));
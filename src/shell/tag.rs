@@ -2,10 +2,14 @@
 
 use smithay::{
     reexports::wayland_server::protocol::wl_surface::WlSurface,
-    utils::{Logical, Rectangle, Serial},
+    utils::{Logical, Point, Rectangle, Serial, Size},
 };
 
-use super::{TilingLayout, WindowId, Windows};
+use super::{TilingLayout, WindowId, Windows, clamp_into};
+use crate::config::{FloatPlacement, FloatSize};
+
+// offset between cascaded windows
+const CASCADE_STEP: i32 = 32;
 
 #[derive(Debug, Default, Clone)]
 pub struct Tag {
@@ -29,6 +33,50 @@ impl Tag {
         }
     }
 
+    // float_size within an area, never larger than it
+    pub fn float_size(&self, area: Size<i32, Logical>) -> Size<i32, Logical> {
+        let (w, h) = match self.layout.config.float_size {
+            FloatSize::Fraction(f) => {
+                let f = f.clamp(0.1, 1.0);
+                ((area.w as f32 * f) as i32, (area.h as f32 * f) as i32)
+            }
+            FloatSize::Size(w, h) => (w, h),
+        };
+        (w.clamp(1, area.w.max(1)), h.clamp(1, area.h.max(1))).into()
+    }
+
+    // floating position of a new window by float_placement, kept inside area
+    pub fn place_float(
+        &self,
+        area: Rectangle<i32, Logical>,
+        size: Size<i32, Logical>,
+        pointer: Point<i32, Logical>,
+    ) -> Point<i32, Logical> {
+        let center =
+            area.loc + Point::from(((area.size.w - size.w) / 2, (area.size.h - size.h) / 2));
+        let loc = match self.layout.config.float_placement {
+            FloatPlacement::Center => center,
+            FloatPlacement::UnderMouse => pointer - size.downscale(2).to_point(),
+            FloatPlacement::Cascade => {
+                // a step per floating window on the tag, starting over at the
+                // center once the next one would leave the area
+                let room = |end: i32, from: i32, len: i32| (end - from - len) / CASCADE_STEP;
+                let steps = room(area.loc.x + area.size.w, center.x, size.w)
+                    .min(room(area.loc.y + area.size.h, center.y, size.h))
+                    .max(0)
+                    + 1;
+                let offset = self.floating.len() as i32 % steps * CASCADE_STEP;
+                center + (offset, offset).into()
+            }
+        };
+        clamp_into(Rectangle::new(loc, size), area)
+    }
+
+    pub fn close(&mut self, id: WindowId) {
+        self.layout.close(id);
+        self.remove(id);
+    }
+
     pub fn add(&mut self, id: WindowId) {
         self.remove(id);
 
@@ -40,6 +88,18 @@ impl Tag {
         }
     }
 
+    // new takes the place of old in the layout and focus order, old leaves
+    pub fn replace(&mut self, old: WindowId, new: WindowId) {
+        let Some(pos) = self.focus_stack.iter().position(|&w| w == old) else {
+            return;
+        };
+        self.remove(new);
+        self.layout.replace(old, new);
+        self.remove(old);
+        self.focus_stack
+            .insert(pos.min(self.focus_stack.len()), new);
+    }
+
     pub fn window_ids(&self) -> Vec<WindowId> {
         if let Some(fs) = self.fullscreen {
             vec![fs]
@@ -60,6 +120,18 @@ impl Tag {
         self.focus_stack.first().copied()
     }
 
+    // last in focus order and below the other floating windows
+    pub fn lower(&mut self, id: WindowId) {
+        if let Some(pos) = self.focus_stack.iter().position(|&wid| wid == id) {
+            let id = self.focus_stack.remove(pos);
+            self.focus_stack.push(id);
+        }
+        if let Some(pos) = self.floating.iter().position(|&wid| wid == id) {
+            let id = self.floating.remove(pos);
+            self.floating.insert(0, id);
+        }
+    }
+
     pub fn raise(&mut self, id: WindowId) {
         if let Some(pos) = self.floating.iter().position(|&wid| wid == id) {
             let id = self.floating.remove(pos);
@@ -67,14 +139,31 @@ impl Tag {
         }
     }
 
+    // rotate the floating windows by delta and return the one now on top,
+    // from a tiled window the top one is returned as is
+    pub fn cycle_floating(&mut self, delta: i32) -> Option<WindowId> {
+        let top = *self.floating.last()?;
+        if self.focused_id() == Some(top) {
+            let n = self.floating.len() as i32;
+            self.floating.rotate_left(delta.rem_euclid(n) as usize);
+        }
+        let top = *self.floating.last()?;
+        self.promote(top);
+        Some(top)
+    }
+
     pub fn recompute_layout(
         &mut self,
         ws: &mut Windows,
         area: Rectangle<i32, Logical>,
         fs_geo: Rectangle<i32, Logical>,
     ) -> Vec<(WlSurface, Serial)> {
-        self.layout
-            .retain(|id| ws.get(id).is_some_and(|we| !we.floating));
+        self.layout.retain(|id| ws.get(id).is_some());
+        // floated tiles keep their slot for when they tile again
+        let floated: Vec<WindowId> = self.layout.ids().filter(|&id| ws[id].floating).collect();
+        for id in floated {
+            self.layout.detach(id);
+        }
         self.floating
             .retain(|&id| ws.get(id).is_some_and(|we| we.floating));
 
@@ -98,7 +187,9 @@ impl Tag {
         let mut configured = Vec::new();
         for &id in &self.focus_stack {
             let Some(we) = ws.get_mut(id) else { continue };
-            let target = if we.fullscreen {
+            let target = if we.fake_fullscreen {
+                self.layout.work_area(area)
+            } else if we.fullscreen {
                 fs_geo
             } else if we.floating {
                 we.float_geo
@@ -106,7 +197,8 @@ impl Tag {
                 let Some(rect) = self.layout.position_of(id) else {
                     continue;
                 };
-                rect
+                // size locked at most the tile, as drawn
+                we.locked_rect(rect)
             };
             if let Some(serial) = we.configure(target.size)
                 && let Some(tl) = we.window.toplevel()
@@ -27,6 +27,8 @@ impl Views {
 pub struct Tile {
     pub id: WindowId,
     pub rect: Rectangle<i32, Logical>,
+    // height relative to the other tiles in its column
+    pub weight: f32,
 }
 
 #[derive(Debug)]
@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
-use std::collections::{BTreeMap, HashMap};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    time::{Duration, Instant},
+};
 
 use derive_more::{Deref, DerefMut};
 
@@ -14,14 +17,14 @@ use smithay::{
     },
     utils::{Logical, Point, Rectangle, Serial, Size},
     wayland::{
-        compositor::with_states,
+        compositor::{RectangleKind, SurfaceAttributes, with_states},
         shell::xdg::{SurfaceCachedState, ToplevelSurface, XdgToplevelSurfaceData},
     },
 };
 
 use crate::{config, render::RenderStep};
 
-use super::WindowId;
+use super::{WindowId, blocker::serial_committed};
 
 fn set_tiled(tl: &ToplevelSurface, tiled: bool) {
     tl.with_pending_state(|s| {
@@ -61,10 +64,47 @@ impl ToplevelSurfaceExt for ToplevelSurface {
     }
 }
 
+// the urgent border alternates on and off, count times each
+#[derive(Debug, Clone, Copy)]
+pub struct Flash {
+    start: Instant,
+    // length of one on or off phase
+    pub period: Duration,
+    count: u32,
+}
+
+impl Flash {
+    // None if the config disables flashing
+    pub fn new(config: &config::Urgent) -> Option<Self> {
+        let phases = config.flashes.checked_mul(2).filter(|&n| n > 0)?;
+        let period = Duration::from_millis(config.duration) / phases;
+        (!period.is_zero()).then(|| Self {
+            start: Instant::now(),
+            period,
+            count: config.flashes,
+        })
+    }
+
+    fn phase(&self) -> u128 {
+        self.start.elapsed().as_nanos() / self.period.as_nanos()
+    }
+
+    fn off(&self) -> bool {
+        let n = self.phase();
+        n < 2 * u128::from(self.count) && n % 2 == 1
+    }
+
+    pub fn done(&self) -> bool {
+        self.phase() >= 2 * u128::from(self.count)
+    }
+}
+
 pub struct Unmapped {
     pub window: Window,
     pub placement: Option<Placement>,
     pub rules: Vec<config::WindowRule>,
+    // marked modal through xdg-dialog before mapping
+    pub modal: bool,
 }
 
 pub struct Placement {
@@ -79,6 +119,7 @@ impl Unmapped {
             window,
             placement: None,
             rules,
+            modal: false,
         }
     }
 
@@ -129,18 +170,44 @@ pub struct WindowElement {
     pub app_id: String,
     pub title: String,
     pub floating: bool,
+    // floating only for having a parent, not by a rule or the user
+    pub floated_by_parent: bool,
+    // floated with ToggleFloat, never tiled behind the user's back
+    pub floated_by_user: bool,
+    // tiled at float_geo's size instead of the tile's, centered in the tile
+    pub size_locked: bool,
+    // terminal hidden in this window's place until it closes
+    pub swallowed: Option<WindowId>,
     pub fullscreen: bool,
+    // left fullscreen when it lost focus, goes back once focused
+    pub lowered_fullscreen: bool,
+    // fullscreen within the layout area, bars and gaps stay visible
+    pub fake_fullscreen: bool,
+    // a dialog that blocks its parent while open, set through xdg-dialog
+    pub modal: bool,
     pub focused: bool,
     pub urgent: bool,
+    // blinking of the urgent border after the window became urgent
+    pub flash: Option<Flash>,
     pub screencasts: u32,
 
     pub float_geo: Rectangle<i32, Logical>,
+    // float_geo left behind on other outputs, by output name
+    pub output_float_geo: HashMap<String, Rectangle<i32, Logical>>,
     configured_size: Size<i32, Logical>,
+    // serial of the last size configure the client has not committed a buffer for
+    pending_configure: Option<Serial>,
+    // interactive resize size, sent at most once per frame
+    pending_size: Option<Size<i32, Logical>>,
+    // last seen client min and max size
+    size_hints: (Size<i32, Logical>, Size<i32, Logical>),
     pub content_offset: Point<i32, Logical>,
 
     pub render_steps: BTreeMap<(usize, u32), RenderStep>,
     pub render_pipeline: Vec<(usize, u32)>,
     pub radius: f32,
+    // false when a no_decorations rule matches
+    pub decorated: bool,
     rules: Vec<config::WindowRule>,
     pub(crate) cache_geo: Rectangle<i32, Logical>,
 
@@ -155,35 +222,57 @@ impl WindowElement {
     pub fn new(id: WindowId, unmapped: Unmapped) -> Self {
         let placement = unmapped.placement.unwrap();
         let rules = unmapped.rules;
+        let modal = unmapped.modal;
         let window = unmapped.window;
         let (app_id, title) = window.toplevel().unwrap().info();
+        let has_parent = window.toplevel().is_some_and(|tl| tl.parent().is_some());
 
         let geom = window.geometry();
         let configured_size =
             if placement.floating { geom.size } else { placement.configured_size };
-        Self {
+        let mut we = Self {
             id,
             window,
             monitor: placement.monitor,
             app_id,
             title,
             floating: placement.floating,
+            floated_by_parent: placement.floating && has_parent,
+            floated_by_user: false,
+            size_locked: false,
+            swallowed: None,
             fullscreen: false,
+            lowered_fullscreen: false,
+            fake_fullscreen: false,
+            modal,
             focused: false,
             screencasts: 0,
             urgent: false,
+            flash: None,
             float_geo: Rectangle::from_size(geom.size),
+            output_float_geo: HashMap::new(),
             render_steps: BTreeMap::new(),
             render_pipeline: Vec::new(),
             radius: 0.0,
+            decorated: true,
             rules,
             cache_geo: Rectangle::default(),
             configured_size,
+            pending_configure: None,
+            pending_size: None,
+            size_hints: Default::default(),
             buffer_committed: true,
             texture_dirty: true,
             content_offset: geom.loc,
             last_texture: None,
-        }
+        };
+        we.size_hints = we.min_max_size();
+        we
+    }
+
+    // urgent rules apply, except while the flash blinks them off
+    fn urgent_shown(&self) -> bool {
+        self.urgent && !self.flash.is_some_and(|f| f.off())
     }
 
     fn matches(&self, rule: &config::WindowRule) -> bool {
@@ -193,7 +282,7 @@ impl WindowElement {
             && m.floating.is_none_or(|v| v == self.floating)
             && m.focused.is_none_or(|v| v == self.focused)
             && m.screencast.is_none_or(|v| v == (self.screencasts > 0))
-            && m.urgent.is_none_or(|v| v == self.urgent)
+            && m.urgent.is_none_or(|v| v == self.urgent_shown())
     }
 
     pub fn resolve_init(&mut self) -> (Option<String>, Option<Vec<usize>>) {
@@ -202,15 +291,25 @@ impl WindowElement {
         for rule in &self.rules {
             if self.matches(rule) {
                 let Some(init) = &rule.init else { continue };
-                self.floating = init.floating.unwrap_or(self.floating);
+                if let Some(floating) = init.floating {
+                    self.floating = floating;
+                    self.floated_by_parent = false;
+                }
                 if let Some((w, h)) = init.size {
                     self.float_geo.size = (w, h).into();
                 }
                 if let Some((x, y)) = init.position {
                     self.float_geo.loc = (x, y).into();
                 }
+                if let Some(locked) = init.size_locked {
+                    self.size_locked = locked;
+                }
                 output = init.output.clone().or(output);
-                tags = init.tags.clone().or(tags);
+                tags = match init.tag {
+                    Some(config::InitTag::Tag(t)) => Some(vec![t]),
+                    Some(config::InitTag::Current) => None,
+                    None => init.tags.clone().or(tags),
+                };
             }
         }
         (output, tags)
@@ -231,8 +330,12 @@ impl WindowElement {
     pub fn resolve_render(&mut self) {
         let mut active: BTreeMap<u32, (usize, u32)> = BTreeMap::new();
         self.radius = 0.0;
+        self.decorated = true;
         for (ri, rule) in self.rules.iter().enumerate() {
             if self.matches(rule) {
+                if let Some(off) = rule.no_decorations {
+                    self.decorated = !off;
+                }
                 for &slot in rule.render.iter().flat_map(|r| r.keys()) {
                     let key = (ri, slot);
                     if self.render_steps.contains_key(&key) {
@@ -244,6 +347,13 @@ impl WindowElement {
             }
         }
         self.render_pipeline = active.into_values().collect();
+        if !self.decorated {
+            // only the surface itself, clipped to nothing
+            let steps = &self.render_steps;
+            self.render_pipeline
+                .retain(|key| matches!(steps.get(key), Some(RenderStep::WindowSurface { .. })));
+            return;
+        }
         for key in &self.render_pipeline {
             if let Some(RenderStep::WindowSurface { radius, .. }) = self.render_steps.get(key) {
                 self.radius = *radius;
@@ -264,25 +374,89 @@ impl WindowElement {
             .unwrap_or_default()
     }
 
+    // true once per change of the client min/max size
+    pub fn size_hints_changed(&mut self) -> bool {
+        let hints = self.min_max_size();
+        hints != std::mem::replace(&mut self.size_hints, hints)
+    }
+
+    // the configured tile size is outside of the client min/max size
+    pub fn violates_size_hints(&self) -> bool {
+        let (min, max) = self.size_hints;
+        let size = self.configured_size;
+        let below = |v: i32, lo: i32| lo > 0 && v < lo;
+        let above = |v: i32, hi: i32| hi > 0 && v > hi;
+        below(size.w, min.w) || below(size.h, min.h) || above(size.w, max.w) || above(size.h, max.h)
+    }
+
     pub fn resize_float(&mut self, size: Size<i32, Logical>) {
         self.float_geo.size = size;
         if let Some(tl) = self.window.toplevel() {
             tl.with_pending_state(|s| s.states.set(xdg_toplevel::State::Resizing));
         }
-        self.configure(self.float_geo.size);
+        self.pending_size = Some(size);
     }
 
     pub fn finish_resize_float(&mut self) {
-        if let Some(tl) = self.window.toplevel() {
-            tl.with_pending_state(|s| s.states.unset(xdg_toplevel::State::Resizing));
+        self.pending_size = None;
+        let Some(tl) = self.window.toplevel() else {
+            return;
+        };
+        tl.with_pending_state(|s| s.states.unset(xdg_toplevel::State::Resizing));
+        // the size may already be configured, the state change still has to go out
+        if self.configure(self.float_geo.size).is_none() {
+            tl.send_pending_configure();
+        }
+    }
+
+    pub fn flush_pending_configure(&mut self) {
+        if let Some(size) = self.pending_size.take() {
+            self.configure(size);
         }
-        self.configure(self.float_geo.size);
+    }
+
+    // the opaque region covers the whole window geometry
+    pub fn is_opaque(&self) -> bool {
+        let Some(wl) = self.window.wl_surface() else {
+            return false;
+        };
+        let geo = self.window.geometry();
+        with_states(&wl, |states| {
+            let mut attrs = states.cached_state.get::<SurfaceAttributes>();
+            let Some(region) = &attrs.current().opaque_region else {
+                return false;
+            };
+            let mut covered = false;
+            for (kind, rect) in &region.rects {
+                match kind {
+                    RectangleKind::Add => covered |= rect.contains_rect(geo),
+                    RectangleKind::Subtract if rect.overlaps(geo) => return false,
+                    RectangleKind::Subtract => {}
+                }
+            }
+            covered
+        })
     }
 
     pub fn surface_loc(&self, loc: Point<i32, Logical>) -> Point<i32, Logical> {
         loc - self.content_offset
     }
 
+    // keep the floating position on from, take the one last used on to or center
+    pub fn move_float_geo(&mut self, from: &str, to: &str, area: Rectangle<i32, Logical>) {
+        self.output_float_geo
+            .insert(from.to_string(), self.float_geo);
+        let size = self.float_geo.size;
+        self.float_geo.loc = match self.output_float_geo.get(to) {
+            Some(geo) => geo.loc,
+            None => {
+                let x = area.loc.x + (area.size.w - size.w) / 2;
+                let y = area.loc.y + (area.size.h - size.h) / 2;
+                (x, y).into()
+            }
+        };
+    }
+
     pub fn set_app_id(&mut self, app_id: String) {
         self.app_id = app_id;
         self.resolve_render();
@@ -299,6 +473,7 @@ impl WindowElement {
         }
         self.focused = focused;
         self.urgent = self.urgent && !focused;
+        self.flash = self.flash.filter(|_| self.urgent);
         self.window.set_activated(focused);
         if let Some(tl) = self.window.toplevel() {
             tl.send_pending_configure();
@@ -322,6 +497,8 @@ impl WindowElement {
 
     pub fn set_fullscreen(&mut self, fullscreen: bool) {
         self.fullscreen = fullscreen;
+        self.lowered_fullscreen = false;
+        self.fake_fullscreen = false;
         if let Some(tl) = self.window.toplevel() {
             tl.with_pending_state(|s| {
                 if fullscreen {
@@ -334,9 +511,18 @@ impl WindowElement {
         }
     }
 
+    // the client still sees the fullscreen state
+    pub fn set_fake_fullscreen(&mut self, fake: bool) {
+        self.set_fullscreen(fake);
+        self.fake_fullscreen = fake;
+    }
+
     pub fn set_floating(&mut self, floating: bool) {
         self.floating = floating;
+        self.floated_by_parent = false;
+        self.floated_by_user = false;
         self.fullscreen = false;
+        self.fake_fullscreen = false;
         self.resolve_render();
         if let Some(tl) = self.window.toplevel() {
             tl.with_pending_state(|s| {
@@ -353,7 +539,33 @@ impl WindowElement {
         let tl = self.window.toplevel()?;
         self.configured_size = size;
         tl.with_pending_state(|s| s.size = Some(size));
-        tl.send_pending_configure()
+        let serial = tl.send_pending_configure();
+        self.pending_configure = serial.or(self.pending_configure);
+        serial
+    }
+
+    // a size locked window keeps its float size, centered in its tile
+    pub fn locked_rect(&self, tile: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        if !self.size_locked || self.float_geo.size.is_empty() {
+            return tile;
+        }
+        let w = self.float_geo.size.w.min(tile.size.w);
+        let h = self.float_geo.size.h.min(tile.size.h);
+        let loc = tile.loc + Point::from(((tile.size.w - w) / 2, (tile.size.h - h) / 2));
+        Rectangle::new(loc, (w, h).into())
+    }
+
+    // where the committed buffer fits in rect while a resize is in flight, so a
+    // slow client is not drawn stretched over bars of fill in its new tile
+    pub fn committed_rect(&self, rect: Rectangle<i32, Logical>) -> Rectangle<i32, Logical> {
+        let rect = self.locked_rect(rect);
+        if self.pending_configure.is_none() {
+            return rect;
+        }
+        let size = self.window.geometry().size;
+        let w = size.w.clamp(1, rect.size.w.max(1));
+        let h = size.h.clamp(1, rect.size.h.max(1));
+        Rectangle::new(rect.loc, (w, h).into())
     }
 
     pub fn on_commit(&mut self) {
@@ -361,6 +573,12 @@ impl WindowElement {
         self.buffer_committed = true;
         self.texture_dirty = true;
         self.content_offset = self.window.geometry().loc;
+        if let Some(serial) = self.pending_configure
+            && let Some(wl) = self.window.wl_surface()
+            && serial_committed(&wl, serial)
+        {
+            self.pending_configure = None;
+        }
 
         // accept client-initiated size for floating windows
         if self.floating && !self.fullscreen {
@@ -421,6 +639,41 @@ impl Windows {
         }
     }
 
+    // windows still open, without the closed ones kept around for rendering
+    pub fn live(&self) -> impl Iterator<Item = &WindowElement> {
+        self.inner
+            .iter()
+            .filter(|(id, _)| !self.zombies.contains(id))
+            .map(|(_, we)| we)
+    }
+
+    // an open modal dialog of id
+    pub fn modal_child(&self, id: WindowId) -> Option<WindowId> {
+        let tl = self.get(id)?.window.toplevel()?;
+        let surface = tl.wl_surface();
+        self.inner
+            .iter()
+            .filter(|(c, we)| we.modal && !self.zombies.contains(c))
+            .find(|(_, we)| {
+                we.window
+                    .toplevel()
+                    .and_then(|t| t.parent())
+                    .is_some_and(|p| p == *surface)
+            })
+            .map(|(c, _)| c)
+    }
+
+    // windows with an open modal dialog, for passes over every window where
+    // modal_child would scan all of them each time
+    pub fn modal_parents(&self) -> HashSet<WindowId> {
+        self.inner
+            .iter()
+            .filter(|(c, we)| we.modal && !self.zombies.contains(c))
+            .filter_map(|(_, we)| we.window.toplevel()?.parent())
+            .filter_map(|p| self.find_by_surface(&p))
+            .collect()
+    }
+
     pub fn find_by_surface(&self, surface: &WlSurface) -> Option<WindowId> {
         self.by_surface.get(&surface.id()).copied()
     }
@@ -1,11 +1,46 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ffi::{OsStr, OsString};
 use std::os::unix::process::CommandExt;
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 use crate::config;
 
+thread_local! {
+    // set for every spawned process on top of the compositor's environment
+    static ENV: RefCell<Vec<(String, OsString)>> = const { RefCell::new(Vec::new()) };
+}
+
+// config variables plus the socket clients have to connect to, which always wins
+pub fn set_env(vars: &BTreeMap<String, String>, socket: &OsStr) {
+    let mut env: Vec<(String, OsString)> = vars
+        .iter()
+        .filter(|(key, _)| {
+            let ours = key.as_str() == "WAYLAND_DISPLAY";
+            if ours {
+                tracing::warn!("env: WAYLAND_DISPLAY is set by monotile, ignoring");
+            }
+            !ours
+        })
+        .map(|(key, value)| (key.clone(), value.into()))
+        .collect();
+    if !socket.is_empty() {
+        env.push(("WAYLAND_DISPLAY".into(), socket.to_os_string()));
+    }
+    ENV.with_borrow_mut(|e| *e = env);
+}
+
+fn apply_env(proc: &mut Command) {
+    ENV.with_borrow(|env| {
+        proc.envs(env.iter().map(|(k, v)| (k, v)));
+    });
+}
+
 pub fn autostart(explicit: Option<PathBuf>) -> Option<i32> {
     let path = config::resolve_autostart(explicit);
     if !path.exists() {
@@ -13,6 +48,7 @@ pub fn autostart(explicit: Option<PathBuf>) -> Option<i32> {
     }
 
     let mut proc = Command::new("sh");
+    apply_env(&mut proc);
     proc.arg(path.to_string_lossy().into_owned())
         .stdin(Stdio::null())
         .stdout(Stdio::null())
@@ -40,23 +76,89 @@ pub fn kill_autostart(pgid: i32) {
         .spawn();
 }
 
-pub fn spawn(cmd: &str, args: &[String], log: bool) {
+pub fn spawn(cmd: &str, args: &[String], log: bool) -> Option<u32> {
+    spawn_env(cmd, args, log, &[])
+}
+
+pub fn spawn_env(cmd: &str, args: &[String], log: bool, env: &[(&str, &str)]) -> Option<u32> {
     let mut proc = Command::new(cmd);
+    apply_env(&mut proc);
     proc.args(args)
+        .envs(env.iter().copied())
         .stdin(Stdio::null())
         .stdout(Stdio::null())
         .stderr(if log { Stdio::inherit() } else { Stdio::null() });
     match proc.spawn() {
         Ok(mut child) => {
             tracing::debug!("{cmd} {}", args.join(" "));
+            let pid = child.id();
             std::thread::spawn(move || {
                 let _ = child.wait();
             });
+            Some(pid)
+        }
+        Err(e) => {
+            tracing::error!("failed to start {cmd}: {e}");
+            None
         }
-        Err(e) => tracing::error!("failed to start {cmd}: {e}"),
     }
 }
 
+pub const PENDING_SPAWN_TIMEOUT: Duration = Duration::from_secs(30);
+
+// set per launch by SpawnAndTag, matched against the client's environment
+pub const SPAWN_TOKEN_ENV: &str = "MONOTILE_SPAWN_TOKEN";
+// set by external launchers to request a tag on the active monitor
+pub const TAG_ENV: &str = "MONOTILE_TAG";
+
+pub fn spawn_token() -> String {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    format!(
+        "{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+// tag a spawned process' first window should land on
+#[derive(Debug)]
+pub struct PendingSpawn {
+    pub token: String,
+    pub pid: u32,
+    pub monitor: usize,
+    pub tag: usize,
+    pub since: Instant,
+}
+
+// walks up the parent chain, commands often fork or exec through a shell
+pub fn is_descendant(mut pid: u32, ancestor: u32) -> bool {
+    for _ in 0..8 {
+        if pid == ancestor {
+            return true;
+        }
+        match parent_pid(pid) {
+            Some(ppid) if ppid > 1 => pid = ppid,
+            _ => return false,
+        }
+    }
+    false
+}
+
+pub fn env_of(pid: u32, key: &str) -> Option<String> {
+    let environ = std::fs::read(format!("/proc/{pid}/environ")).ok()?;
+    environ.split(|&b| b == 0).find_map(|kv| {
+        let value = kv.strip_prefix(key.as_bytes())?.strip_prefix(b"=")?;
+        Some(String::from_utf8_lossy(value).into_owned())
+    })
+}
+
+fn parent_pid(pid: u32) -> Option<u32> {
+    let stat = std::fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+    // comm may contain spaces, fields after the closing paren are fixed
+    let rest = stat.get(stat.rfind(')')? + 2..)?;
+    rest.split(' ').nth(1)?.parse().ok()
+}
+
 pub fn spawn_shell(command: &str) {
     spawn("sh", &["-c".into(), command.into()], false);
 }
@@ -75,3 +177,20 @@ pub fn notify(level: &str, title: &str, msg: &str) {
         false,
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn child_descends_from_its_parent_only() {
+        let mut child = Command::new("sleep").arg("5").spawn().unwrap();
+        let (pid, me) = (child.id(), std::process::id());
+        let down = is_descendant(pid, me);
+        let up = is_descendant(me, pid);
+        child.kill().ok();
+        child.wait().ok();
+        assert!(down, "the child descends from us");
+        assert!(!up, "we don't descend from the child");
+    }
+}
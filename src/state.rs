@@ -1,7 +1,7 @@
 // SPDX-License-Identifier: GPL-3.0-only
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     ffi::OsString,
     os::unix::net::UnixStream,
     sync::Arc,
@@ -11,12 +11,14 @@ use tracing::{info, warn};
 
 use smithay::{
     desktop::{PopupManager, WindowSurfaceType, layer_map_for_output},
-    input::{Seat, SeatState},
-    output::Output,
+    input::{Seat, SeatState, keyboard::Keycode},
+    output::{Mode, Output, PhysicalProperties, Subpixel},
     reexports::{
         calloop::{
             EventLoop, Interest, LoopHandle, LoopSignal, Mode as CalloopMode, PostAction,
+            RegistrationToken,
             generic::Generic,
+            timer::{TimeoutAction, Timer},
         },
         wayland_protocols::xdg::shell::server::xdg_toplevel,
         wayland_protocols_misc::server_decoration::server::org_kde_kwin_server_decoration_manager::Mode as KdeMode,
@@ -31,6 +33,7 @@ use smithay::{
         compositor::{CompositorClientState, CompositorState, get_parent},
         cursor_shape::CursorShapeManagerState,
         dmabuf::{DmabufGlobal, DmabufState},
+        drm_syncobj::DrmSyncobjState,
         idle_inhibit::IdleInhibitManagerState,
         idle_notify::IdleNotifierState,
         output::OutputManagerState,
@@ -45,7 +48,7 @@ use smithay::{
         shell::{
             kde::decoration::KdeDecorationState,
             wlr_layer::{Layer, WlrLayerShellState},
-            xdg::{XdgShellState, decoration::XdgDecorationState},
+            xdg::{XdgShellState, decoration::XdgDecorationState, dialog::XdgDialogState},
         },
         shm::ShmState,
         single_pixel_buffer::SinglePixelBufferState,
@@ -57,14 +60,17 @@ use smithay::{
 
 use crate::{
     backend::Backend,
-    config::Config,
+    config::{Config, Direction, FullscreenUnfocus},
     handlers::{
         Devices, foreign_toplevel::ForeignToplevelState, output_power, screencopy::ScreencopyState,
     },
     ipc::IpcState,
-    render::cursor::CursorManager,
-    shell::{Monitor, MonitorSettings, Monitors, Unmapped, WindowElement, WindowId, Windows},
-    spawn::notify,
+    render::{Switcher, cursor::CursorManager},
+    session::Session,
+    shell::{
+        Flash, Monitor, MonitorSettings, Monitors, Unmapped, WindowElement, WindowId, Windows,
+    },
+    spawn::{self, PendingSpawn, notify},
 };
 
 pub struct Monotile {
@@ -107,24 +113,37 @@ impl Monotile {
         let socket = ListeningSocketSource::new_auto().unwrap();
         state.socket = socket.socket_name().to_os_string();
         info!("listening on {}", state.socket.to_string_lossy());
+        spawn::set_env(&state.client_env(), &state.socket);
+        state.update_session();
         loop_handle
             .insert_source(socket, |stream, _, mt| mt.state.insert_client(stream))
             .unwrap();
 
-        (
-            event_loop,
-            Self {
-                backend: Backend::Unset,
-                state,
-            },
-        )
+        let mut mt = Self {
+            backend: Backend::Unset,
+            state,
+        };
+        mt.arm_session_timer();
+        mt.arm_idle_timer();
+        mt.arm_park_timer();
+        (event_loop, mt)
     }
 
+    #[cfg_attr(
+        feature = "profiling",
+        tracing::instrument(
+            level = "trace",
+            skip(self),
+            fields(windows = self.state.windows.len()),
+        )
+    )]
     pub fn recompute_layout(&mut self, idx: usize) {
         self.update_focus();
-        self.backend
-            .schedule_render(&self.state.monitors[idx].output);
-        self.state.monitors[idx].recompute_layout(&mut self.state.windows);
+        let Some(mon) = self.state.monitors.get_mut(idx) else {
+            return;
+        };
+        self.backend.schedule_render(&mon.output);
+        mon.recompute_layout(&mut self.state.windows);
     }
 
     pub fn advance_view_queues(&mut self) {
@@ -170,7 +189,10 @@ impl Monotile {
         };
 
         let seat_conf = &config.seats["seat0"];
-        let kb_changed = seat_conf.keyboard != self.state.config.seats["seat0"].keyboard;
+        let old_seat = &self.state.config.seats["seat0"];
+        let kb_changed = seat_conf.keyboard != old_seat.keyboard;
+        let cursor_changed = seat_conf.cursor_theme != old_seat.cursor_theme
+            || seat_conf.cursor_size != old_seat.cursor_size;
         if kb_changed {
             let kb_conf = &seat_conf.keyboard;
             let kb = self.state.seat.get_keyboard().unwrap();
@@ -183,14 +205,29 @@ impl Monotile {
         }
 
         self.state.config = config;
+        if cursor_changed {
+            self.state.reload_cursor_theme();
+        }
+        spawn::set_env(&self.state.client_env(), &self.state.socket);
+        self.state.update_session();
+        self.arm_session_timer();
+        self.arm_idle_timer();
+        self.arm_park_timer();
+        // never leave input inhibited without a way back
+        self.state.input_inhibited &= self.state.config.binds.has_inhibit_toggle();
+        self.state.submaps.clear();
+        self.arm_submap_timer();
         self.state.windows.update_rules(&self.state.config.windows);
         self.state.monitors.update_rules(&self.state.config.outputs);
+        self.state.active_monitor = self.state.monitors.showing(self.state.active_monitor);
         self.backend.apply_output_settings(&self.state.monitors);
         self.reconfigure_devices();
         for mon in self.state.monitors.iter_mut() {
             for tag in &mut mon.tags {
                 tag.layout.config = self.state.config.layout.clone();
             }
+            // an output mode change may have left floating windows out of reach
+            mon.rescue_offscreen(&mut self.state.windows);
             mon.recompute_layout(&mut self.state.windows);
         }
         self.update_focus();
@@ -199,21 +236,48 @@ impl Monotile {
     }
 
     pub fn update_focus(&mut self) {
-        self.set_focus(self.state.mon().tag().focused_id());
+        self.set_focus(self.state.focused_id());
+    }
+
+    // make another monitor active, restoring the focus its active tag remembers,
+    // a duplicate hands it to its source
+    pub fn focus_monitor(&mut self, idx: usize) {
+        let idx = self.state.monitors.showing(idx);
+        if idx >= self.state.monitors.len() || idx == self.state.active_monitor {
+            return;
+        }
+        self.state.active_monitor = idx;
+        self.update_focus();
+        // the active_output outline moves
+        if self.state.config.active_output.border.is_some() {
+            self.backend.schedule_render_all();
+        }
     }
 
     pub fn set_focus(&mut self, id: Option<WindowId>) {
+        let id = id.map(|id| self.state.modal_target(id));
+        // focusing another window takes the keyboard back from a clicked layer
+        if self.state.windows.focused != id {
+            self.state.layer_focus = None;
+        }
+        let mut unfocused_fullscreen = None;
         if let Some(old) = self.state.windows.focused
             && Some(old) != id
         {
+            let new_mon = id
+                .and_then(|id| self.state.windows.get(id))
+                .map(|w| w.monitor);
             if let Some(we) = self.state.windows.get_mut(old) {
                 we.set_focused(false);
+                if we.fullscreen && new_mon == Some(we.monitor) {
+                    unfocused_fullscreen = Some(old);
+                }
             }
             self.state.windows.focused = None;
         }
 
         // if locked, focus lock surface
-        if let Some(ls) = &self.state.mon().lock_surface {
+        if let Some(ls) = self.state.mon().and_then(|m| m.lock_surface.as_ref()) {
             let surface = ls.wl_surface().clone();
             if let Some(kb) = self.state.seat.get_keyboard() {
                 kb.set_focus(self, Some(surface), SERIAL_COUNTER.next_serial());
@@ -222,7 +286,7 @@ impl Monotile {
         }
 
         // if exclusive layer exists, focus it
-        if let Some(surface) = self.state.mon().exclusive_layer.clone() {
+        if let Some(surface) = self.state.mon().and_then(|m| m.exclusive_layer.clone()) {
             if let Some(kb) = self.state.seat.get_keyboard() {
                 kb.set_focus(self, Some(surface), SERIAL_COUNTER.next_serial());
             }
@@ -231,19 +295,108 @@ impl Monotile {
 
         // if none of the above, focus window
         if let Some(id) = id {
-            self.state.mon_mut().tag_mut().promote(id);
+            let raise = self.state.config.seats["seat0"].raise_on_focus;
+            if let Some(mon) = self.state.mon_mut() {
+                mon.tag_mut().promote(id);
+                if raise {
+                    mon.tag_mut().raise(id);
+                }
+            }
             if let Some(we) = self.state.windows.get_mut(id) {
                 we.set_focused(true);
             }
             self.state.windows.focused = id.into();
+            self.unfocus_fullscreen(unfocused_fullscreen, id);
         }
 
-        let target = self.state.windows.focused_surface();
+        // the clicked layer keeps the keyboard while it stays on-demand
+        let layer = self.state.layer_focus.take().filter(|s| {
+            let mon = self.state.mon();
+            mon.is_some_and(|m| m.on_demand_layer(s).is_some())
+        });
+        self.state.layer_focus = layer.clone();
+        let target = layer.or_else(|| self.state.windows.focused_surface());
         if let Some(kb) = self.state.seat.get_keyboard() {
             kb.set_focus(self, target, SERIAL_COUNTER.next_serial());
         }
         self.state.ipc.dirty = true;
     }
+
+    // fullscreen_on_unfocus for the window that lost focus to focused, and a
+    // lowered window going back to fullscreen
+    fn unfocus_fullscreen(&mut self, old: Option<WindowId>, focused: WindowId) {
+        let mode = self.state.config.layout.fullscreen_on_unfocus;
+        let mut changed = None;
+        if let Some(old) = old
+            && mode != FullscreenUnfocus::Keep
+        {
+            let we = &mut self.state.windows[old];
+            we.set_fullscreen(false);
+            if mode == FullscreenUnfocus::Lower {
+                we.lowered_fullscreen = true;
+                self.state.monitors[we.monitor].tag_mut().lower(old);
+            }
+            changed = Some(we.monitor);
+        }
+        let we = &mut self.state.windows[focused];
+        if we.lowered_fullscreen {
+            we.set_fullscreen(true);
+            changed = Some(we.monitor);
+        }
+        // not recompute_layout, which comes back here through update_focus
+        if let Some(mon) = changed.and_then(|m| self.state.monitors.get_mut(m)) {
+            self.backend.schedule_render(&mon.output);
+            mon.recompute_layout(&mut self.state.windows);
+        }
+    }
+
+    // mark a window as wanting attention and blink its border
+    pub fn set_urgent(&mut self, id: WindowId) {
+        let Some(we) = self.state.windows.get_mut(id) else {
+            return;
+        };
+        let flashing = we.flash.is_some();
+        we.urgent = true;
+        if !flashing {
+            we.flash = Flash::new(&self.state.config.urgent);
+        }
+        we.resolve_render();
+        let period = we.flash.filter(|_| !flashing).map(|f| f.period);
+        self.state.ipc.dirty = true;
+        // orphans without a monitor still turn urgent, there is just nothing to draw
+        if let Some(mon) = self.state.monitors.get(we.monitor) {
+            self.backend.schedule_render(&mon.output);
+        }
+
+        let Some(period) = period else {
+            return;
+        };
+        let timer = Timer::from_duration(period);
+        let token = self
+            .state
+            .loop_handle
+            .insert_source(timer, move |_, _, mt| {
+                // focus or close ends the flash early
+                let Some(we) = mt.state.windows.get_mut(id) else {
+                    return TimeoutAction::Drop;
+                };
+                let Some(flash) = we.flash else {
+                    return TimeoutAction::Drop;
+                };
+                let done = flash.done();
+                if done {
+                    we.flash = None;
+                }
+                we.resolve_render();
+                if let Some(mon) = mt.state.monitors.get(we.monitor) {
+                    mt.backend.schedule_render(&mon.output);
+                }
+                if done { TimeoutAction::Drop } else { TimeoutAction::ToDuration(period) }
+            });
+        if let Err(err) = token {
+            warn!(?err, "failed to schedule urgent flash");
+        }
+    }
 }
 
 pub struct State {
@@ -251,6 +404,7 @@ pub struct State {
     pub start_time: std::time::Instant,
     pub socket: OsString,
     pub display_handle: DisplayHandle,
+    pub loop_handle: LoopHandle<'static, Monotile>,
     pub loop_signal: LoopSignal,
     pub compositor_state: CompositorState,
     pub xdg_shell_state: XdgShellState,
@@ -266,12 +420,21 @@ pub struct State {
     pub ext_data_control_state: ExtDataControlState,
     pub dmabuf_state: DmabufState,
     pub dmabuf_global: Option<DmabufGlobal>,
+    // linux-drm-syncobj-v1, only on DRM devices supporting syncobj eventfds
+    pub syncobj_state: Option<DrmSyncobjState>,
     pub viewporter_state: ViewporterState,
     pub single_pixel_buffer_state: SinglePixelBufferState,
     pub idle_notifier_state: IdleNotifierState<Monotile>,
     pub idle_notifier_activity: bool,
     pub idle_inhibit_state: IdleInhibitManagerState,
     pub idle_inhibitors: Vec<WlSurface>,
+    // a visible window inhibits idle, also holds off the screensaver
+    pub idle_inhibited: bool,
+    // last input, the screensaver starts config.idle.timeout after it
+    pub last_activity: std::time::Instant,
+    // when the screensaver started, cleared by input
+    pub idle_since: Option<std::time::Instant>,
+    pub idle_timer: Option<RegistrationToken>,
     pub popups: PopupManager,
     pub seat: Seat<Monotile>,
     pub pointer_gestures_state: PointerGesturesState,
@@ -279,18 +442,60 @@ pub struct State {
     pub cursor: CursorManager,
     pub windows: Windows,
     pub unmapped: HashMap<ObjectId, Unmapped>,
+    // mapped windows kept alive while no monitor exists
+    pub orphans: Vec<WindowId>,
+    // virtual output standing in while no physical output is connected
+    pub fallback_output: Option<Output>,
+    pub pending_spawns: Vec<PendingSpawn>,
+    pub screenshots: Vec<crate::render::Screenshot>,
     pub monitors: Monitors,
     // TODO: active_monitor should be derived, not stored.
     // Every lookup (render, map, unmap, focus, layout) really needs
     // "monitor for this output/window/pointer location", not "active".
     // Remove this index when multi-monitor is implemented.
     pub active_monitor: usize,
+    // keep the pointer on the active monitor instead of crossing to its neighbours
+    pub pointer_confined: bool,
+    // root_scroll distance not yet turned into a focus step
+    pub root_scroll: f64,
     pub locked: bool,
+    // key held for PeekPrevTag and the tag to return to on its release
+    pub peek: Option<(Keycode, usize)>,
+    // SwitchWindow list while its modifiers are held
+    pub switcher: Option<Switcher>,
+    // entered submaps, keys go to the last one
+    pub submaps: Vec<String>,
+    // leaves all submaps after submap_timeout
+    pub submap_timer: Option<RegistrationToken>,
+    // repaints monitors showing render stats
+    pub stats_timer: Option<RegistrationToken>,
     pub pending_lock: Option<(SessionLocker, HashSet<Output>)>,
+    // KillTag (false) or KillOthers (true) pressed once, waiting for confirmation
+    pub pending_bulk_close: Option<(bool, std::time::Instant)>,
+    // cancels ExitGracefully if windows are still open when it fires
+    pub exit_timer: Option<RegistrationToken>,
+    // on-demand layer surface given the keyboard by a click
+    pub layer_focus: Option<WlSurface>,
+    // button, time and position of the last press, for DoubleClick binds
+    pub last_click: Option<(u32, std::time::Instant, Point<f64, Logical>)>,
+    // for cursor_park_timeout, the pointer is parked until it moves again
+    pub last_pointer_motion: std::time::Instant,
+    pub cursor_parked: bool,
+    pub park_timer: Option<RegistrationToken>,
+    // ToggleInputInhibit, all input but its key bind is dropped
+    pub input_inhibited: bool,
+    // keys held when input was inhibited, their releases still reach the client
+    pub inhibit_held: Vec<Keycode>,
+    // saved window placements, when enabled
+    pub session: Option<Session>,
+    pub session_timer: Option<RegistrationToken>,
+    // suspend command waiting for the session lock and when it was requested
+    pub suspend_after_lock: Option<(Vec<String>, std::time::Instant)>,
     pub session_lock_state: SessionLockManagerState,
     pub screencopy: ScreencopyState,
     pub foreign_toplevel: ForeignToplevelState,
     pub xdg_activation_state: XdgActivationState,
+    pub xdg_dialog_state: XdgDialogState,
     pub ipc: IpcState,
 }
 
@@ -312,7 +517,7 @@ impl State {
         let session_lock_state = SessionLockManagerState::new::<Monotile, _>(&dh, |_| true);
         let viewporter_state = ViewporterState::new::<Monotile>(&dh);
         let single_pixel_buffer_state = SinglePixelBufferState::new::<Monotile>(&dh);
-        let idle_notifier_state = IdleNotifierState::<Monotile>::new(&dh, lh);
+        let idle_notifier_state = IdleNotifierState::<Monotile>::new(&dh, lh.clone());
         let idle_inhibit_state = IdleInhibitManagerState::new::<Monotile>(&dh);
         let shm_state = ShmState::new::<Monotile>(&dh, vec![]);
         let output_manager_state = OutputManagerState::new_with_xdg_output::<Monotile>(&dh);
@@ -333,7 +538,6 @@ impl State {
             kb_conf.repeat_rate,
         )
         .unwrap();
-        seat.add_pointer();
         info!(
             "keyboard: layout={} variant={}",
             kb_conf.layout, kb_conf.variant
@@ -341,10 +545,12 @@ impl State {
 
         let pointer_gestures_state = PointerGesturesState::new::<Monotile>(&dh);
         let cursor_shape_state = CursorShapeManagerState::new::<Monotile>(&dh);
-        let cursor = CursorManager::new(1.0);
+        let (cursor_theme, cursor_size) = config.seats["seat0"].cursor();
+        let cursor = CursorManager::new(&cursor_theme, cursor_size);
         let screencopy = ScreencopyState::new(&dh);
         let foreign_toplevel = ForeignToplevelState::new(&dh);
         let xdg_activation_state = XdgActivationState::new::<Monotile>(&dh);
+        let xdg_dialog_state = XdgDialogState::new::<Monotile>(&dh);
         output_power::register_global(&dh);
         let ipc = IpcState::new(&dh);
 
@@ -353,6 +559,7 @@ impl State {
             start_time: std::time::Instant::now(),
             socket: OsString::new(),
             display_handle: dh,
+            loop_handle: lh,
             loop_signal: signal,
             compositor_state,
             xdg_shell_state,
@@ -368,12 +575,17 @@ impl State {
             ext_data_control_state,
             dmabuf_state: DmabufState::new(),
             dmabuf_global: None,
+            syncobj_state: None,
             viewporter_state,
             single_pixel_buffer_state,
             idle_notifier_state,
             idle_notifier_activity: false,
             idle_inhibit_state,
             idle_inhibitors: Vec::new(),
+            idle_inhibited: false,
+            last_activity: std::time::Instant::now(),
+            idle_since: None,
+            idle_timer: None,
             popups: PopupManager::default(),
             seat,
             pointer_gestures_state,
@@ -382,29 +594,107 @@ impl State {
             windows: Windows::default(),
             monitors: Monitors::default(),
             active_monitor: 0,
+            pointer_confined: false,
+            root_scroll: 0.0,
+            peek: None,
+            switcher: None,
             unmapped: HashMap::new(),
+            orphans: Vec::new(),
+            fallback_output: None,
+            pending_spawns: Vec::new(),
+            screenshots: Vec::new(),
             locked: false,
+            submaps: Vec::new(),
+            submap_timer: None,
+            stats_timer: None,
             pending_lock: None,
+            pending_bulk_close: None,
+            exit_timer: None,
+            layer_focus: None,
+            last_click: None,
+            last_pointer_motion: std::time::Instant::now(),
+            cursor_parked: false,
+            park_timer: None,
+            input_inhibited: false,
+            inhibit_held: Vec::new(),
+            session: None,
+            session_timer: None,
+            suspend_after_lock: None,
             session_lock_state,
             screencopy,
             foreign_toplevel,
             xdg_activation_state,
+            xdg_dialog_state,
             ipc,
         }
     }
 
-    pub fn mon(&self) -> &Monitor {
-        &self.monitors[self.active_monitor]
+    pub fn mon(&self) -> Option<&Monitor> {
+        self.monitors.get(self.active_monitor)
+    }
+
+    pub fn mon_mut(&mut self) -> Option<&mut Monitor> {
+        self.monitors.get_mut(self.active_monitor)
+    }
+
+    // the active monitor of several, gets the active_output outline
+    pub fn outlined(&self, idx: usize) -> bool {
+        self.monitors.len() > 1 && idx == self.active_monitor
+    }
+
+    pub fn focused_id(&self) -> Option<WindowId> {
+        self.mon()?.tag().focused_id()
     }
 
-    pub fn mon_mut(&mut self) -> &mut Monitor {
-        &mut self.monitors[self.active_monitor]
+    // FocusTagGlobal, duplicates are skipped as they show their source
+    pub fn set_active_tag_global(&mut self, tag: usize) {
+        for mon in self.monitors.iter_mut() {
+            if mon.duplicate.is_none() {
+                mon.set_active_tag(tag);
+            }
+        }
     }
 
     pub fn add_monitor(&mut self, output: Output, settings: MonitorSettings) {
         let global = output.create_global::<Monotile>(&self.display_handle);
-        let mon = Monitor::new(output, global, settings, &self.config.layout);
+        let mut mon = Monitor::new(output, global, settings, &self.config.layout);
+
+        // adopt windows that survived without a monitor
+        let idx = self.monitors.len();
+        for id in self.orphans.drain(..) {
+            if let Some(we) = self.windows.get_mut(id) {
+                we.monitor = idx;
+                mon.tag_mut().add(id);
+            }
+        }
         self.monitors.push(mon);
+        if self.monitors.len() == 1 {
+            self.active_monitor = 0;
+        }
+        // the source of the active output may just have appeared
+        self.active_monitor = self.monitors.showing(self.active_monitor);
+    }
+
+    // move the focused window to the active tag of the neighbouring monitor
+    pub fn move_active_to_monitor_dir(&mut self, dir: Direction) -> Option<usize> {
+        let from = self.active_monitor;
+        let to = self.monitors.in_direction(from, dir)?;
+        let id = self.focused_id()?;
+        self.windows[id].set_fullscreen(false);
+        for tag in &mut self.monitors[from].tags {
+            tag.remove(id);
+        }
+        self.monitors[to].tag_mut().add(id);
+
+        let area = self.monitors[to].geometry();
+        let from_name = self.monitors[from].output.name();
+        let to_name = self.monitors[to].output.name();
+        let we = &mut self.windows[id];
+        we.monitor = to;
+        we.move_float_geo(&from_name, &to_name, area);
+        self.active_monitor = to;
+        self.ipc.dirty = true;
+        Some(to)
     }
 
     pub fn remove_monitor(&mut self, output: &Output) {
@@ -424,34 +714,95 @@ impl State {
 
         let dead = self.monitors.remove(idx);
         let ids = dead.window_ids();
+        let dead_name = dead.output.name();
         self.display_handle.remove_global::<Monotile>(dead.global);
 
         self.confirm_lock(output);
 
+        // the monitors after the removed one moved down
+        for we in self.windows.values_mut() {
+            if we.monitor > idx {
+                we.monitor -= 1;
+            }
+        }
         // TODO: replace active_monitor with per-seat focused monitor
+        if self.active_monitor > idx {
+            self.active_monitor -= 1;
+        }
         if !self.monitors.is_empty() {
             self.active_monitor = self.active_monitor.min(self.monitors.len() - 1);
         }
-
-        // migrate windows to active monitor / active tag
-        if !ids.is_empty() && !self.monitors.is_empty() {
-            for &id in &ids {
-                if let Some(we) = self.windows.get_mut(id) {
-                    we.set_fullscreen(false);
-                    we.monitor = self.active_monitor;
-                }
+        for &id in &ids {
+            if let Some(we) = self.windows.get_mut(id) {
+                we.set_fullscreen(false);
+                we.monitor = self.active_monitor;
             }
+        }
+
+        // keep windows until a monitor returns
+        if self.monitors.is_empty() {
+            self.orphans.extend(ids);
+            self.ipc.dirty = true;
+            return;
+        }
 
+        // migrate windows to active monitor / active tag
+        if !ids.is_empty() {
             // TODO: replace active_monitor with per-seat focused monitor
             let mon = &mut self.monitors[self.active_monitor];
+            let to_name = mon.output.name();
+            let area = mon.geometry();
             for id in ids {
                 mon.tag_mut().add(id);
+                // floating windows leave their place on the unplugged output
+                if let Some(we) = self.windows.get_mut(id) {
+                    we.move_float_geo(&dead_name, &to_name, area);
+                }
             }
+            mon.rescue_offscreen(&mut self.windows);
             mon.recompute_layout(&mut self.windows);
         }
         self.ipc.dirty = true;
     }
 
+    pub fn ensure_fallback_output(&mut self) -> Option<Output> {
+        if !self.monitors.is_empty() {
+            return None;
+        }
+        let (name, make, model, serial) = ("HEADLESS-1", "monotile", "headless", "0");
+        let output = Output::new(
+            name.into(),
+            PhysicalProperties {
+                size: (0, 0).into(),
+                subpixel: Subpixel::Unknown,
+                make: make.into(),
+                model: model.into(),
+                serial_number: serial.into(),
+            },
+        );
+        let mode = Mode {
+            size: (1920, 1080).into(),
+            refresh: 60_000,
+        };
+        output.set_preferred(mode);
+        output.change_current_state(Some(mode), None, None, Some((0, 0).into()));
+        info!("{name}: no outputs left, adding fallback output");
+
+        let s = MonitorSettings::resolve(&self.config.outputs, name, make, model, serial);
+        self.add_monitor(output.clone(), s);
+        self.monitors[0].recompute_layout(&mut self.windows);
+        self.fallback_output = Some(output.clone());
+        self.ipc.dirty = true;
+        Some(output)
+    }
+
+    pub fn remove_fallback_output(&mut self) {
+        if let Some(output) = self.fallback_output.take() {
+            info!("{}: removing fallback output", output.name());
+            self.remove_monitor(&output);
+        }
+    }
+
     pub fn monitor_idx(&self, name: &str) -> usize {
         self.monitors
             .iter()
@@ -463,35 +814,242 @@ impl State {
         let id = self
             .windows
             .insert_with_key(|id| WindowElement::new(id, unmapped));
-        let (output, tags) = self.windows[id].resolve_init();
+        let (output, mut tags) = self.windows[id].resolve_init();
         self.windows[id].build_render_steps();
 
         if let Some(name) = output {
             self.windows[id].monitor = self.monitor_idx(&name);
         }
+        if self.windows[id].modal {
+            self.place_over_parent(id);
+        }
+        // window rules take precedence over the saved session
+        if tags.is_none()
+            && let Some(session) = &mut self.session
+            && let Some(entry) = session.take(&self.windows[id].app_id)
+        {
+            self.windows[id].monitor = self.monitor_idx(&entry.output);
+            tags = Some(entry.tags);
+        }
+        if let Some((mon, tag)) = self.spawn_placement(id)
+            && mon < self.monitors.len()
+        {
+            self.windows[id].monitor = mon;
+            tags = Some(vec![tag]);
+        }
+        let swallowed = self.swallow_target(id);
+        if let Some(term) = swallowed {
+            // the terminal's tags win over rules, the session and spawn tags
+            let mon = self.windows[term].monitor;
+            self.windows[id].monitor = mon;
+            self.windows[id].swallowed = Some(term);
+            tags = self.monitors.get(mon).map(|m| {
+                (0..m.tags.len())
+                    .filter(|&t| m.tags[t].contains(term))
+                    .collect()
+            });
+            // hidden from taskbars until it takes its place back
+            self.foreign_toplevel.remove(term);
+            self.ipc.dirty = true;
+        }
         self.foreign_toplevel
             .add(id, &self.windows[id].title, &self.windows[id].app_id);
 
-        let idx = self.windows[id].monitor;
-        self.monitors[idx].map(&mut self.windows, id, tags);
+        // a duplicate doesn't show its own tags
+        let idx = self.monitors.showing(self.windows[id].monitor);
+        self.windows[id].monitor = idx;
+        let pointer = self
+            .seat
+            .get_pointer()
+            .map(|p| p.current_location())
+            .unwrap_or_default();
+        match self.monitors.get_mut(idx) {
+            Some(mon) => {
+                let pointer = pointer.to_i32_round() - mon.output.current_location();
+                mon.map(&mut self.windows, id, tags, pointer);
+                if let Some(term) = swallowed {
+                    mon.replace_window(term, id);
+                }
+            }
+            None => self.orphans.push(id),
+        }
         id
     }
 
+    // a modal dialog floats centered over its parent, on the parent's output
+    fn place_over_parent(&mut self, id: WindowId) {
+        let parent = self.windows[id]
+            .window
+            .toplevel()
+            .and_then(|tl| tl.parent());
+        let Some(parent) = parent.and_then(|p| self.windows.find_by_surface(&p)) else {
+            return;
+        };
+        let mon = self.windows[parent].monitor;
+        let Some(rect) = self
+            .monitors
+            .get(mon)
+            .and_then(|m| m.window_rect(&self.windows, parent))
+        else {
+            return;
+        };
+        let we = &mut self.windows[id];
+        we.monitor = mon;
+        we.floating = true;
+        let size = we.float_geo.size;
+        we.float_geo.loc = (
+            rect.loc.x + (rect.size.w - size.w) / 2,
+            rect.loc.y + (rect.size.h - size.h) / 2,
+        )
+            .into();
+    }
+
+    fn client_pid(&self, id: WindowId) -> Option<u32> {
+        let client = self.windows[id].window.toplevel()?.wl_surface().client()?;
+        let creds = client.get_credentials(&self.display_handle).ok()?;
+        Some(creds.pid as u32)
+    }
+
+    // visible terminal the new window was started from, the focused one first
+    fn swallow_target(&self, id: WindowId) -> Option<WindowId> {
+        let swallow = &self.config.swallow;
+        let we = &self.windows[id];
+        let has_parent = we.window.toplevel().is_some_and(|tl| tl.parent().is_some());
+        if swallow.terminals.is_empty()
+            || has_parent
+            || swallow.terminals.contains(&we.app_id)
+            || swallow.exclude.contains(&we.app_id)
+        {
+            return None;
+        }
+        let pid = self.client_pid(id)?;
+        self.windows
+            .live()
+            .filter(|t| t.id != id && swallow.terminals.contains(&t.app_id))
+            // not already swallowed
+            .filter(|t| {
+                self.monitors
+                    .iter()
+                    .any(|m| m.tags.iter().any(|g| g.contains(t.id)))
+            })
+            .filter(|t| {
+                self.client_pid(t.id)
+                    .is_some_and(|tp| spawn::is_descendant(pid, tp))
+            })
+            .min_by_key(|t| !t.focused)
+            .map(|t| t.id)
+    }
+
+    // (monitor, tag) requested for a new window by its launcher
+    fn spawn_placement(&mut self, id: WindowId) -> Option<(usize, usize)> {
+        self.pending_spawns
+            .retain(|p| p.since.elapsed() < spawn::PENDING_SPAWN_TIMEOUT);
+        let pid = self.client_pid(id)?;
+
+        // per-launch token, survives forks and reparenting
+        let token = spawn::env_of(pid, spawn::SPAWN_TOKEN_ENV);
+        let idx = token
+            .and_then(|t| self.pending_spawns.iter().position(|p| p.token == t))
+            .or_else(|| {
+                self.pending_spawns
+                    .iter()
+                    .position(|p| spawn::is_descendant(pid, p.pid))
+            });
+        if let Some(idx) = idx {
+            let p = self.pending_spawns.swap_remove(idx);
+            return Some((p.monitor, p.tag));
+        }
+
+        let tag = spawn::env_of(pid, spawn::TAG_ENV)?.parse().ok()?;
+        Some((self.active_monitor, tag))
+    }
+
     pub fn destroy_window(&mut self, surface: &ObjectId) -> Option<usize> {
         self.unmapped.remove(surface);
         let id = self.windows.detach(surface)?;
         self.screencopy.remove_toplevel(id);
         self.foreign_toplevel.remove(id);
+        self.orphans.retain(|&o| o != id);
         let mon = self.windows[id].monitor;
-        self.monitors[mon].unmap(id);
+        // a swallowed terminal takes its place back
+        let term = self.windows[id]
+            .swallowed
+            .filter(|&t| self.windows.live().any(|we| we.id == t));
+        if let Some(m) = self.monitors.get_mut(mon) {
+            if let Some(term) = term {
+                m.replace_window(id, term);
+                let we = &mut self.windows[term];
+                we.monitor = mon;
+                self.foreign_toplevel.add(term, &we.title, &we.app_id);
+                self.ipc.dirty = true;
+            }
+            let was_shown = m.tag().contains(id);
+            m.unmap(id);
+            if was_shown && self.config.layout.view_prev_on_empty {
+                m.view_prev_if_empty();
+            }
+            if was_shown && self.config.layout.auto_tile_sole_window {
+                self.tile_sole_window(mon);
+            }
+        }
         Some(mon)
     }
 
+    // the only window left on the active tag tiles, if it floats on its own
+    fn tile_sole_window(&mut self, mon: usize) {
+        let [id] = self.monitors[mon].tag().focus_stack[..] else {
+            return;
+        };
+        let Some(we) = self.windows.get_mut(id) else {
+            return;
+        };
+        if we.floating && !we.floated_by_user && !we.fullscreen {
+            we.set_floating(false);
+        }
+    }
+
+    // finish ExitGracefully once the last window closed
+    pub fn exit_if_all_closed(&mut self) {
+        if self.exit_timer.is_none() || self.windows.live().next().is_some() {
+            return;
+        }
+        if let Some(token) = self.exit_timer.take() {
+            self.loop_handle.remove(token);
+        }
+        info!("all windows closed, exiting");
+        self.loop_signal.stop();
+    }
+
+    // the window input for id goes to, its modal dialog if one is open on the active tag
+    pub fn modal_target(&self, mut id: WindowId) -> WindowId {
+        let Some(mon) = self.mon().filter(|_| self.config.modal.block_input) else {
+            return id;
+        };
+        // bounded in case clients build a parent loop
+        for _ in 0..self.windows.len() {
+            let Some(child) = self
+                .windows
+                .modal_child(id)
+                .filter(|&c| mon.tag().contains(c))
+            else {
+                break;
+            };
+            id = child;
+        }
+        id
+    }
+
     pub fn surface_under(&self, pos: Point<f64, Logical>) -> SurfaceUnder {
         let monitor = self.active_monitor;
+        let Some(mon) = self.mon() else {
+            return SurfaceUnder {
+                surface: None,
+                window: None,
+                monitor,
+            };
+        };
         if self.locked {
-            let surface = self
-                .mon()
+            let surface = mon
                 .lock_surface
                 .as_ref()
                 .map(|ls| (ls.wl_surface().clone(), pos));
@@ -502,7 +1060,6 @@ impl State {
             };
         }
 
-        let mon = self.mon();
         let map = layer_map_for_output(&mon.output);
         let layer_hit = |layer| {
             let layer = map.layer_under(layer, pos)?;
@@ -522,6 +1079,11 @@ impl State {
         }
 
         // windows and popups
+        let modal_parents = if self.config.modal.block_input {
+            self.windows.modal_parents()
+        } else {
+            HashSet::new()
+        };
         for id in mon.tag().window_ids().into_iter().rev() {
             let Some(we) = self.windows.get(id) else {
                 continue;
@@ -529,9 +1091,26 @@ impl State {
             let Some(rect) = mon.window_rect(&self.windows, id) else {
                 continue;
             };
+            // a window behind a modal dialog gets no pointer input itself
+            if modal_parents.contains(&id) {
+                if rect.to_f64().contains(pos) {
+                    return SurfaceUnder {
+                        surface: None,
+                        window: Some(id),
+                        monitor,
+                    };
+                }
+                continue;
+            }
             let loc = rect.loc - we.content_offset;
             let rel = pos - loc.to_f64();
-            if let Some((s, point)) = we.window.surface_under(rel, WindowSurfaceType::ALL) {
+            // an oversized buffer is cropped to the tile, only popups reach beyond it
+            let kind = if !we.floating && !we.fullscreen && !rect.to_f64().contains(pos) {
+                WindowSurfaceType::POPUP
+            } else {
+                WindowSurfaceType::ALL
+            };
+            if let Some((s, point)) = we.window.surface_under(rel, kind) {
                 return SurfaceUnder {
                     surface: Some((s, (point + loc).to_f64())),
                     window: Some(id),
@@ -556,6 +1135,42 @@ impl State {
         }
     }
 
+    // nothing but the wallpaper under pos: gaps, an empty tag
+    pub fn over_root(&self, pos: Point<f64, Logical>) -> bool {
+        let under = self.surface_under(pos);
+        if under.window.is_some() {
+            return false;
+        }
+        let (Some((surface, _)), Some(mon)) = (under.surface, self.mon()) else {
+            return true;
+        };
+        let map = layer_map_for_output(&mon.output);
+        map.layer_for_surface(&surface, WindowSurfaceType::ALL)
+            .is_some_and(|l| l.layer() == Layer::Background)
+    }
+
+    // load the configured cursor theme again, then the cursor currently shown
+    // at the scale of every output
+    pub fn reload_cursor_theme(&mut self) {
+        let (theme, size) = self.config.seats["seat0"].cursor();
+        self.cursor.reload(&theme, size);
+        for mon in self.monitors.iter() {
+            self.cursor
+                .preload(mon.output.current_scale().fractional_scale());
+        }
+        info!("cursor: theme={} size={size}", self.cursor.theme_name);
+    }
+
+    // config env plus the cursor in use, so clients draw the same one
+    pub fn client_env(&self) -> BTreeMap<String, String> {
+        let mut env = self.config.env.clone();
+        env.entry("XCURSOR_THEME".into())
+            .or_insert_with(|| self.cursor.theme_name.clone());
+        env.entry("XCURSOR_SIZE".into())
+            .or_insert_with(|| self.cursor.size.to_string());
+        env
+    }
+
     pub fn insert_client(&mut self, stream: UnixStream) {
         self.display_handle
             .insert_client(stream, Arc::new(ClientState::default()))
@@ -580,6 +1195,7 @@ impl State {
                     .find_by_surface(&root)
                     .is_none_or(|id| self.monitors.shows_window(id))
             });
+        self.idle_inhibited = inhibited;
         self.idle_notifier_state.set_is_inhibited(inhibited);
     }
 
@@ -10,8 +10,8 @@ use wayland_client::{
     Connection, Dispatch, EventQueue, QueueHandle,
     protocol::{
         wl_buffer, wl_callback, wl_compositor, wl_data_device, wl_data_device_manager,
-        wl_data_source, wl_output, wl_pointer, wl_registry, wl_seat, wl_shm, wl_shm_pool,
-        wl_surface,
+        wl_data_source, wl_output, wl_pointer, wl_region, wl_registry, wl_seat, wl_shm,
+        wl_shm_pool, wl_subcompositor, wl_subsurface, wl_surface,
     },
 };
 use wayland_protocols::ext::foreign_toplevel_list::v1::client::{
@@ -34,7 +34,11 @@ use wayland_protocols::xdg::{
         xdg_activation_token_v1::{self, XdgActivationTokenV1},
         xdg_activation_v1::{self, XdgActivationV1},
     },
-    shell::client::{xdg_surface, xdg_toplevel, xdg_wm_base},
+    dialog::v1::client::{
+        xdg_dialog_v1::{self, XdgDialogV1},
+        xdg_wm_dialog_v1::{self, XdgWmDialogV1},
+    },
+    shell::client::{xdg_popup, xdg_positioner, xdg_surface, xdg_toplevel, xdg_wm_base},
 };
 use wayland_protocols_wlr::layer_shell::v1::client::{
     zwlr_layer_shell_v1::{self, ZwlrLayerShellV1},
@@ -61,6 +65,7 @@ pub struct Client {
 
 struct ClientData {
     compositor: Option<wl_compositor::WlCompositor>,
+    subcompositor: Option<wl_subcompositor::WlSubcompositor>,
     wm_base: Option<xdg_wm_base::XdgWmBase>,
     shm: Option<wl_shm::WlShm>,
     buffer: Option<wl_buffer::WlBuffer>,
@@ -71,10 +76,13 @@ struct ClientData {
     layer_shell: Option<ZwlrLayerShellV1>,
     layers: Vec<LayerState>,
     windows: Vec<WindowState>,
+    popups: Vec<PopupState>,
 
     ipc_output: Option<wl_output::WlOutput>,
     ipc_seat: Option<wl_seat::WlSeat>,
     ipc_status_manager: Option<ZmonotileStatusManagerV1>,
+    // registry and global name of the status manager, to bind it again
+    ipc_status_global: Option<(wl_registry::WlRegistry, u32)>,
     ipc_control: Option<ZmonotileControlV1>,
     ipc_output_status: Option<ZmonotileOutputStatusV1>,
     ipc_seat_status: Option<ZmonotileSeatStatusV1>,
@@ -99,6 +107,8 @@ struct ClientData {
 
     activation: Option<XdgActivationV1>,
     pub activation_tokens: Vec<String>,
+
+    wm_dialog: Option<XdgWmDialogV1>,
 }
 
 impl ClientData {
@@ -120,6 +130,12 @@ pub struct LayerState {
     pub last_serial: u32,
 }
 
+pub struct PopupState {
+    pub popup: xdg_popup::XdgPopup,
+    // x, y, width, height relative to the parent
+    pub configures: Vec<(i32, i32, i32, i32)>,
+}
+
 pub struct WindowState {
     pub surface: wl_surface::WlSurface,
     pub xdg_surface: xdg_surface::XdgSurface,
@@ -165,6 +181,10 @@ pub enum IpcEvent {
         symbol: String,
     },
     Screencast(bool),
+    TagLayout {
+        index: u32,
+        symbol: String,
+    },
     FocusedOutput,
     FocusedToplevel {
         title: Option<String>,
@@ -233,6 +253,7 @@ impl Client {
 
         let data = ClientData {
             compositor: None,
+            subcompositor: None,
             wm_base: None,
             shm: None,
             buffer: None,
@@ -243,9 +264,11 @@ impl Client {
             layer_shell: None,
             layers: Vec::new(),
             windows: Vec::new(),
+            popups: Vec::new(),
             ipc_output: None,
             ipc_seat: None,
             ipc_status_manager: None,
+            ipc_status_global: None,
             ipc_control: None,
             ipc_output_status: None,
             ipc_seat_status: None,
@@ -270,6 +293,7 @@ impl Client {
 
             activation: None,
             activation_tokens: Vec::new(),
+            wm_dialog: None,
         };
 
         let mut client = Client { conn, queue, data };
@@ -401,6 +425,66 @@ impl Client {
         let _ = self.queue.flush();
     }
 
+    /// Request a frame callback, the flag is set once it is done
+    pub fn request_frame(&self, win: usize) -> Arc<AtomicBool> {
+        self.request_surface_frame(&self.data.windows[win].surface)
+    }
+
+    pub fn request_surface_frame(&self, surface: &wl_surface::WlSurface) -> Arc<AtomicBool> {
+        let done = Arc::new(AtomicBool::new(false));
+        let qh = self.queue.handle();
+        surface.frame(&qh, done.clone());
+        let _ = self.queue.flush();
+        done
+    }
+
+    /// Create a sync subsurface of the window with a buffer of the given size,
+    /// applied on the next commit of the window
+    pub fn create_subsurface(
+        &self,
+        win: usize,
+        pos: (i32, i32),
+        size: (i32, i32),
+    ) -> wl_surface::WlSurface {
+        let qh = self.queue.handle();
+        let sub = self
+            .data
+            .subcompositor
+            .as_ref()
+            .expect("wl_subcompositor not bound");
+        let surface = self.create_surface();
+        let subsurface = sub.get_subsurface(&surface, &self.data.windows[win].surface, &qh, ());
+        subsurface.set_position(pos.0, pos.1);
+        let buffer = self.create_shm_buffer(size.0, size.1);
+        surface.attach(Some(&buffer), 0, 0);
+        surface.commit();
+        let _ = self.queue.flush();
+        surface
+    }
+
+    /// Attach a buffer of the given size with a buffer scale and commit
+    pub fn commit_scaled_buffer(&self, win: usize, size: (i32, i32), scale: i32) {
+        let buffer = self.create_shm_buffer(size.0, size.1);
+        let surface = &self.data.windows[win].surface;
+        surface.set_buffer_scale(scale);
+        surface.attach(Some(&buffer), 0, 0);
+        surface.commit();
+        let _ = self.queue.flush();
+    }
+
+    /// Mark the whole surface opaque, applied on the next commit
+    pub fn set_opaque(&self, win: usize) {
+        let qh = self.queue.handle();
+        let comp = self.data.compositor.as_ref().expect("compositor not bound");
+        let region = comp.create_region(&qh, ());
+        region.add(0, 0, 10_000, 10_000);
+        self.data.windows[win]
+            .surface
+            .set_opaque_region(Some(&region));
+        region.destroy();
+        let _ = self.queue.flush();
+    }
+
     pub fn destroy_window(&mut self, win: usize) {
         let ws = &self.data.windows[win];
         ws.toplevel.destroy();
@@ -451,6 +535,50 @@ impl Client {
         idx
     }
 
+    pub fn layer(&self, ls: usize) -> &LayerState {
+        &self.data.layers[ls]
+    }
+
+    /// Create a popup of the layer surface above anchor_rect that may slide
+    /// vertically, and send its initial commit
+    pub fn create_layer_popup(
+        &mut self,
+        ls: usize,
+        size: (i32, i32),
+        anchor_rect: (i32, i32, i32, i32),
+    ) -> usize {
+        let qh = self.queue.handle();
+        let comp = self.data.compositor.as_ref().expect("compositor not bound");
+        let wm = self.data.wm_base.as_ref().expect("xdg_wm_base not bound");
+
+        let positioner = wm.create_positioner(&qh, ());
+        positioner.set_size(size.0, size.1);
+        let (x, y, w, h) = anchor_rect;
+        positioner.set_anchor_rect(x, y, w, h);
+        positioner.set_anchor(xdg_positioner::Anchor::Top);
+        positioner.set_gravity(xdg_positioner::Gravity::Top);
+        positioner.set_constraint_adjustment(xdg_positioner::ConstraintAdjustment::SlideY);
+
+        let surface = comp.create_surface(&qh, ());
+        let xdg = wm.get_xdg_surface(&surface, &qh, ());
+        let popup = xdg.get_popup(None, &positioner, &qh, ());
+        self.data.layers[ls].layer_surface.get_popup(&popup);
+        positioner.destroy();
+        surface.commit();
+
+        let idx = self.data.popups.len();
+        self.data.popups.push(PopupState {
+            popup,
+            configures: Vec::new(),
+        });
+        let _ = self.queue.flush();
+        idx
+    }
+
+    pub fn take_popup_configures(&mut self, popup: usize) -> Vec<(i32, i32, i32, i32)> {
+        self.data.popups[popup].configures.drain(..).collect()
+    }
+
     pub fn layer_commit(&self, ls: usize) {
         self.data.layers[ls].surface.commit();
         let _ = self.queue.flush();
@@ -474,6 +602,19 @@ impl Client {
 
     // monotile-ipc
 
+    // bind the status manager again at an older version, as a client built
+    // against that revision of the protocol would
+    pub fn rebind_status_manager(&mut self, version: u32) {
+        let qh = self.queue.handle();
+        let (registry, name) = self
+            .data
+            .ipc_status_global
+            .clone()
+            .expect("ipc_status_global");
+        self.data.ipc_status_manager = Some(registry.bind(name, version, &qh, ()));
+        let _ = self.queue.flush();
+    }
+
     pub fn bind_output_status(&mut self) {
         let qh = self.queue.handle();
         let mgr = self
@@ -567,6 +708,21 @@ impl Client {
         self.data.activation_tokens.drain(..).collect()
     }
 
+    // xdg-dialog
+
+    pub fn set_modal(&mut self, win: usize) -> XdgDialogV1 {
+        let qh = self.queue.handle();
+        let wm = self
+            .data
+            .wm_dialog
+            .as_ref()
+            .expect("xdg_wm_dialog_v1 not bound");
+        let dialog = wm.get_xdg_dialog(&self.data.windows[win].toplevel, &qh, ());
+        dialog.set_modal();
+        let _ = self.queue.flush();
+        dialog
+    }
+
     // dwl-ipc
 
     pub fn bind_dwl_output(&mut self) {
@@ -727,6 +883,9 @@ impl Dispatch<wl_registry::WlRegistry, ()> for ClientData {
                 "wl_compositor" => {
                     state.compositor = Some(registry.bind(name, version, qh, ()));
                 }
+                "wl_subcompositor" => {
+                    state.subcompositor = Some(registry.bind(name, version, qh, ()));
+                }
                 "xdg_wm_base" => {
                     state.wm_base = Some(registry.bind(name, version, qh, ()));
                 }
@@ -744,6 +903,7 @@ impl Dispatch<wl_registry::WlRegistry, ()> for ClientData {
                 }
                 "zmonotile_status_manager_v1" => {
                     state.ipc_status_manager = Some(registry.bind(name, version, qh, ()));
+                    state.ipc_status_global = Some((registry.clone(), name));
                 }
                 "zmonotile_control_v1" => {
                     state.ipc_control = Some(registry.bind(name, version, qh, ()));
@@ -770,6 +930,9 @@ impl Dispatch<wl_registry::WlRegistry, ()> for ClientData {
                 "xdg_activation_v1" => {
                     state.activation = Some(registry.bind(name, version, qh, ()));
                 }
+                "xdg_wm_dialog_v1" => {
+                    state.wm_dialog = Some(registry.bind(name, version, qh, ()));
+                }
                 _ => {}
             }
         }
@@ -788,6 +951,42 @@ impl Dispatch<wl_compositor::WlCompositor, ()> for ClientData {
     }
 }
 
+impl Dispatch<wl_subcompositor::WlSubcompositor, ()> for ClientData {
+    fn event(
+        _: &mut Self,
+        _: &wl_subcompositor::WlSubcompositor,
+        _: wl_subcompositor::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_subsurface::WlSubsurface, ()> for ClientData {
+    fn event(
+        _: &mut Self,
+        _: &wl_subsurface::WlSubsurface,
+        _: wl_subsurface::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<wl_region::WlRegion, ()> for ClientData {
+    fn event(
+        _: &mut Self,
+        _: &wl_region::WlRegion,
+        _: wl_region::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
 impl Dispatch<xdg_wm_base::XdgWmBase, ()> for ClientData {
     fn event(
         _: &mut Self,
@@ -882,6 +1081,40 @@ impl Dispatch<xdg_toplevel::XdgToplevel, ()> for ClientData {
     }
 }
 
+impl Dispatch<xdg_positioner::XdgPositioner, ()> for ClientData {
+    fn event(
+        _: &mut Self,
+        _: &xdg_positioner::XdgPositioner,
+        _: xdg_positioner::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<xdg_popup::XdgPopup, ()> for ClientData {
+    fn event(
+        state: &mut Self,
+        popup: &xdg_popup::XdgPopup,
+        event: xdg_popup::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let xdg_popup::Event::Configure {
+            x,
+            y,
+            width,
+            height,
+        } = event
+            && let Some(ps) = state.popups.iter_mut().find(|p| p.popup == *popup)
+        {
+            ps.configures.push((x, y, width, height));
+        }
+    }
+}
+
 impl Dispatch<wl_callback::WlCallback, Arc<AtomicBool>> for ClientData {
     fn event(
         _: &mut Self,
@@ -1029,6 +1262,9 @@ impl Dispatch<ZmonotileOutputStatusV1, ()> for ClientData {
             Event::Screencast { active } => {
                 state.ipc_events.push(IpcEvent::Screencast(active != 0));
             }
+            Event::TagLayout { index, symbol } => {
+                state.ipc_events.push(IpcEvent::TagLayout { index, symbol });
+            }
         }
     }
 }
@@ -1475,3 +1711,29 @@ impl Dispatch<XdgActivationTokenV1, ()> for ClientData {
         }
     }
 }
+
+// ── XDG Dialog Dispatch impls ─────────────────────
+
+impl Dispatch<XdgWmDialogV1, ()> for ClientData {
+    fn event(
+        _: &mut Self,
+        _: &XdgWmDialogV1,
+        _: xdg_wm_dialog_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<XdgDialogV1, ()> for ClientData {
+    fn event(
+        _: &mut Self,
+        _: &XdgDialogV1,
+        _: xdg_dialog_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
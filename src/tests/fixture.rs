@@ -1,7 +1,9 @@
 use std::os::unix::net::UnixStream;
 use std::time::Duration;
 
+use smithay::backend::egl::{EGLContext, EGLDevice, EGLDisplay};
 use smithay::backend::input::ButtonState;
+use smithay::backend::renderer::glow::GlowRenderer;
 use smithay::input::pointer::{ButtonEvent, MotionEvent};
 use smithay::output::{Mode, Output, PhysicalProperties, Subpixel};
 use smithay::reexports::calloop::EventLoop;
@@ -9,6 +11,7 @@ use smithay::reexports::wayland_server::protocol::wl_surface::WlSurface;
 use smithay::utils::{Logical, Point, SERIAL_COUNTER};
 
 use super::client::Client;
+use crate::render::{MonotileElement, OutputFrame, compile_shaders, output_elements};
 use crate::{Monotile, config::Config, shell::MonitorSettings};
 
 pub struct Fixture {
@@ -23,11 +26,41 @@ impl Fixture {
     }
 
     pub fn with_config(config: Config) -> Self {
-        let (event_loop, mut mt) = Monotile::new(config);
+        let (event_loop, mt) = Monotile::new(config);
+        let mut f = Fixture {
+            event_loop,
+            mt,
+            clients: Vec::new(),
+        };
+        f.add_output("test", (0, 0));
+        f.mt.state.seat.add_pointer();
+        f
+    }
+
+    // elements of the active output as the backends build them, None without
+    // an EGL device to render with, so tests using it are #[ignore]d
+    pub fn output_elements(&mut self) -> Option<Vec<MonotileElement>> {
+        let device = EGLDevice::enumerate().ok()?.next()?;
+        let display = unsafe { EGLDisplay::new(device) }.ok()?;
+        let context = EGLContext::new(&display).ok()?;
+        let mut renderer = unsafe { GlowRenderer::new(context) }.ok()?;
+        let shaders = compile_shaders(&mut renderer, None).ok()?;
+        let state = &mut self.mt.state;
+        let mon = state.monitors.get_mut(state.active_monitor)?;
+        Some(output_elements(
+            &mut renderer,
+            mon,
+            &mut state.windows,
+            &shaders,
+            &state.config,
+            OutputFrame::default(),
+        ))
+    }
 
-        // headless output
+    // headless 1000x800 output at a global position
+    pub fn add_output(&mut self, name: &str, loc: (i32, i32)) -> Output {
         let output = Output::new(
-            "test".into(),
+            name.into(),
             PhysicalProperties {
                 size: (0, 0).into(),
                 subpixel: Subpixel::Unknown,
@@ -40,16 +73,13 @@ impl Fixture {
             size: (1000, 800).into(),
             refresh: 60_000,
         };
-        output.create_global::<Monotile>(&mt.state.display_handle);
-        output.change_current_state(Some(mode), None, None, Some((0, 0).into()));
+        output.create_global::<Monotile>(&self.mt.state.display_handle);
+        output.change_current_state(Some(mode), None, None, Some(loc.into()));
         output.set_preferred(mode);
-        mt.state.add_monitor(output, MonitorSettings::default());
-
-        Fixture {
-            event_loop,
-            mt,
-            clients: Vec::new(),
-        }
+        self.mt
+            .state
+            .add_monitor(output.clone(), MonitorSettings::default());
+        output
     }
 
     pub fn add_client(&mut self) -> usize {
@@ -1,13 +1,17 @@
 mod client;
 mod fixture;
 mod ipc_client_protocol;
+mod test_close;
 mod test_dnd;
 mod test_dwl_ipc;
 mod test_foreign_toplevel;
+mod test_idle;
 mod test_layer_shell;
 mod test_monotile_ipc;
 mod test_screencopy;
 mod test_session_lock;
+mod test_subsurface;
+mod test_swallow;
 mod test_transitions;
 mod test_views;
 mod test_window_opening;
@@ -0,0 +1,53 @@
+use super::Fixture;
+use crate::config::Action;
+
+fn open_window(f: &mut Fixture, c: usize) -> usize {
+    let w = f.client_mut(c).create_window();
+    f.client_mut(c).commit(w);
+    f.roundtrip(c);
+    f.client_mut(c).ack_and_commit(w);
+    f.roundtrip(c);
+    w
+}
+
+fn closed(f: &Fixture, c: usize, ws: &[usize]) -> Vec<bool> {
+    ws.iter().map(|&w| f.client(c).window(w).closed).collect()
+}
+
+#[test]
+fn kill_others_closes_all_but_the_focused() {
+    let mut f = Fixture::new();
+    f.mt.state.config.bulk_close.confirm = 0;
+    let c = f.add_client();
+    let ws = [0; 3].map(|_| open_window(&mut f, c));
+
+    // the last opened window has focus
+    f.mt.handle_action(Action::KillOthers);
+    f.mt.state.flush_clients();
+    f.roundtrip(c);
+    assert_eq!(closed(&f, c, &ws), [true, true, false]);
+}
+
+#[test]
+fn kill_tag_waits_for_confirmation() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let ws = [0; 2].map(|_| open_window(&mut f, c));
+
+    f.mt.handle_action(Action::KillTag);
+    f.mt.state.flush_clients();
+    f.roundtrip(c);
+    assert_eq!(closed(&f, c, &ws), [false, false], "first press only asks");
+
+    // a different bulk close does not confirm it
+    f.mt.handle_action(Action::KillOthers);
+    f.mt.handle_action(Action::KillTag);
+    f.mt.state.flush_clients();
+    f.roundtrip(c);
+    assert_eq!(closed(&f, c, &ws), [false, false]);
+
+    f.mt.handle_action(Action::KillTag);
+    f.mt.state.flush_clients();
+    f.roundtrip(c);
+    assert_eq!(closed(&f, c, &ws), [true, true]);
+}
@@ -0,0 +1,49 @@
+use std::time::{Duration, Instant};
+
+use smithay::output::Mode;
+
+use super::Fixture;
+use crate::render::frame_interval;
+
+#[test]
+fn idle_fade_is_cancelled_by_input() {
+    let mut f = Fixture::new();
+    f.mt.state.config.idle.fade = 0;
+
+    f.mt.start_idle();
+    assert_eq!(f.mt.state.idle_dim(), 1.0, "zero fade goes black at once");
+
+    f.mt.reset_idle();
+    assert!(f.mt.state.idle_since.is_none());
+    assert_eq!(f.mt.state.idle_dim(), 0.0);
+}
+
+#[test]
+fn idle_command_replaces_the_fade() {
+    let mut f = Fixture::new();
+    f.mt.state.config.idle.command = Some(vec!["swaylock".into(), "-f".into()]);
+
+    let (cmd, args) = f.mt.state.idle_command().unwrap();
+    assert_eq!((cmd.as_str(), args), ("swaylock", &["-f".to_string()][..]));
+    // as start_idle leaves it, without spawning the command
+    f.mt.state.idle_since = Some(Instant::now());
+    assert_eq!(f.mt.state.idle_dim(), 0.0);
+}
+
+#[test]
+fn fade_paces_to_the_fastest_output() {
+    let mut f = Fixture::new();
+    assert_eq!(
+        f.mt.state.frame_interval(),
+        Duration::from_nanos(16_666_666)
+    );
+
+    let fast = f.add_output("fast", (1000, 0));
+    let mode = Mode {
+        size: (1000, 800).into(),
+        refresh: 144_000,
+    };
+    fast.change_current_state(Some(mode), None, None, None);
+    assert_eq!(frame_interval(&fast), Duration::from_nanos(6_944_444));
+    assert_eq!(f.mt.state.frame_interval(), frame_interval(&fast));
+}
@@ -1,4 +1,10 @@
 use super::Fixture;
+use crate::config::PopupOverflow;
+use smithay::reexports::wayland_server::Resource;
+use wayland_client::Proxy;
+use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_surface_v1::{
+    Anchor, KeyboardInteractivity,
+};
 
 fn open_window(f: &mut Fixture, c: usize) -> usize {
     let w = f.client_mut(c).create_window();
@@ -9,6 +15,13 @@ fn open_window(f: &mut Fixture, c: usize) -> usize {
     w
 }
 
+fn map_layer(f: &mut Fixture, c: usize, ls: usize) {
+    f.client_mut(c).layer_commit(ls);
+    f.roundtrip(c);
+    f.client_mut(c).layer_attach_and_commit(ls);
+    f.roundtrip(c);
+}
+
 /// Regression test for batched layer-shell commits (dwlb toggle-visibility).
 ///
 /// When a layer-shell client re-creates its surface, it may batch the
@@ -40,3 +53,92 @@ fn batched_initial_commits() {
     // the client survived.
     f.roundtrip(c);
 }
+
+#[test]
+fn layer_popup_stays_clear_of_other_exclusive_zones() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+
+    // a 30px bar at the top and a 30px panel at the bottom, both exclusive
+    let bar = f.client_mut(c).create_layer_surface();
+    f.client(c).layer(bar).layer_surface.set_exclusive_zone(30);
+    map_layer(&mut f, c, bar);
+    let panel = f.client_mut(c).create_layer_surface();
+    let ls = &f.client(c).layer(panel).layer_surface;
+    ls.set_anchor(Anchor::Bottom | Anchor::Left | Anchor::Right);
+    ls.set_exclusive_zone(30);
+    map_layer(&mut f, c, panel);
+
+    // a tall menu opening upwards from the panel would reach under the bar
+    let popup = f
+        .client_mut(c)
+        .create_layer_popup(panel, (100, 760), (0, 0, 10, 10));
+    f.roundtrip(c);
+
+    let cfgs = f.client_mut(c).take_popup_configures(popup);
+    let &(_, y, _, h) = cfgs.last().expect("popup should be configured");
+    assert_eq!(h, 760);
+    // the panel starts at y 770, the bar ends at 30
+    assert_eq!(770 + y, 30, "popup should slide below the top bar");
+}
+
+#[test]
+fn oversized_popup_in_a_corner_stays_on_the_output() {
+    for overflow in [PopupOverflow::Slide, PopupOverflow::Shrink] {
+        let mut f = Fixture::new();
+        f.mt.state.config.popup_overflow = overflow;
+        let c = f.add_client();
+        let panel = f.client_mut(c).create_layer_surface();
+        let ls = &f.client(c).layer(panel).layer_surface;
+        ls.set_anchor(Anchor::Bottom | Anchor::Left | Anchor::Right);
+        map_layer(&mut f, c, panel);
+
+        // taller than the output and hanging over its right edge
+        let popup = f
+            .client_mut(c)
+            .create_layer_popup(panel, (100, 900), (995, 0, 10, 10));
+        f.roundtrip(c);
+
+        let cfgs = f.client_mut(c).take_popup_configures(popup);
+        let &(x, y, w, h) = cfgs.last().expect("popup should be configured");
+        // the panel spans the bottom 30px of the 1000x800 output
+        let (x, y) = (x, 770 + y);
+        assert!(x >= 0 && x + w <= 1000, "{overflow:?}: x {x} w {w}");
+        assert_eq!(y, 0, "{overflow:?}: the top stays visible");
+        match overflow {
+            PopupOverflow::Shrink => assert_eq!(h, 800),
+            _ => assert_eq!(h, 900),
+        }
+    }
+}
+
+#[test]
+fn only_exclusive_layers_take_the_keyboard() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    open_window(&mut f, c);
+    let window = f.mt.state.windows.focused_surface();
+    assert!(window.is_some());
+    let kb_focus = |f: &Fixture| f.mt.state.seat.get_keyboard().unwrap().current_focus();
+
+    // a bar without keyboard interactivity never steals focus
+    let ls = f.client_mut(c).create_layer_surface();
+    map_layer(&mut f, c, ls);
+    assert_eq!(kb_focus(&f), window);
+
+    let layer = f.client(c).layer(ls).layer_surface.clone();
+    layer.set_keyboard_interactivity(KeyboardInteractivity::Exclusive);
+    f.client(c).layer_commit(ls);
+    f.roundtrip(c);
+    let surface = f.client(c).layer(ls).surface.id();
+    assert_eq!(
+        kb_focus(&f).map(|s| s.id().protocol_id()),
+        Some(surface.protocol_id())
+    );
+
+    // dropping exclusivity hands the keyboard back
+    layer.set_keyboard_interactivity(KeyboardInteractivity::None);
+    f.client(c).layer_commit(ls);
+    f.roundtrip(c);
+    assert_eq!(kb_focus(&f), window);
+}
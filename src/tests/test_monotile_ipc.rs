@@ -1,7 +1,7 @@
 use super::Fixture;
 use super::client::IpcEvent;
 use super::ipc_client_protocol::monotile::zmonotile_seat_control_v1::Position;
-use crate::config::Action;
+use crate::config::{Action, LayoutMode};
 
 fn open_window(f: &mut Fixture, c: usize) -> usize {
     let w = f.client_mut(c).create_window();
@@ -153,7 +153,7 @@ fn output_status_focused_tags_on_switch() {
     f.roundtrip(c);
     f.client_mut(c).take_ipc_events();
 
-    f.mt.state.mon_mut().set_active_tag(2);
+    f.mt.state.mon_mut().unwrap().set_active_tag(2);
     f.mt.state.ipc.dirty = true;
     f.mt.state.flush_clients();
     f.roundtrip(c);
@@ -165,6 +165,65 @@ fn output_status_focused_tags_on_switch() {
     );
 }
 
+#[test]
+fn output_status_tag_layouts() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    f.client_mut(c).bind_output_status();
+    f.roundtrip(c);
+    f.client_mut(c).take_ipc_events();
+
+    f.mt.handle_action(Action::SetLayout(LayoutMode::Spiral));
+    f.mt.handle_action(Action::FocusTag(1));
+    f.mt.state.flush_clients();
+    f.roundtrip(c);
+
+    let events = f.client_mut(c).take_ipc_events();
+    let symbols: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            IpcEvent::TagLayout { index, symbol } => Some((*index, symbol.as_str())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(symbols.len(), 9, "one symbol per tag, got {events:?}");
+    assert_eq!(symbols[0], (0, "[@]"), "tag 0 keeps its spiral layout");
+    assert_eq!(symbols[1], (1, "[]="));
+    assert!(
+        events.contains(&IpcEvent::Layout {
+            name: "tile".into(),
+            symbol: "[]=".into(),
+        }),
+        "active layout follows the tag, got {events:?}"
+    );
+}
+
+#[test]
+fn version_1_output_status_gets_no_tag_layouts() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    f.client_mut(c).rebind_status_manager(1);
+    f.client_mut(c).bind_output_status();
+    f.roundtrip(c);
+    f.client_mut(c).take_ipc_events();
+
+    f.mt.handle_action(Action::SetLayout(LayoutMode::Spiral));
+    f.mt.state.flush_clients();
+    f.roundtrip(c);
+
+    let events = f.client_mut(c).take_ipc_events();
+    assert!(
+        !events
+            .iter()
+            .any(|e| matches!(e, IpcEvent::TagLayout { .. })),
+        "tag_layout is version 2, got {events:?}"
+    );
+    assert!(
+        events.iter().any(|e| matches!(e, IpcEvent::Layout { .. })),
+        "version 1 events still arrive, got {events:?}"
+    );
+}
+
 #[test]
 fn output_status_occupied_tags_on_map() {
     let mut f = Fixture::new();
@@ -479,12 +538,12 @@ fn seat_control_swap() {
     f.roundtrip(c);
 
     // swap focused window with next
-    let before: Vec<_> = f.mt.state.mon().tag().layout.ids().collect();
+    let before: Vec<_> = f.mt.state.mon().unwrap().tag().layout.ids().collect();
     f.client(c).seat_control().swap(Position::Next);
     f.client(c).flush();
     f.roundtrip(c);
 
-    let after: Vec<_> = f.mt.state.mon().tag().layout.ids().collect();
+    let after: Vec<_> = f.mt.state.mon().unwrap().tag().layout.ids().collect();
     assert_ne!(before, after, "tiled order should change after swap");
 }
 
@@ -495,7 +554,7 @@ fn seat_control_close() {
 
     let w = open_window(&mut f, c);
     assert!(
-        f.mt.state.mon().tag().focused_id().is_some(),
+        f.mt.state.mon().unwrap().tag().focused_id().is_some(),
         "window should be focused"
     );
 
@@ -569,12 +628,12 @@ fn seat_control_adjust_main_count() {
     f.client_mut(c).bind_seat_control();
     f.roundtrip(c);
 
-    let before = f.mt.state.mon().tag().layout.main_count;
+    let before = f.mt.state.mon().unwrap().tag().layout.main_count;
     f.client(c).seat_control().adjust_main_count(1);
     f.client(c).flush();
     f.roundtrip(c);
 
-    let after = f.mt.state.mon().tag().layout.main_count;
+    let after = f.mt.state.mon().unwrap().tag().layout.main_count;
     assert_eq!(after, before + 1, "main_count should increase by 1");
 }
 
@@ -589,7 +648,7 @@ fn seat_control_set_main_ratio() {
     f.client(c).flush();
     f.roundtrip(c);
 
-    let ratio = f.mt.state.mon().tag().layout.main_factor;
+    let ratio = f.mt.state.mon().unwrap().tag().layout.main_factor;
     assert!(
         (ratio - 0.7).abs() < 0.01,
         "main_factor should be ~0.7, got {ratio}"
@@ -624,7 +683,7 @@ fn two_clients_both_get_events() {
     f.client_mut(c1).take_ipc_events();
     f.client_mut(c2).take_ipc_events();
 
-    f.mt.state.mon_mut().set_active_tag(4);
+    f.mt.state.mon_mut().unwrap().set_active_tag(4);
     f.mt.state.ipc.dirty = true;
     f.mt.state.flush_clients();
     f.roundtrip(c1);
@@ -653,7 +712,7 @@ fn output_status_destroy_stops_events() {
     f.client_mut(c).destroy_output_status();
     f.roundtrip(c);
 
-    f.mt.state.mon_mut().set_active_tag(5);
+    f.mt.state.mon_mut().unwrap().set_active_tag(5);
     f.mt.state.ipc.dirty = true;
     f.mt.state.flush_clients();
     f.roundtrip(c);
@@ -680,3 +739,55 @@ fn seat_status_destroy_stops_events() {
     let events = f.client_mut(c).take_ipc_events();
     assert!(events.is_empty(), "no events after destroy, got {events:?}");
 }
+
+#[test]
+fn seat_control_screenshot_stays_in_the_screenshot_dir() {
+    let mut f = Fixture::new();
+    let dir = tempfile::tempdir().unwrap();
+    f.mt.state.config.screenshot_dir = Some(dir.path().into());
+    let c = f.add_client();
+    f.client_mut(c).bind_seat_control();
+    f.roundtrip(c);
+
+    let shoot = |f: &mut Fixture, name: &str| {
+        f.client(c)
+            .seat_control()
+            .screenshot(name.into(), 0, 0, 0, 0, 0, 0);
+        control_roundtrip(f, c);
+        f.mt.state.screenshots.pop().map(|s| s.path)
+    };
+    assert_eq!(shoot(&mut f, "shot.png"), Some(dir.path().join("shot.png")));
+    for name in [
+        "../shot.png",
+        "/tmp/shot.png",
+        "a/shot.png",
+        ".bashrc",
+        "..",
+    ] {
+        assert_eq!(shoot(&mut f, name), None, "{name} escapes the directory");
+    }
+    let named = shoot(&mut f, "").expect("a timestamped name");
+    assert_eq!(named.parent(), Some(dir.path()));
+}
+
+#[cfg(feature = "dump")]
+#[test]
+fn dump_state_writes_windows_per_tag() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    open_window(&mut f, c);
+    open_window(&mut f, c);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("state.json");
+
+    f.mt.handle_action(Action::DumpState(Some(path.clone())));
+    let json = std::fs::read_to_string(&path).expect("dump written");
+    let dump: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let tags = &dump["monitors"][0]["tags"];
+    let windows = tags[0]["windows"].as_array().unwrap();
+    assert_eq!(windows.len(), 2);
+    let focused: Vec<_> = windows.iter().filter(|w| w["focused"] == true).collect();
+    assert_eq!(focused.len(), 1);
+    assert_eq!(focused[0]["id"], dump["focused"]);
+    assert!(tags[1]["windows"].as_array().unwrap().is_empty());
+}
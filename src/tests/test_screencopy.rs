@@ -4,6 +4,7 @@ use super::Fixture;
 use super::client::{
     CaptureFrameEvent, CaptureSessionEvent, CursorSessionEvent, ForeignToplevelEvent,
 };
+use crate::config::Action;
 
 // ── Helpers ────────────────────────────────────────
 
@@ -455,7 +456,7 @@ fn toplevel_capture_sets_screencast_flag() {
     f.roundtrip(c);
 
     open_window_and_get_handle(&mut f, c);
-    let id = f.mt.state.mon().tag().focused_id().expect("focused window");
+    let id = f.mt.state.focused_id().expect("focused window");
     assert!(
         !(f.mt.state.windows[id].screencasts > 0),
         "screencast off initially"
@@ -496,7 +497,7 @@ fn output_capture_does_not_set_screencast_flag() {
     f.roundtrip(c);
 
     open_window_and_get_handle(&mut f, c);
-    let id = f.mt.state.mon().tag().focused_id().expect("focused window");
+    let id = f.mt.state.focused_id().expect("focused window");
 
     let source = f
         .client(c)
@@ -557,7 +558,7 @@ fn cursor_session_enter_and_position() {
     // Simulate pointer motion to trigger cursor enter + position
     let ptr = f.mt.state.seat.get_pointer().unwrap();
     let pos = ptr.current_location();
-    let output = &f.mt.state.mon().output;
+    let output = &f.mt.state.mon().unwrap().output;
     let hotspot = f.mt.state.cursor.hotspot;
     f.mt.state
         .screencopy
@@ -599,7 +600,7 @@ fn cursor_session_hotspot() {
 
     let ptr = f.mt.state.seat.get_pointer().unwrap();
     let pos = ptr.current_location();
-    let output = &f.mt.state.mon().output;
+    let output = &f.mt.state.mon().unwrap().output;
     let hotspot = f.mt.state.cursor.hotspot;
     f.mt.state
         .screencopy
@@ -647,3 +648,35 @@ fn cursor_session_stopped_on_destroy() {
         "inner capture session should receive stopped, got {events:?}",
     );
 }
+
+#[test]
+fn captured_window_on_a_hidden_tag_keeps_getting_frames() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    f.roundtrip(c);
+
+    let w = open_window_and_get_handle(&mut f, c);
+    f.client_mut(c).take_foreign_toplevel_events();
+    let handles = f.client_mut(c).take_foreign_toplevel_handles();
+    let source = f
+        .client(c)
+        .create_toplevel_capture_source(&handles[0])
+        .expect("create_source");
+    let _session = f
+        .client(c)
+        .create_capture_session(&source, false)
+        .expect("create_session");
+    f.roundtrip(c);
+
+    f.mt.handle_action(Action::FocusTag(1));
+    let output = f.mt.state.mon().unwrap().output.clone();
+    let frame = f.client_mut(c).request_frame(w);
+    f.client_mut(c).ack_and_commit(w);
+    f.roundtrip(c);
+    f.mt.state.send_frame_callbacks(&output, None);
+    f.roundtrip(c);
+    assert!(
+        frame.load(std::sync::atomic::Ordering::Relaxed),
+        "the stream keeps the hidden window drawing"
+    );
+}
@@ -0,0 +1,86 @@
+use std::sync::atomic::Ordering;
+
+use smithay::{reexports::wayland_server::Resource, utils::Point};
+use wayland_client::Proxy;
+
+use super::Fixture;
+
+fn open_window(f: &mut Fixture, c: usize) -> usize {
+    let w = f.client_mut(c).create_window();
+    f.client_mut(c).commit(w);
+    f.roundtrip(c);
+    f.client_mut(c).ack_and_commit(w);
+    f.roundtrip(c);
+    w
+}
+
+#[test]
+fn subsurface_gets_input_and_frames_at_its_offset() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let w = open_window(&mut f, c);
+
+    // sync subsurface, its state including the frame request lands with the parent commit
+    let child = f.client_mut(c).create_subsurface(w, (50, 40), (20, 20));
+    let frame = f.client_mut(c).request_surface_frame(&child);
+    child.commit();
+    f.client_mut(c).ack_and_commit(w);
+    f.roundtrip(c);
+
+    let id = f.mt.state.focused_id().unwrap();
+    let mon = f.mt.state.mon().unwrap();
+    let output = mon.output.clone();
+    let loc = mon
+        .window_rect(&f.mt.state.windows, id)
+        .unwrap()
+        .loc
+        .to_f64();
+
+    let under = f.mt.state.surface_under(loc + Point::from((55.0, 45.0)));
+    let (surface, origin) = under.surface.expect("subsurface should be hit");
+    assert_eq!(
+        surface.id().protocol_id(),
+        child.id().protocol_id(),
+        "pointer over the subsurface should target it"
+    );
+    assert_eq!(origin, loc + Point::from((50.0, 40.0)));
+    assert_eq!(under.window, Some(id));
+
+    let under = f.mt.state.surface_under(loc + Point::from((0.5, 0.5)));
+    let (surface, _) = under.surface.expect("main surface should be hit");
+    assert_ne!(surface.id().protocol_id(), child.id().protocol_id());
+
+    f.mt.state.send_frame_callbacks(&output, None);
+    f.roundtrip(c);
+    assert!(
+        frame.load(Ordering::Relaxed),
+        "subsurface should get its frame callback"
+    );
+}
+
+#[test]
+fn oversized_tiled_buffer_takes_no_input_outside_its_tile() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let w = open_window(&mut f, c);
+    let id = f.mt.state.focused_id().unwrap();
+
+    // ignores the configured size
+    f.client_mut(c).commit_scaled_buffer(w, (2000, 2000), 1);
+    f.roundtrip(c);
+
+    let mon = f.mt.state.mon().unwrap();
+    let tile = mon.window_rect(&f.mt.state.windows, id).unwrap();
+    let inside = tile.loc.to_f64() + Point::from((10.0, 10.0));
+    assert_eq!(f.mt.state.surface_under(inside).window, Some(id));
+
+    // the outer gap below and right of the tile
+    let corner = (tile.loc + tile.size.to_point()).to_f64() + Point::from((0.5, 0.5));
+    assert!(
+        mon.geometry().to_f64().contains(corner),
+        "still on the output"
+    );
+    let under = f.mt.state.surface_under(corner);
+    assert!(under.surface.is_none(), "the overflow is not hit");
+    assert_eq!(under.window, None);
+}
@@ -0,0 +1,113 @@
+use super::Fixture;
+use super::client::ForeignToplevelEvent;
+
+fn open_window_with(f: &mut Fixture, c: usize, app_id: &str) -> usize {
+    let w = f.client_mut(c).create_window();
+    f.client(c).window(w).toplevel.set_app_id(app_id.into());
+    f.client(c).flush();
+    f.client_mut(c).commit(w);
+    f.roundtrip(c);
+    f.client_mut(c).ack_and_commit(w);
+    f.roundtrip(c);
+    w
+}
+
+#[test]
+fn window_from_a_terminal_takes_its_place() {
+    let mut f = Fixture::new();
+    f.mt.state.config.swallow.terminals = vec!["term".into()];
+    // the test clients all run in this process, so every window descends from the terminal
+    let c = f.add_client();
+    open_window_with(&mut f, c, "other");
+    open_window_with(&mut f, c, "term");
+    let term = f.mt.state.focused_id().unwrap();
+    let ids = |f: &Fixture| {
+        f.mt.state
+            .mon()
+            .unwrap()
+            .tag()
+            .layout
+            .ids()
+            .collect::<Vec<_>>()
+    };
+    let before = ids(&f);
+
+    let viewer = open_window_with(&mut f, c, "viewer");
+    let viewer_id = f.mt.state.focused_id().unwrap();
+    let swapped: Vec<_> = before
+        .iter()
+        .map(|&id| if id == term { viewer_id } else { id })
+        .collect();
+    assert_eq!(ids(&f), swapped, "the viewer is in the terminal's slot");
+    assert!(!f.mt.state.mon().unwrap().tag().contains(term));
+
+    f.client_mut(c).destroy_window(viewer);
+    f.roundtrip(c);
+    assert_eq!(ids(&f), before, "the terminal is back");
+}
+
+#[test]
+fn excluded_windows_do_not_swallow() {
+    let mut f = Fixture::new();
+    f.mt.state.config.swallow.terminals = vec!["term".into()];
+    f.mt.state.config.swallow.exclude = vec!["editor".into()];
+    let c = f.add_client();
+    open_window_with(&mut f, c, "term");
+    let term = f.mt.state.focused_id().unwrap();
+
+    open_window_with(&mut f, c, "editor");
+    assert!(f.mt.state.mon().unwrap().tag().contains(term));
+}
+
+#[test]
+fn swallowed_terminal_leaves_the_toplevel_list_until_restored() {
+    let mut f = Fixture::new();
+    f.mt.state.config.swallow.terminals = vec!["term".into()];
+    let c = f.add_client();
+    open_window_with(&mut f, c, "term");
+    f.client_mut(c).take_foreign_toplevel_events();
+
+    let viewer = open_window_with(&mut f, c, "viewer");
+    let events = f.client_mut(c).take_foreign_toplevel_events();
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, ForeignToplevelEvent::Closed { .. })),
+        "the terminal's handle is closed, got {events:?}"
+    );
+
+    f.client_mut(c).destroy_window(viewer);
+    f.roundtrip(c);
+    let events = f.client_mut(c).take_foreign_toplevel_events();
+    assert!(
+        events.iter().any(|e| matches!(
+            e, ForeignToplevelEvent::AppId { app_id, .. } if app_id == "term"
+        )),
+        "the terminal is listed again, got {events:?}"
+    );
+}
+
+#[test]
+fn swallowing_window_only_takes_the_terminals_tags() {
+    let mut f = Fixture::new();
+    f.mt.state.config.swallow.terminals = vec!["term".into()];
+    let c = f.add_client();
+    open_window_with(&mut f, c, "term");
+    let term = f.mt.state.focused_id().unwrap();
+    // the terminal lives on the third tag only
+    let mon = f.mt.state.mon_mut().unwrap();
+    mon.tags[0].remove(term);
+    mon.tags[2].add(term);
+    let on = |f: &Fixture, id| -> Vec<usize> {
+        let mon = f.mt.state.mon().unwrap();
+        (0..mon.tags.len())
+            .filter(|&t| mon.tags[t].contains(id))
+            .collect()
+    };
+
+    open_window_with(&mut f, c, "viewer");
+    let viewer = f.mt.state.windows.live().find(|we| we.app_id == "viewer");
+    let viewer = viewer.unwrap().id;
+    assert_eq!(on(&f, viewer), vec![2]);
+    assert!(on(&f, term).is_empty());
+}
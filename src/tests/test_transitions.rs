@@ -19,12 +19,13 @@ fn settle(f: &mut Fixture, c: usize, win: usize) {
 }
 
 fn views_len(f: &Fixture) -> usize {
-    f.mt.state.mon().views.len()
+    f.mt.state.mon().unwrap().views.len()
 }
 
 fn front_tiled_rect(f: &Fixture, id: WindowId) -> Option<Rectangle<i32, Logical>> {
     f.mt.state
         .mon()
+        .unwrap()
         .views
         .front()?
         .tiled
@@ -36,6 +37,7 @@ fn front_tiled_rect(f: &Fixture, id: WindowId) -> Option<Rectangle<i32, Logical>
 fn front_shows(f: &Fixture, id: WindowId) -> bool {
     f.mt.state
         .mon()
+        .unwrap()
         .views
         .front()
         .is_some_and(|v| v.contains(id))
@@ -51,7 +53,7 @@ fn closing_stack_window_holds_view_until_main_commits() {
     settle(&mut f, c, 0); // main acks the split
 
     assert_eq!(views_len(&f), 1, "layout settled");
-    let main_id = f.mt.state.mon().views.front().unwrap().tiled[0].id;
+    let main_id = f.mt.state.mon().unwrap().views.front().unwrap().tiled[0].id;
     let main_before = front_tiled_rect(&f, main_id).unwrap();
 
     // destroy the stack window
@@ -64,7 +66,7 @@ fn closing_stack_window_holds_view_until_main_commits() {
         "closing the stack window queues a held view"
     );
     assert_eq!(
-        f.mt.state.mon().views.front().unwrap().tiled.len(),
+        f.mt.state.mon().unwrap().views.front().unwrap().tiled.len(),
         2,
         "held view still renders both tiles (destroyed one from its texture)",
     );
@@ -99,7 +101,14 @@ fn closing_lone_window_settles_immediately() {
 
     assert_eq!(views_len(&f), 1, "no survivors to wait on");
     assert!(
-        f.mt.state.mon().views.front().unwrap().tiled.is_empty(),
+        f.mt.state
+            .mon()
+            .unwrap()
+            .views
+            .front()
+            .unwrap()
+            .tiled
+            .is_empty(),
         "nothing left to draw",
     );
 }
@@ -161,7 +170,14 @@ fn tag_switch_without_resize_settles_immediately() {
 
     assert_eq!(views_len(&f), 1, "empty incoming tag needs no hold");
     assert!(
-        f.mt.state.mon().views.front().unwrap().tiled.is_empty(),
+        f.mt.state
+            .mon()
+            .unwrap()
+            .views
+            .front()
+            .unwrap()
+            .tiled
+            .is_empty(),
         "the new tag is presented immediately",
     );
 }
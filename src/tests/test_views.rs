@@ -1,7 +1,15 @@
-use smithay::utils::Rectangle;
+use smithay::{
+    backend::input::KeyState,
+    input::keyboard::{FilterResult, Keycode, Keysym},
+    output::Mode,
+    utils::{Logical, Point, Rectangle, SERIAL_COUNTER},
+};
 
 use super::Fixture;
-use crate::{config::Config, shell::View};
+use crate::{
+    config::{Action, Config, Direction, Mods, OsdTrigger, Rel},
+    shell::View,
+};
 
 fn open_window(f: &mut Fixture, c: usize) -> usize {
     let w = f.client_mut(c).create_window();
@@ -19,7 +27,7 @@ fn settle(f: &mut Fixture, c: usize, win: usize) {
 }
 
 fn project(f: &Fixture) -> View {
-    View::project(f.mt.state.mon().tag(), Vec::new())
+    View::project(f.mt.state.mon().unwrap().tag(), Vec::new())
 }
 
 #[test]
@@ -43,7 +51,7 @@ fn two_tiled_windows_project_in_layout_order() {
     let _b = open_window(&mut f, c);
     settle(&mut f, c, a);
 
-    let order: Vec<_> = f.mt.state.mon().tag().layout.ids().collect();
+    let order: Vec<_> = f.mt.state.mon().unwrap().tag().layout.ids().collect();
     let v = project(&f);
     assert_eq!(v.tiled.len(), 2);
     assert_eq!(
@@ -58,7 +66,7 @@ fn floating_window_lands_in_floating_group() {
     let mut f = Fixture::new();
     let c = f.add_client();
     let a = open_window(&mut f, c);
-    let id = f.mt.state.mon().tag().focused_id().unwrap();
+    let id = f.mt.state.mon().unwrap().tag().focused_id().unwrap();
     f.mt.state.windows[id].set_floating(true);
     f.mt.recompute_layout(f.mt.state.active_monitor);
     settle(&mut f, c, a);
@@ -73,7 +81,7 @@ fn fullscreen_window_projects_to_fullscreen() {
     let mut f = Fixture::new();
     let c = f.add_client();
     let a = open_window(&mut f, c);
-    let id = f.mt.state.mon().tag().focused_id().unwrap();
+    let id = f.mt.state.mon().unwrap().tag().focused_id().unwrap();
     f.mt.state.windows[id].set_fullscreen(true);
     f.mt.recompute_layout(f.mt.state.active_monitor);
     settle(&mut f, c, a);
@@ -93,7 +101,7 @@ fn smart_gaps_single_window_fills_area() {
     let a = open_window(&mut f, c);
     settle(&mut f, c, a);
 
-    let tile = f.mt.state.mon().views.front().unwrap().tiled[0].rect;
+    let tile = f.mt.state.mon().unwrap().views.front().unwrap().tiled[0].rect;
     assert_eq!(
         tile,
         Rectangle::from_size((1000, 800).into()),
@@ -113,3 +121,528 @@ fn lone_tiled_is_derivable_from_tiled_len() {
     settle(&mut f, c, a);
     assert_eq!(project(&f).tiled.len(), 2);
 }
+
+#[test]
+fn window_hints_label_windows_in_layout_order() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let a = open_window(&mut f, c);
+    let _b = open_window(&mut f, c);
+    settle(&mut f, c, a);
+
+    f.mt.handle_action(Action::WindowHints);
+    let mon = f.mt.state.mon().unwrap();
+    let order: Vec<_> = mon.tag().layout.ids().collect();
+    let hints: Vec<_> = mon.hints.iter().map(|h| (h.key, h.window)).collect();
+    assert_eq!(
+        hints,
+        vec![('a', order[0]), ('s', order[1])],
+        "home row keys label the windows in layout order",
+    );
+}
+
+#[test]
+fn cycle_layout_shows_layout_name_in_osd() {
+    let mut f = Fixture::new();
+    f.mt.handle_action(Action::CycleLayout);
+    let mon = f.mt.state.mon().unwrap();
+    assert_eq!(mon.tag().layout.name(), "spiral", "layout cycled");
+    let osd = mon.osd.as_ref().expect("layout change shows the osd");
+    assert_eq!(osd.text, "spiral");
+    assert!(mon.osd_timer.is_some(), "osd hides on a timer");
+}
+
+#[test]
+fn osd_on_another_output_keeps_its_own_timer() {
+    let mut f = Fixture::new();
+    let projector = f.add_output("projector", (1000, 0));
+    f.mt.show_osd("first".into());
+    f.mt.handle_action(Action::FocusOutput(Direction::Right));
+    f.mt.show_osd("second".into());
+
+    let monitors = &f.mt.state.monitors;
+    let (idx, _) = monitors.by_output(&projector).unwrap();
+    for mon in [&monitors[0], &monitors[idx]] {
+        assert!(mon.osd.is_some());
+        assert!(mon.osd_timer.is_some(), "each osd hides on its own timer");
+    }
+    assert_eq!(monitors[0].osd.as_ref().unwrap().text, "first");
+}
+
+#[test]
+fn main_ratio_osd_sits_on_the_split() {
+    let mut config = Config::new();
+    config.osd.show_on = vec![OsdTrigger::MainRatio];
+    let mut f = Fixture::with_config(config);
+    f.mt.handle_action(Action::SetMainRatio(0.5));
+
+    let mon = f.mt.state.mon().unwrap();
+    let osd = mon.osd.as_ref().expect("ratio change shows the osd");
+    assert_eq!(osd.text, "50% / 1");
+    let center = osd.center.expect("placed at the split");
+    let width = mon.geometry().size.w;
+    assert!((center.x - width / 2).abs() <= 1, "split is halfway across");
+}
+
+#[test]
+fn tile_keeps_committed_size_until_the_resize_is_committed() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let a = open_window(&mut f, c);
+    settle(&mut f, c, a);
+
+    let id = f.mt.state.focused_id().unwrap();
+    let tile =
+        f.mt.state
+            .mon()
+            .unwrap()
+            .tag()
+            .layout
+            .position_of(id)
+            .unwrap();
+    assert_eq!(
+        f.mt.state.windows[id].committed_rect(tile),
+        tile,
+        "a settled window fills its tile",
+    );
+
+    // a second window shrinks the first, its 1x1 buffer is still the old one
+    let _b = open_window(&mut f, c);
+    let tile =
+        f.mt.state
+            .mon()
+            .unwrap()
+            .tag()
+            .layout
+            .position_of(id)
+            .unwrap();
+    let rect = f.mt.state.windows[id].committed_rect(tile);
+    assert_eq!(rect.loc, tile.loc, "drawn from the tile origin");
+    assert_eq!(
+        rect.size,
+        (1, 1).into(),
+        "drawn at the committed buffer size"
+    );
+
+    settle(&mut f, c, a);
+    assert_eq!(f.mt.state.windows[id].committed_rect(tile), tile);
+}
+
+#[test]
+fn mirror_is_per_tag() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    open_window(&mut f, c);
+    open_window(&mut f, c);
+    f.mt.handle_action(Action::FocusTag(1));
+    open_window(&mut f, c);
+    open_window(&mut f, c);
+    f.mt.handle_action(Action::ToggleMirror);
+
+    // main window x on each tag
+    let main_x = |f: &Fixture, tag: usize| {
+        let layout = &f.mt.state.mon().unwrap().tags[tag].layout;
+        let id = layout.ids().next().unwrap();
+        layout.position_of(id).unwrap().loc.x
+    };
+    let mon = f.mt.state.mon().unwrap();
+    assert!(mon.tags[1].layout.mirrored);
+    assert!(!mon.tags[0].layout.mirrored, "other tags keep their side");
+    assert_eq!(mon.tags[1].layout.symbol(), "=[]");
+    assert!(
+        main_x(&f, 1) > main_x(&f, 0),
+        "main is on the right of tag 1"
+    );
+}
+
+#[test]
+fn reset_all_layouts_resets_every_tag() {
+    let mut f = Fixture::new();
+    f.mt.handle_action(Action::SetMainRatio(0.7));
+    f.mt.handle_action(Action::FocusTag(1));
+    f.mt.handle_action(Action::SetMainRatio(0.3));
+    f.mt.handle_action(Action::SetMainCount(3));
+
+    f.mt.handle_action(Action::ResetAllLayouts);
+    let tile = f.mt.state.config.layout.tile.clone();
+    for tag in &f.mt.state.mon().unwrap().tags[..2] {
+        assert_eq!(tag.layout.main_factor, tile.main_factor);
+        assert_eq!(tag.layout.main_count, tile.main_count);
+    }
+}
+
+#[test]
+fn duplicate_output_toggles_a_clone_of_the_active_output() {
+    let mut f = Fixture::new();
+    let projector = f.add_output("projector", (1000, 0));
+    let monitors = &f.mt.state.monitors;
+    let (target, _) = monitors.by_output(&projector).unwrap();
+    assert_eq!(monitors.duplicate_source(target), None);
+
+    f.mt.handle_action(Action::DuplicateOutput(Direction::Right));
+    let monitors = &f.mt.state.monitors;
+    assert_eq!(
+        monitors.duplicate_source(target),
+        Some((0, 1.0)),
+        "same size"
+    );
+    assert_eq!(monitors.duplicates_of(0), vec![projector.clone()]);
+    assert_eq!(monitors.duplicate_source(0), None);
+    assert_eq!(monitors.letterbox(target), (0, 0).into());
+
+    f.mt.handle_action(Action::DuplicateOutput(Direction::Right));
+    let monitors = &f.mt.state.monitors;
+    assert_eq!(monitors.duplicate_source(target), None, "toggled off");
+    assert!(monitors.duplicates_of(0).is_empty());
+}
+
+#[test]
+fn duplicate_is_letterboxed_to_the_source_aspect() {
+    let mut f = Fixture::new();
+    let projector = f.add_output("projector", (1000, 0));
+    let mode = Mode {
+        size: (1920, 1080).into(),
+        refresh: 60_000,
+    };
+    projector.change_current_state(Some(mode), None, None, None);
+    f.mt.handle_action(Action::DuplicateOutput(Direction::Right));
+
+    let monitors = &f.mt.state.monitors;
+    let (target, _) = monitors.by_output(&projector).unwrap();
+    // 1000x800 fits 1080 high at 1.35, leaving 570 px of bars
+    assert_eq!(monitors.duplicate_source(target), Some((0, 1.35)));
+    assert_eq!(monitors.letterbox(target), (285, 0).into());
+}
+
+#[test]
+fn duplicate_takes_no_pointer_focus_or_windows() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let projector = f.add_output("projector", (1000, 0));
+    f.mt.handle_action(Action::DuplicateOutput(Direction::Right));
+    let (target, _) = f.mt.state.monitors.by_output(&projector).unwrap();
+
+    let monitors = &f.mt.state.monitors;
+    assert_eq!(monitors.at((1500.0, 400.0).into()), None);
+    assert_eq!(monitors.across(0, (1000.0, 400.0).into()), None);
+    assert_eq!(monitors.in_direction(0, Direction::Right), None);
+    assert_eq!(
+        monitors.output_in_direction(0, Direction::Right),
+        Some(target)
+    );
+
+    f.mt.focus_monitor(target);
+    assert_eq!(f.mt.state.active_monitor, 0, "the source keeps focus");
+
+    f.mt.state.monitors[target].duplicate = None;
+    f.mt.focus_monitor(target);
+    open_window(&mut f, c);
+    f.mt.state.monitors[target].duplicate = Some("test".into());
+    open_window(&mut f, c);
+    let on_target = f.mt.state.monitors[target].window_ids().len();
+    assert_eq!(
+        on_target, 1,
+        "a window opened on a duplicate goes to its source"
+    );
+    assert_eq!(f.mt.state.monitors[0].window_ids().len(), 1);
+}
+
+#[test]
+fn duplicate_toggle_survives_a_reload() {
+    let mut f = Fixture::new();
+    let projector = f.add_output("projector", (1000, 0));
+    f.mt.handle_action(Action::DuplicateOutput(Direction::Right));
+    let (target, _) = f.mt.state.monitors.by_output(&projector).unwrap();
+
+    let rules = f.mt.state.config.outputs.clone();
+    f.mt.state.monitors.update_rules(&rules);
+    assert!(f.mt.state.monitors.is_duplicate(target));
+}
+
+#[test]
+fn zoom_and_expand_promotes_then_sizes_main() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    for _ in 0..3 {
+        open_window(&mut f, c);
+    }
+    let main_rect = |f: &Fixture| {
+        let layout = &f.mt.state.mon().unwrap().tag().layout;
+        let id = layout.ids().next().unwrap();
+        (id, layout.position_of(id).unwrap())
+    };
+    let (_, before) = main_rect(&f);
+    f.mt.handle_action(Action::Focus(Rel::Last));
+    let last = f.mt.state.focused_id().unwrap();
+
+    f.mt.handle_action(Action::ZoomAndExpand(0.7));
+    let (main, rect) = main_rect(&f);
+    assert_eq!(main, last, "zoomed first");
+    assert_eq!(f.mt.state.mon().unwrap().tag().layout.main_factor, 0.7);
+    assert!(rect.size.w > before.size.w, "then expanded");
+
+    // again on the main window keeps it there
+    f.mt.handle_action(Action::ZoomAndExpand(0.6));
+    assert_eq!(main_rect(&f).0, last);
+}
+
+#[test]
+fn switch_window_walks_the_focus_stack() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    for _ in 0..3 {
+        open_window(&mut f, c);
+    }
+    let stack = f.mt.state.mon().unwrap().tag().focus_stack.clone();
+    let selection = |f: &Fixture| f.mt.state.switcher.as_ref().and_then(|s| s.selection());
+
+    f.mt.handle_action(Action::SwitchWindow(1));
+    assert_eq!(selection(&f), Some(stack[1]));
+    f.mt.handle_action(Action::SwitchWindow(1));
+    assert_eq!(selection(&f), Some(stack[2]));
+    f.mt.finish_switch(true);
+    assert!(f.mt.state.switcher.is_none());
+    assert_eq!(f.mt.state.focused_id(), Some(stack[2]));
+
+    // cancelling keeps the focus where it was
+    f.mt.handle_action(Action::SwitchWindow(-1));
+    f.mt.finish_switch(false);
+    assert!(f.mt.state.switcher.is_none());
+    assert_eq!(f.mt.state.focused_id(), Some(stack[2]));
+}
+
+#[test]
+fn submap_takes_one_key_then_leaves() {
+    let mut config = Config::new();
+    let submaps = r#"(submaps: {
+        "layout": [
+            ([], Key("Left"), AdjustMainRatio(-0.05)),
+            ([], Key("w"), EnterSubmap("window")),
+        ],
+        "window": [([], Key("f"), ToggleFloat)],
+    })"#;
+    config.submaps = Config::parse(submaps).unwrap().submaps;
+    let mut f = Fixture::with_config(config);
+    let none = Mods::default();
+
+    f.mt.handle_action(Action::EnterSubmap("layout".into()));
+    assert_eq!(f.mt.state.submaps, ["layout"]);
+    assert!(f.mt.state.submap_timer.is_some());
+    let action = f.mt.submap_key(&[Keysym::Left], none);
+    assert!(matches!(action, Some(Action::AdjustMainRatio(_))));
+    assert!(f.mt.state.submaps.is_empty(), "back to the main binds");
+    assert!(f.mt.state.submap_timer.is_none());
+
+    f.mt.handle_action(Action::EnterSubmap("layout".into()));
+    assert!(f.mt.submap_key(&[Keysym::q], none).is_none());
+    assert!(f.mt.state.submaps.is_empty(), "an unbound key leaves");
+
+    // nested, escape goes back a level
+    f.mt.handle_action(Action::EnterSubmap("layout".into()));
+    let action = f.mt.submap_key(&[Keysym::w], none).unwrap();
+    f.mt.handle_action(action);
+    assert_eq!(f.mt.state.submaps, ["layout", "window"]);
+    assert!(f.mt.submap_key(&[Keysym::Escape], none).is_none());
+    assert_eq!(f.mt.state.submaps, ["layout"]);
+}
+
+#[test]
+fn submap_times_out_without_a_key() {
+    let mut config = Config::new();
+    config.submaps = Config::parse(r#"(submaps: {"layout": []})"#)
+        .unwrap()
+        .submaps;
+    let mut f = Fixture::with_config(config);
+
+    f.mt.handle_action(Action::EnterSubmap("layout".into()));
+    assert_eq!(f.mt.state.submaps, ["layout"]);
+    assert!(f.mt.state.submap_timer.is_some(), "the timeout is armed");
+    // what the timer runs once it fires
+    f.mt.submap_timed_out();
+    assert!(f.mt.state.submaps.is_empty());
+    assert!(f.mt.state.submap_timer.is_none());
+}
+
+#[test]
+fn input_inhibit_indicator_stays_until_turned_off() {
+    let mut config = Config::new();
+    let binds = r#"(binds: [([Ctrl, Alt], Key("Pause"), ToggleInputInhibit)])"#;
+    config.binds = Config::parse(binds).unwrap().binds;
+    let mut f = Fixture::with_config(config);
+
+    f.mt.handle_action(Action::ToggleInputInhibit);
+    assert!(f.mt.state.input_inhibited);
+    // the banner follows the flag, not the transient osd and its timer
+    let mon = f.mt.state.mon().unwrap();
+    assert!(mon.osd.is_none() && mon.osd_timer.is_none());
+    f.dispatch();
+    assert!(f.mt.state.input_inhibited);
+
+    f.mt.handle_action(Action::ToggleInputInhibit);
+    assert!(!f.mt.state.input_inhibited);
+}
+
+#[test]
+fn keys_held_into_input_inhibit_are_still_released() {
+    let mut config = Config::new();
+    let binds = r#"(binds: [([Ctrl, Alt], Key("Pause"), ToggleInputInhibit)])"#;
+    config.binds = Config::parse(binds).unwrap().binds;
+    let mut f = Fixture::with_config(config);
+    let (held, other) = (Keycode::new(38), Keycode::new(39));
+
+    let keyboard = f.mt.state.seat.get_keyboard().unwrap();
+    let serial = SERIAL_COUNTER.next_serial();
+    keyboard.input::<(), _>(&mut f.mt, held, KeyState::Pressed, serial, 0, |_, _, _| {
+        FilterResult::Forward
+    });
+    f.mt.handle_action(Action::ToggleInputInhibit);
+    assert!(f.mt.state.input_inhibited);
+
+    assert!(!f.mt.inhibit_releases(other), "pressed while inhibited");
+    assert!(
+        f.mt.inhibit_releases(held),
+        "its release reaches the client"
+    );
+    assert!(!f.mt.inhibit_releases(held), "only once");
+
+    f.mt.handle_action(Action::ToggleInputInhibit);
+    assert!(f.mt.state.inhibit_held.is_empty());
+}
+
+#[test]
+fn peek_shows_the_previous_tag_until_its_key_is_released() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    open_window(&mut f, c);
+    f.mt.handle_action(Action::FocusTag(1));
+    let key = Keycode::new(30);
+
+    f.mt.begin_peek(key);
+    f.mt.handle_action(Action::PeekPrevTag);
+    assert_eq!(f.mt.state.mon().unwrap().active_tag, 0, "peeking");
+    assert!(f.mt.end_peek(Keycode::new(31)).is_none(), "another key");
+
+    let back = f.mt.end_peek(key).expect("released");
+    f.mt.handle_action(back);
+    assert_eq!(f.mt.state.mon().unwrap().active_tag, 1, "back");
+    assert!(f.mt.state.peek.is_none());
+}
+
+#[test]
+fn double_click_needs_the_same_button_close_by() {
+    let mut f = Fixture::new();
+    let (left, right) = (0x110, 0x111);
+    let at = |x| Point::<f64, Logical>::from((x, 10.0));
+
+    assert!(!f.mt.is_double_click(left, at(10.0)));
+    assert!(f.mt.is_double_click(left, at(12.0)), "within the distance");
+    assert!(
+        !f.mt.is_double_click(left, at(12.0)),
+        "a third press starts over"
+    );
+
+    assert!(!f.mt.is_double_click(right, at(12.0)), "other button");
+    assert!(!f.mt.is_double_click(left, at(12.0)));
+    assert!(!f.mt.is_double_click(left, at(40.0)), "moved too far");
+
+    f.mt.state
+        .config
+        .seats
+        .get_mut("seat0")
+        .unwrap()
+        .double_click_interval = 0;
+    assert!(!f.mt.is_double_click(left, at(40.0)), "interval elapsed");
+}
+
+#[test]
+fn render_stats_toggle_on_every_output() {
+    let mut f = Fixture::new();
+    f.add_output("projector", (1000, 0));
+    let c = f.add_client();
+    open_window(&mut f, c);
+
+    f.mt.handle_action(Action::ToggleRenderStats);
+    assert!(f.mt.state.monitors.iter().all(|m| m.stats.is_some()));
+    assert!(
+        f.mt.state.stats_timer.is_some(),
+        "repaints to keep the numbers current"
+    );
+
+    f.mt.handle_action(Action::ToggleRenderStats);
+    assert!(f.mt.state.monitors.iter().all(|m| m.stats.is_none()));
+    assert!(f.mt.state.stats_timer.is_none());
+}
+
+#[test]
+fn park_cursor_moves_to_the_corner_and_keeps_focus() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    open_window(&mut f, c);
+    open_window(&mut f, c);
+    let focused = f.mt.state.focused_id();
+
+    f.mt.handle_action(Action::ParkCursor);
+    let ptr = f.mt.state.seat.get_pointer().unwrap();
+    assert_eq!(
+        ptr.current_location(),
+        (999.0, 799.0).into(),
+        "bottom right"
+    );
+    assert!(f.mt.state.cursor_parked);
+    assert_eq!(f.mt.state.focused_id(), focused);
+}
+
+#[test]
+fn outline_follows_the_active_output_of_several() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    open_window(&mut f, c);
+    assert!(!f.mt.state.outlined(0), "a single output has no outline");
+
+    let projector = f.add_output("projector", (1000, 0));
+    let (other, _) = f.mt.state.monitors.by_output(&projector).unwrap();
+    assert!(f.mt.state.outlined(0));
+    assert!(!f.mt.state.outlined(other));
+
+    f.mt.handle_action(Action::FocusOutput(Direction::Right));
+    assert!(f.mt.state.outlined(other));
+    assert!(!f.mt.state.outlined(0));
+}
+
+#[test]
+fn unplugged_output_hands_its_windows_to_the_active_one() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let middle = f.add_output("middle", (1000, 0));
+    let right = f.add_output("right", (2000, 0));
+
+    f.mt.handle_action(Action::FocusOutput(Direction::Right));
+    open_window(&mut f, c);
+    let lost = f.mt.state.focused_id().unwrap();
+    f.mt.handle_action(Action::ToggleFloat);
+    f.mt.state.windows[lost].float_geo = Rectangle::new((900, 700).into(), (300, 200).into());
+
+    f.mt.handle_action(Action::FocusOutput(Direction::Right));
+    open_window(&mut f, c);
+    let kept = f.mt.state.focused_id().unwrap();
+
+    f.mt.state.remove_monitor(&middle);
+    let state = &f.mt.state;
+    assert_eq!(
+        state.mon().unwrap().output,
+        right,
+        "still the active output"
+    );
+    let (idx, mon) = state.monitors.by_output(&right).unwrap();
+    assert_eq!(
+        state.windows[kept].monitor, idx,
+        "index after the removed one"
+    );
+    assert_eq!(state.windows[lost].monitor, idx);
+    assert!(mon.tag().contains(lost), "moved to the active tag");
+    let geo = state.windows[lost].float_geo;
+    assert!(
+        mon.geometry().contains_rect(geo),
+        "floating window on the new output: {geo:?}"
+    );
+}
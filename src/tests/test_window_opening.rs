@@ -1,6 +1,11 @@
 use super::Fixture;
-use crate::config::{Action, Rel};
+use crate::config::{Action, Config, Direction, FloatPlacement, FloatSize, FullscreenUnfocus, Rel};
+use crate::render::{MonotileElement, RenderStep};
+use crate::session::Session;
+use crate::shell::{MonitorSettings, WindowId};
+use crate::spawn::PendingSpawn;
 use smithay::{reexports::wayland_server::Resource, utils::Rectangle};
+use std::time::Instant;
 use wayland_protocols::xdg::shell::client::xdg_toplevel::State as ToplevelState;
 
 /// Simulate a client that maps to it's own remembered size on first commit
@@ -29,7 +34,7 @@ fn new_window_not_deactivated_during_open() {
 }
 
 fn windows_on_tag(f: &Fixture) -> usize {
-    f.mt.state.mon().tag().window_ids().len()
+    f.mt.state.mon().unwrap().tag().window_ids().len()
 }
 
 fn open_window(f: &mut Fixture, c: usize) -> usize {
@@ -39,7 +44,7 @@ fn open_window(f: &mut Fixture, c: usize) -> usize {
     f.client_mut(c).ack_and_commit(w);
     f.roundtrip(c);
     assert!(
-        f.mt.state.mon().tag().focused_id().is_some(),
+        f.mt.state.mon().unwrap().tag().focused_id().is_some(),
         "window {w} should be mapped after open_window",
     );
     w
@@ -79,6 +84,30 @@ fn two_windows() {
     );
 }
 
+#[test]
+fn relayout_with_unchanged_sizes_sends_no_configure() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+
+    let w1 = open_window(&mut f, c);
+    let w2 = open_window(&mut f, c);
+    f.client_mut(c).take_configures(w1); // drain
+    f.client_mut(c).take_configures(w2);
+
+    let idx = f.mt.state.active_monitor;
+    f.mt.recompute_layout(idx);
+    f.roundtrip(c);
+
+    assert!(
+        f.client_mut(c).take_configures(w1).is_empty(),
+        "main window size is unchanged, no configure expected"
+    );
+    assert!(
+        f.client_mut(c).take_configures(w2).is_empty(),
+        "stack window size is unchanged, no configure expected"
+    );
+}
+
 #[test]
 fn close_window() {
     let mut f = Fixture::new();
@@ -88,7 +117,7 @@ fn close_window() {
     let w2 = open_window(&mut f, c);
 
     // close the active window (w2) via the server
-    if let Some(id) = f.mt.state.mon().tag().focused_id() {
+    if let Some(id) = f.mt.state.mon().unwrap().tag().focused_id() {
         if let Some(tl) = f.mt.state.windows[id].window.toplevel() {
             tl.send_close();
         }
@@ -197,7 +226,7 @@ fn focus_cycle() {
     f.client_mut(c).take_configures(w2);
 
     // cycle focus to w1
-    let tag = f.mt.state.mon().tag();
+    let tag = f.mt.state.mon().unwrap().tag();
     if let Some(cur) = tag.focused_id()
         && let Some(id) = tag.layout.target(cur, Rel::Next)
     {
@@ -225,7 +254,7 @@ fn focus_after_remove() {
     f.client_mut(c).take_configures(w1);
 
     // remove the active window and re-sync focus
-    let active = f.mt.state.mon().tag().focused_id().unwrap();
+    let active = f.mt.state.mon().unwrap().tag().focused_id().unwrap();
     let tl = f.mt.state.windows[active].window.toplevel().unwrap();
     let surface_id = tl.wl_surface().id();
 
@@ -252,7 +281,7 @@ fn float_geo_preserved_across_toggle() {
     let w = open_window(&mut f, c);
     f.client_mut(c).take_configures(w);
 
-    let id = f.mt.state.mon().tag().focused_id().unwrap();
+    let id = f.mt.state.mon().unwrap().tag().focused_id().unwrap();
 
     // toggle to floating, should get a centered float_geo
     {
@@ -321,6 +350,45 @@ fn floating_window_gets_configure() {
     );
 }
 
+#[test]
+fn fullscreen_on_unfocus() {
+    for mode in [
+        FullscreenUnfocus::Keep,
+        FullscreenUnfocus::Exit,
+        FullscreenUnfocus::Lower,
+    ] {
+        let mut f = Fixture::new();
+        f.mt.state.config.layout.fullscreen_on_unfocus = mode;
+        let c = f.add_client();
+        open_window(&mut f, c);
+        let other = f.mt.state.focused_id().unwrap();
+        open_window(&mut f, c);
+        let fs = f.mt.state.focused_id().unwrap();
+        f.mt.handle_action(Action::ToggleFullscreen);
+        assert!(f.mt.state.windows[fs].fullscreen);
+
+        f.mt.set_focus(Some(other));
+        let tag = f.mt.state.mon().unwrap().tag();
+        let we = &f.mt.state.windows[fs];
+        match mode {
+            FullscreenUnfocus::Keep => assert!(we.fullscreen),
+            FullscreenUnfocus::Exit => {
+                assert!(!we.fullscreen && !we.lowered_fullscreen);
+                assert_eq!(tag.fullscreen, None);
+            }
+            FullscreenUnfocus::Lower => {
+                assert!(!we.fullscreen && we.lowered_fullscreen);
+                assert_eq!(tag.focus_stack.last(), Some(&fs), "lowered");
+            }
+        }
+
+        // focusing it again brings a lowered window back to fullscreen
+        f.mt.set_focus(Some(fs));
+        let fullscreen = f.mt.state.windows[fs].fullscreen;
+        assert_eq!(fullscreen, mode != FullscreenUnfocus::Exit, "{mode:?}");
+    }
+}
+
 #[test]
 fn fullscreen_configure_has_state_and_output_size() {
     let mut f = Fixture::new();
@@ -344,3 +412,1085 @@ fn fullscreen_configure_has_state_and_output_size() {
         "fullscreen size should match output",
     );
 }
+
+#[test]
+fn fake_fullscreen_keeps_outer_gap_and_restores_tile() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    open_window(&mut f, c);
+    let w = open_window(&mut f, c);
+    let id = f.mt.state.focused_id().unwrap();
+    let tile =
+        f.mt.state
+            .mon()
+            .unwrap()
+            .tag()
+            .layout
+            .position_of(id)
+            .unwrap();
+    f.client_mut(c).take_configures(w);
+
+    f.mt.handle_action(Action::ToggleFakeFullscreen);
+    f.roundtrip(c);
+    let cfgs = f.client_mut(c).take_configures(w);
+    let last = cfgs
+        .last()
+        .expect("should get a configure for fake fullscreen");
+    assert!(last.states.contains(&ToplevelState::Fullscreen));
+    let gap = f.mt.state.config.layout.outer_gap;
+    assert_eq!((last.width, last.height), (1000 - 2 * gap, 800 - 2 * gap));
+
+    // real fullscreen takes over from fake fullscreen
+    f.mt.handle_action(Action::ToggleFullscreen);
+    f.roundtrip(c);
+    let cfgs = f.client_mut(c).take_configures(w);
+    let last = cfgs.last().unwrap();
+    assert_eq!((last.width, last.height), (1000, 800));
+    assert!(!f.mt.state.windows[id].fake_fullscreen);
+
+    f.mt.handle_action(Action::ToggleFullscreen);
+    f.roundtrip(c);
+    let cfgs = f.client_mut(c).take_configures(w);
+    let last = cfgs.last().unwrap();
+    assert!(!last.states.contains(&ToplevelState::Fullscreen));
+    assert_eq!((last.width, last.height), (tile.size.w, tile.size.h));
+}
+
+#[test]
+fn windows_survive_without_monitor() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+
+    let w = open_window(&mut f, c);
+    f.client_mut(c).take_configures(w);
+    let id = f.mt.state.mon().unwrap().tag().focused_id().unwrap();
+
+    // unplug the only output
+    let output = f.mt.state.mon().unwrap().output.clone();
+    let settings = MonitorSettings::default();
+    f.mt.state.remove_monitor(&output);
+    assert!(f.mt.state.mon().is_none());
+
+    // input and actions must not panic
+    f.mt.handle_action(Action::Focus(Rel::Next));
+    f.mt.handle_action(Action::AdjustMainRatio(0.1));
+    f.mt.update_focus();
+    f.roundtrip(c);
+    assert!(
+        f.mt.state.windows.contains_key(id),
+        "window should stay mapped"
+    );
+
+    // output returns, window is adopted by its active tag
+    f.mt.state.add_monitor(output, settings);
+    f.mt.recompute_layout(0);
+    f.roundtrip(c);
+    assert_eq!(
+        windows_on_tag(&f),
+        1,
+        "window should be back on the monitor"
+    );
+    assert!(f.mt.state.orphans.is_empty());
+}
+
+#[test]
+fn fallback_output_keeps_windows_configured() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+
+    let w = open_window(&mut f, c);
+    f.client_mut(c).take_configures(w);
+
+    let output = f.mt.state.mon().unwrap().output.clone();
+    f.mt.state.remove_monitor(&output);
+    let fallback =
+        f.mt.state
+            .ensure_fallback_output()
+            .expect("fallback output");
+    assert!(
+        f.mt.state.ensure_fallback_output().is_none(),
+        "only one fallback"
+    );
+    f.roundtrip(c);
+
+    assert_eq!(windows_on_tag(&f), 1, "window should move to the fallback");
+    let cfgs = f.client_mut(c).take_configures(w);
+    let last = cfgs.last().expect("window should be reconfigured");
+    let mode = fallback.current_mode().unwrap().size;
+    assert!(last.width <= mode.w && last.height <= mode.h);
+
+    // a real output returns and replaces the fallback
+    f.mt.state
+        .add_monitor(output.clone(), MonitorSettings::default());
+    f.mt.state.remove_fallback_output();
+    f.roundtrip(c);
+    assert_eq!(f.mt.state.monitors.len(), 1);
+    assert_eq!(f.mt.state.mon().unwrap().output, output);
+    assert_eq!(
+        windows_on_tag(&f),
+        1,
+        "window should return to the real output"
+    );
+}
+
+#[test]
+fn send_to_output_moves_window_to_monitor_on_the_right() {
+    let mut f = Fixture::new();
+    let right = f.add_output("right", (1000, 0));
+    let c = f.add_client();
+    let _w1 = open_window(&mut f, c);
+    let _w2 = open_window(&mut f, c);
+    let id = f.mt.state.focused_id().unwrap();
+
+    f.mt.handle_action(Action::SendToOutput(Direction::Left));
+    assert_eq!(f.mt.state.active_monitor, 0, "nothing to the left");
+
+    f.mt.handle_action(Action::SendToOutput(Direction::Right));
+    f.roundtrip(c);
+    let mon = f.mt.state.mon().unwrap();
+    assert_eq!(mon.output, right, "focus follows the window");
+    assert_eq!(mon.tag().layout.ids().collect::<Vec<_>>(), vec![id]);
+    assert!(!f.mt.state.windows[id].floating, "still tiled");
+    assert_eq!(f.mt.state.windows[id].monitor, 1);
+    assert_eq!(f.mt.state.focused_id(), Some(id));
+    assert!(
+        !f.mt.state.monitors[0].tag().contains(id),
+        "gone from the old monitor"
+    );
+
+    f.mt.handle_action(Action::FocusOutput(Direction::Left));
+    assert_eq!(f.mt.state.active_monitor, 0, "focus moves back left");
+}
+
+#[test]
+fn focus_output_restores_each_monitors_focus() {
+    let mut f = Fixture::new();
+    f.add_output("right", (1000, 0));
+    let c = f.add_client();
+    let w1 = open_window(&mut f, c);
+    let id1 = f.mt.state.focused_id().unwrap();
+
+    f.mt.handle_action(Action::FocusOutput(Direction::Right));
+    f.roundtrip(c);
+    assert_eq!(f.mt.state.focused_id(), None, "right monitor starts empty");
+    assert!(!is_activated(&mut f, c, w1), "w1 loses focus");
+
+    let w2 = open_window(&mut f, c);
+    let id2 = f.mt.state.focused_id().unwrap();
+    assert_eq!(f.mt.state.windows[id2].monitor, 1);
+    f.client_mut(c).take_configures(w2); // drain
+
+    f.mt.handle_action(Action::FocusOutput(Direction::Left));
+    f.roundtrip(c);
+    assert_eq!(f.mt.state.focused_id(), Some(id1));
+    assert!(is_activated(&mut f, c, w1), "w1 focus is restored");
+    assert!(!is_activated(&mut f, c, w2), "w2 is deactivated");
+
+    f.mt.handle_action(Action::FocusOutput(Direction::Right));
+    f.roundtrip(c);
+    assert_eq!(f.mt.state.focused_id(), Some(id2));
+    assert!(!is_activated(&mut f, c, w1));
+    assert!(is_activated(&mut f, c, w2), "w2 focus is restored");
+}
+
+#[test]
+fn pending_spawn_places_window_on_recorded_tag() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+
+    // the test client lives in this process
+    f.mt.state.pending_spawns.push(PendingSpawn {
+        token: String::new(),
+        pid: std::process::id(),
+        monitor: 0,
+        tag: 3,
+        since: Instant::now(),
+    });
+
+    let w = f.client_mut(c).create_window();
+    f.client_mut(c).commit(w);
+    f.roundtrip(c);
+    f.client_mut(c).ack_and_commit(w);
+    f.roundtrip(c);
+
+    let mon = f.mt.state.mon().unwrap();
+    assert_eq!(
+        mon.tag().window_ids().len(),
+        0,
+        "active tag should stay empty"
+    );
+    assert_eq!(
+        mon.tags[3].window_ids().len(),
+        1,
+        "window should be on tag 3"
+    );
+    assert!(
+        f.mt.state.pending_spawns.is_empty(),
+        "entry should be consumed"
+    );
+}
+
+#[test]
+fn occluded_window_gets_no_frame_callbacks() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+
+    let w1 = open_window(&mut f, c);
+    let w2 = open_window(&mut f, c);
+
+    // float w2 over the whole output and make it opaque
+    f.mt.handle_action(Action::ToggleFloat);
+    let id = f.mt.state.focused_id().unwrap();
+    let mon = f.mt.state.mon().unwrap();
+    let (output, geo) = (mon.output.clone(), mon.geometry());
+    f.mt.state.windows[id].float_geo = geo;
+    f.client_mut(c).set_opaque(w2);
+    f.client_mut(c).ack_and_commit(w2);
+
+    let frame = f.client_mut(c).request_frame(w1);
+    f.client_mut(c).ack_and_commit(w1);
+    f.roundtrip(c);
+    f.mt.state.send_frame_callbacks(&output, None);
+    f.roundtrip(c);
+    assert!(
+        !frame.load(std::sync::atomic::Ordering::Relaxed),
+        "window covered by an opaque window should not get a frame callback"
+    );
+
+    // tiling w2 again uncovers w1
+    f.mt.handle_action(Action::ToggleFloat);
+    f.roundtrip(c);
+    f.mt.state.send_frame_callbacks(&output, None);
+    f.roundtrip(c);
+    assert!(
+        frame.load(std::sync::atomic::Ordering::Relaxed),
+        "uncovered window should get the pending frame callback"
+    );
+}
+
+#[test]
+fn buffer_scale_gives_logical_window_size() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+
+    let w = open_window(&mut f, c);
+    f.mt.handle_action(Action::ToggleFloat);
+    f.roundtrip(c);
+
+    // a floating window is sized by its buffer, 200x100 at scale 2 is 100x50
+    f.client_mut(c).commit_scaled_buffer(w, (200, 100), 2);
+    f.roundtrip(c);
+
+    let id = f.mt.state.focused_id().unwrap();
+    let we = &f.mt.state.windows[id];
+    assert_eq!(we.window.geometry().size, (100, 50).into());
+    assert_eq!(we.float_geo.size, (100, 50).into());
+}
+
+#[test]
+fn tile_floats_when_min_size_grows_past_it() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let _w1 = open_window(&mut f, c);
+    let w2 = open_window(&mut f, c);
+    let id = f.mt.state.focused_id().unwrap();
+    let tile =
+        f.mt.state
+            .mon()
+            .unwrap()
+            .window_rect(&f.mt.state.windows, id)
+            .unwrap();
+    assert!(!f.mt.state.windows[id].floating, "starts tiled");
+
+    f.client(c)
+        .window(w2)
+        .toplevel
+        .set_min_size(tile.size.w + 100, tile.size.h);
+    f.client_mut(c).commit(w2);
+    f.roundtrip(c);
+
+    let we = &f.mt.state.windows[id];
+    assert!(we.floating, "a tile below the new minimum floats");
+    assert_eq!(we.float_geo.loc, tile.loc, "it floats in place");
+    assert_eq!(
+        we.float_geo.size.w,
+        tile.size.w + 100,
+        "clamped to the minimum"
+    );
+}
+
+#[test]
+fn rescue_windows_pulls_offscreen_float_back() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    open_window(&mut f, c);
+
+    f.mt.handle_action(Action::ToggleFloat);
+    let id = f.mt.state.focused_id().unwrap();
+    let size = f.mt.state.windows[id].float_geo.size;
+    f.mt.state.windows[id].float_geo.loc = (5000, -3000).into();
+
+    f.mt.handle_action(Action::RescueWindows);
+    let geo = f.mt.state.windows[id].float_geo;
+    let area = f.mt.state.mon().unwrap().geometry();
+    assert!(
+        area.contains_rect(geo),
+        "{geo:?} should be back inside {area:?}"
+    );
+    assert_eq!(geo.size, size, "rescuing only moves the window");
+    assert_eq!(geo.loc.y, 0, "it is pulled to the nearest edge");
+}
+
+// a modal dialog of parent
+fn open_dialog(f: &mut Fixture, c: usize, parent: usize) -> usize {
+    let dialog = f.client_mut(c).create_window();
+    let parent_tl = f.client_mut(c).window(parent).toplevel.clone();
+    f.client_mut(c)
+        .window(dialog)
+        .toplevel
+        .set_parent(Some(&parent_tl));
+    f.client_mut(c).set_modal(dialog);
+    f.client_mut(c).commit(dialog);
+    f.roundtrip(c);
+    f.client_mut(c).ack_and_commit(dialog);
+    f.roundtrip(c);
+    dialog
+}
+
+#[test]
+fn focus_parent_and_child_follow_the_dialog_link() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let parent = open_window(&mut f, c);
+    let parent_id = f.mt.state.focused_id().unwrap();
+
+    let dialog = f.client_mut(c).create_window();
+    let parent_tl = f.client_mut(c).window(parent).toplevel.clone();
+    f.client_mut(c)
+        .window(dialog)
+        .toplevel
+        .set_parent(Some(&parent_tl));
+    f.client_mut(c).commit(dialog);
+    f.roundtrip(c);
+    f.client_mut(c).ack_and_commit(dialog);
+    f.roundtrip(c);
+    let dialog_id = f.mt.state.focused_id().unwrap();
+    assert_ne!(dialog_id, parent_id, "the dialog takes focus");
+
+    // a dialog has no child of its own
+    f.mt.handle_action(Action::FocusChild);
+    assert_eq!(f.mt.state.focused_id(), Some(dialog_id));
+
+    f.mt.handle_action(Action::FocusParent);
+    assert_eq!(f.mt.state.focused_id(), Some(parent_id));
+    f.roundtrip(c);
+    assert!(is_activated(&mut f, c, parent));
+
+    f.mt.handle_action(Action::FocusChild);
+    assert_eq!(f.mt.state.focused_id(), Some(dialog_id));
+}
+
+#[test]
+fn modal_dialog_keeps_focus_until_closed() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let parent = open_window(&mut f, c);
+    let parent_id = f.mt.state.focused_id().unwrap();
+
+    let dialog = open_dialog(&mut f, c, parent);
+    let dialog_id = f.mt.state.focused_id().unwrap();
+    assert_eq!(f.mt.state.windows.modal_child(parent_id), Some(dialog_id));
+    assert!(f.mt.state.windows.modal_parents().contains(&parent_id));
+
+    // focusing the blocked parent lands on its dialog
+    f.mt.set_focus(Some(parent_id));
+    assert_eq!(f.mt.state.focused_id(), Some(dialog_id));
+    // the parent still takes clicks to raise the dialog, its surface gets no events
+    let under = f.mt.state.surface_under((10.0, 10.0).into());
+    assert_eq!(under.window, Some(parent_id));
+    assert!(
+        under.surface.is_none(),
+        "pointer input to the parent is blocked"
+    );
+
+    f.client_mut(c).destroy_window(dialog);
+    f.roundtrip(c);
+    assert_eq!(
+        f.mt.state.windows.modal_child(parent_id),
+        None,
+        "closing the dialog unblocks"
+    );
+    f.mt.set_focus(Some(parent_id));
+    assert_eq!(f.mt.state.focused_id(), Some(parent_id));
+}
+
+#[test]
+fn floating_window_remembers_its_position_per_output() {
+    let mut f = Fixture::new();
+    f.add_output("right", (1000, 0));
+    let c = f.add_client();
+    open_window(&mut f, c);
+    f.mt.handle_action(Action::ToggleFloat);
+    let id = f.mt.state.focused_id().unwrap();
+    f.mt.state.windows[id].float_geo.loc = (30, 40).into();
+    let size = f.mt.state.windows[id].float_geo.size;
+
+    f.mt.handle_action(Action::SendToOutput(Direction::Right));
+    let geo = f.mt.state.windows[id].float_geo;
+    let area = f.mt.state.mon().unwrap().geometry();
+    let x = area.loc.x + (area.size.w - size.w) / 2;
+    let y = area.loc.y + (area.size.h - size.h) / 2;
+    assert_eq!(geo.loc, (x, y).into(), "centered on its first visit");
+    f.mt.state.windows[id].float_geo.loc = (500, 60).into();
+
+    f.mt.handle_action(Action::SendToOutput(Direction::Left));
+    assert_eq!(f.mt.state.windows[id].float_geo.loc, (30, 40).into());
+    f.mt.handle_action(Action::SendToOutput(Direction::Right));
+    assert_eq!(f.mt.state.windows[id].float_geo.loc, (500, 60).into());
+}
+
+#[test]
+fn keyboard_focus_raises_floating_windows_only_when_enabled() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    open_window(&mut f, c);
+    f.mt.handle_action(Action::ToggleFloat);
+    let a = f.mt.state.focused_id().unwrap();
+    open_window(&mut f, c);
+    f.mt.handle_action(Action::ToggleFloat);
+    let b = f.mt.state.focused_id().unwrap();
+    let stack = |f: &Fixture| f.mt.state.mon().unwrap().tag().floating.clone();
+    assert_eq!(stack(&f), vec![a, b], "b is on top");
+
+    f.mt.set_focus(Some(a));
+    assert_eq!(f.mt.state.focused_id(), Some(a));
+    assert_eq!(stack(&f), vec![a, b], "focus alone keeps the stacking");
+
+    f.mt.set_focus(Some(b));
+    f.mt.state
+        .config
+        .seats
+        .get_mut("seat0")
+        .unwrap()
+        .raise_on_focus = true;
+    f.mt.set_focus(Some(a));
+    assert_eq!(stack(&f), vec![b, a], "focus raises a");
+}
+
+#[test]
+fn exit_gracefully_waits_for_every_window_to_close() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let a = open_window(&mut f, c);
+    let b = open_window(&mut f, c);
+
+    f.mt.handle_action(Action::ExitGracefully);
+    f.roundtrip(c);
+    assert!(f.client(c).window(a).closed);
+    assert!(f.client(c).window(b).closed);
+    assert!(
+        f.mt.state.exit_timer.is_some(),
+        "exit waits for the windows"
+    );
+
+    f.client_mut(c).destroy_window(a);
+    f.roundtrip(c);
+    assert!(f.mt.state.exit_timer.is_some(), "one window is still open");
+    f.client_mut(c).destroy_window(b);
+    f.roundtrip(c);
+    assert!(f.mt.state.exit_timer.is_none(), "the last close exits");
+}
+
+#[test]
+fn session_saves_only_run_while_enabled() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut f = Fixture::new();
+    assert!(f.mt.state.session.is_none());
+    assert!(f.mt.state.session_timer.is_none(), "off by default");
+
+    f.mt.state.session = Some(Session::load(dir.path().join("session.ron")));
+    f.mt.arm_session_timer();
+    assert!(f.mt.state.session_timer.is_some());
+
+    f.mt.state.session = None;
+    f.mt.arm_session_timer();
+    assert!(f.mt.state.session_timer.is_none());
+}
+
+#[test]
+fn session_returns_relaunched_apps_to_their_tags() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("session.ron");
+    let mut f = Fixture::new();
+    f.mt.state.session = Some(Session::load(path.clone()));
+    let c = f.add_client();
+    let w = f.client_mut(c).create_window();
+    f.client(c)
+        .window(w)
+        .toplevel
+        .set_app_id("test.editor".into());
+    f.client_mut(c).commit(w);
+    f.roundtrip(c);
+    f.client_mut(c).ack_and_commit(w);
+    f.roundtrip(c);
+    f.mt.handle_action(Action::SetTag(3));
+    f.mt.state.save_session();
+
+    // a restart, the app comes back on its saved tag
+    f.client_mut(c).destroy_window(w);
+    f.roundtrip(c);
+    f.mt.state.session = Some(Session::load(path));
+    let w = f.client_mut(c).create_window();
+    f.client(c)
+        .window(w)
+        .toplevel
+        .set_app_id("test.editor".into());
+    f.client_mut(c).commit(w);
+    f.roundtrip(c);
+    f.client_mut(c).ack_and_commit(w);
+    f.roundtrip(c);
+    let mon = f.mt.state.mon().unwrap();
+    let id = mon.tags[3].focused_id().expect("restored onto tag 3");
+    assert!(!mon.tags[0].contains(id));
+}
+
+#[test]
+fn closing_the_last_window_on_a_tag_shows_the_previous_tag() {
+    let mut f = Fixture::new();
+    f.mt.state.config.layout.view_prev_on_empty = true;
+    let c = f.add_client();
+    open_window(&mut f, c);
+    f.mt.handle_action(Action::FocusTag(2));
+    let a = open_window(&mut f, c);
+    let b = open_window(&mut f, c);
+
+    f.client_mut(c).destroy_window(a);
+    f.roundtrip(c);
+    assert_eq!(
+        f.mt.state.mon().unwrap().active_tag,
+        2,
+        "tag 2 still has a window"
+    );
+    f.client_mut(c).destroy_window(b);
+    f.roundtrip(c);
+    let mon = f.mt.state.mon().unwrap();
+    assert_eq!(mon.active_tag, 0, "the emptied tag switched back");
+    assert!(f.mt.state.focused_id().is_some());
+}
+
+#[test]
+fn window_rules_place_apps_on_tags_by_app_id() {
+    let ron = r#"#![enable(implicit_some)]
+    (windows: [
+        (match: (), init: (tag: Tag(4))),
+        (match: (app_id: "fire.*"), init: (tag: Tag(1))),
+        (match: (app_id: "foot"), init: (tag: Current)),
+    ])"#;
+    let mut f = Fixture::new();
+    f.mt.state.config.windows = Config::parse(ron).unwrap().windows;
+    let c = f.add_client();
+    let open_app = |f: &mut Fixture, app_id: &str| {
+        let w = f.client_mut(c).create_window();
+        f.client(c).window(w).toplevel.set_app_id(app_id.into());
+        f.client_mut(c).commit(w);
+        f.roundtrip(c);
+        f.client_mut(c).ack_and_commit(w);
+        f.roundtrip(c);
+    };
+
+    open_app(&mut f, "firefox");
+    open_app(&mut f, "foot");
+    open_app(&mut f, "other");
+    let mon = f.mt.state.mon().unwrap();
+    let on = |t: usize| mon.tags[t].focus_stack.len();
+    assert_eq!(on(1), 1, "firefox matched by regex");
+    assert_eq!(on(0), 1, "foot stays on the current tag");
+    assert_eq!(on(4), 1, "the catch-all rule");
+}
+
+// a decorated catch-all rule and mpv without decorations
+fn no_decorations_fixture() -> (Fixture, usize) {
+    let ron = r#"#![enable(implicit_some)]
+    (windows: [
+        (match: (), render: {
+            0: Shadow(softness: 10, spread: 2, offset: (0, 2), color: "black"),
+            1: WindowSurface(radius: 6.0, fill: "black"),
+            2: Border(width: 2, color: "gray"),
+        }),
+        (match: (app_id: "mpv"), no_decorations: true),
+    ])"#;
+    let mut f = Fixture::new();
+    f.mt.state.config.windows = Config::parse(ron).unwrap().windows;
+    f.mt.state.config.layout.smart_borders = false;
+    f.mt.state.config.layout.smart_gaps = false;
+    let c = f.add_client();
+    (f, c)
+}
+
+fn open_app(f: &mut Fixture, c: usize, app_id: &str) -> WindowId {
+    let w = f.client_mut(c).create_window();
+    f.client(c).window(w).toplevel.set_app_id(app_id.into());
+    f.client_mut(c).commit(w);
+    f.roundtrip(c);
+    f.client_mut(c).ack_and_commit(w);
+    f.roundtrip(c);
+    f.mt.state.focused_id().unwrap()
+}
+
+#[test]
+fn no_decorations_rule_leaves_only_the_surface() {
+    let (mut f, c) = no_decorations_fixture();
+    let mpv = open_app(&mut f, c, "mpv");
+    let foot = open_app(&mut f, c, "foot");
+
+    let we = &f.mt.state.windows[foot];
+    assert!(we.decorated);
+    assert_eq!(we.render_pipeline.len(), 3);
+    assert_eq!(we.radius, 6.0);
+
+    let we = &f.mt.state.windows[mpv];
+    assert!(!we.decorated);
+    assert_eq!(we.radius, 0.0, "not rounded");
+    let steps: Vec<_> = we
+        .render_pipeline
+        .iter()
+        .map(|k| &we.render_steps[k])
+        .collect();
+    assert!(
+        matches!(steps[..], [RenderStep::WindowSurface { .. }]),
+        "no border or shadow: {steps:?}"
+    );
+}
+
+#[test]
+#[ignore = "needs an EGL device to build the elements"]
+fn no_decorations_window_builds_no_decoration_elements() {
+    let (mut f, c) = no_decorations_fixture();
+    let decoration = |e: &MonotileElement| {
+        matches!(
+            e,
+            MonotileElement::Decoration(_)
+                | MonotileElement::Border(_)
+                | MonotileElement::Clipped(_)
+        )
+    };
+
+    open_app(&mut f, c, "mpv");
+    let elems = f.output_elements().expect("an EGL device");
+    assert!(!elems.is_empty(), "the surface is drawn");
+    assert!(
+        !elems.iter().any(decoration),
+        "no border, shadow or background"
+    );
+
+    open_app(&mut f, c, "foot");
+    let elems = f.output_elements().expect("an EGL device");
+    assert!(elems.iter().any(decoration), "foot is decorated");
+}
+
+#[test]
+fn sole_floating_window_tiles_after_closes() {
+    let ron = r#"#![enable(implicit_some)]
+    (windows: [(match: (app_id: "float"), init: (floating: true))])"#;
+    let mut f = Fixture::new();
+    f.mt.state.config.windows = Config::parse(ron).unwrap().windows;
+    f.mt.state.config.layout.auto_tile_sole_window = true;
+    let c = f.add_client();
+    let floating = f.client_mut(c).create_window();
+    f.client(c)
+        .window(floating)
+        .toplevel
+        .set_app_id("float".into());
+    f.client_mut(c).commit(floating);
+    f.roundtrip(c);
+    f.client_mut(c).ack_and_commit(floating);
+    f.roundtrip(c);
+    let float_id = f.mt.state.focused_id().unwrap();
+    let a = open_window(&mut f, c);
+    let b = open_window(&mut f, c);
+    assert!(f.mt.state.windows[float_id].floating);
+
+    f.client_mut(c).destroy_window(a);
+    f.roundtrip(c);
+    assert!(f.mt.state.windows[float_id].floating, "not alone yet");
+    f.client_mut(c).destroy_window(b);
+    f.roundtrip(c);
+    assert!(!f.mt.state.windows[float_id].floating, "tiled once alone");
+
+    // floated by hand it stays floating
+    let b = open_window(&mut f, c);
+    f.mt.set_focus(Some(float_id));
+    f.mt.handle_action(Action::ToggleFloat);
+    f.client_mut(c).destroy_window(b);
+    f.roundtrip(c);
+    assert!(f.mt.state.windows[float_id].floating);
+}
+
+#[test]
+fn modal_dialog_floats_centered_over_its_parent() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let parent = open_window(&mut f, c);
+    let parent_id = f.mt.state.focused_id().unwrap();
+    // the parent only fills half of the output
+    open_window(&mut f, c);
+
+    // a child window that is not marked modal blocks nothing
+    let child = f.client_mut(c).create_window();
+    let parent_tl = f.client(c).window(parent).toplevel.clone();
+    f.client(c)
+        .window(child)
+        .toplevel
+        .set_parent(Some(&parent_tl));
+    f.client_mut(c).commit(child);
+    f.roundtrip(c);
+    f.client_mut(c).ack_and_commit(child);
+    f.roundtrip(c);
+    assert_eq!(f.mt.state.windows.modal_child(parent_id), None);
+    f.client_mut(c).destroy_window(child);
+    f.roundtrip(c);
+
+    open_dialog(&mut f, c, parent);
+    let dialog_id = f.mt.state.focused_id().unwrap();
+    assert_eq!(f.mt.state.windows.modal_child(parent_id), Some(dialog_id));
+    assert!(f.mt.state.windows[dialog_id].floating);
+    let mon = f.mt.state.mon().unwrap();
+    let p = mon.window_rect(&f.mt.state.windows, parent_id).unwrap();
+    let d = mon.window_rect(&f.mt.state.windows, dialog_id).unwrap();
+    let center = |r: Rectangle<i32, _>| (r.loc.x + r.size.w / 2, r.loc.y + r.size.h / 2);
+    let (pc, dc) = (center(p), center(d));
+    assert!(
+        (pc.0 - dc.0).abs() <= 1 && (pc.1 - dc.1).abs() <= 1,
+        "dialog {d:?} is centered over its parent {p:?}"
+    );
+}
+
+#[test]
+fn cascade_offsets_new_floating_windows_only() {
+    let ron = r#"#![enable(implicit_some)]
+    (windows: [(match: (app_id: "float"), init: (floating: true))])"#;
+    let mut f = Fixture::new();
+    f.mt.state.config.windows = Config::parse(ron).unwrap().windows;
+    let c = f.add_client();
+    f.mt.state
+        .mon_mut()
+        .unwrap()
+        .tag_mut()
+        .layout
+        .config
+        .float_placement = FloatPlacement::Cascade;
+    let open_app = |f: &mut Fixture, app_id: &str| {
+        let w = f.client_mut(c).create_window();
+        f.client(c).window(w).toplevel.set_app_id(app_id.into());
+        f.client_mut(c).commit(w);
+        f.roundtrip(c);
+        f.client_mut(c).ack_and_commit(w);
+        f.roundtrip(c);
+        f.mt.state.focused_id().unwrap()
+    };
+
+    // tiled windows do not move the next floating one
+    open_app(&mut f, "tiled");
+    open_app(&mut f, "tiled");
+    let first = open_app(&mut f, "float");
+    let Rectangle { loc, size } = f.mt.state.windows[first].float_geo;
+    let area = f.mt.state.mon().unwrap().geometry().size;
+    let center = ((area.w - size.w) / 2, (area.h - size.h) / 2);
+    assert_eq!(loc, center.into(), "the first floating window is centered");
+
+    let second = open_app(&mut f, "float");
+    let a = f.mt.state.windows[first].float_geo.loc;
+    let b = f.mt.state.windows[second].float_geo.loc;
+    assert_eq!(b - a, (32, 32).into(), "second floating window is offset");
+
+    let mon = f.mt.state.mon_mut().unwrap();
+    mon.unmap(first);
+    mon.unmap(second);
+    let third = open_app(&mut f, "float");
+    let loc = f.mt.state.windows[third].float_geo.loc;
+    assert_eq!(loc, a, "no floating window left, back at the center");
+}
+
+#[test]
+fn floated_main_returns_to_its_slot() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    for _ in 0..3 {
+        open_window(&mut f, c);
+    }
+    let ids = |f: &Fixture| {
+        f.mt.state
+            .mon()
+            .unwrap()
+            .tag()
+            .layout
+            .ids()
+            .collect::<Vec<_>>()
+    };
+    let before = ids(&f);
+    let main = before[0];
+
+    f.mt.set_focus(Some(main));
+    f.mt.handle_action(Action::ToggleFloat);
+    assert!(
+        !ids(&f).contains(&main),
+        "floating windows leave the layout"
+    );
+
+    f.mt.handle_action(Action::ToggleFloat);
+    assert_eq!(ids(&f), before, "main is back at index 0");
+}
+
+#[test]
+fn unparented_dialog_tiles_again() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let parent = open_window(&mut f, c);
+    let w = open_window(&mut f, c);
+    let id = f.mt.state.focused_id().unwrap();
+    let parent_tl = f.client_mut(c).window(parent).toplevel.clone();
+
+    f.client_mut(c)
+        .window(w)
+        .toplevel
+        .set_parent(Some(&parent_tl));
+    f.roundtrip(c);
+    assert!(f.mt.state.windows[id].floating, "a late parent floats it");
+
+    f.client_mut(c).window(w).toplevel.set_parent(None);
+    f.roundtrip(c);
+    assert!(!f.mt.state.windows[id].floating, "losing it tiles it again");
+
+    // floated by the user, it stays floating
+    f.client_mut(c)
+        .window(w)
+        .toplevel
+        .set_parent(Some(&parent_tl));
+    f.roundtrip(c);
+    f.mt.handle_action(Action::ToggleFloat);
+    f.mt.handle_action(Action::ToggleFloat);
+    f.client_mut(c).window(w).toplevel.set_parent(None);
+    f.roundtrip(c);
+    assert!(f.mt.state.windows[id].floating);
+}
+
+#[test]
+fn cycle_floating_rotates_and_raises() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    open_window(&mut f, c);
+    let tiled = f.mt.state.focused_id().unwrap();
+    let mut floats = Vec::new();
+    for _ in 0..2 {
+        open_window(&mut f, c);
+        floats.push(f.mt.state.focused_id().unwrap());
+        f.mt.handle_action(Action::ToggleFloat);
+    }
+    let [a, b] = floats[..] else { unreachable!() };
+    let stack = |f: &Fixture| f.mt.state.mon().unwrap().tag().floating.clone();
+    assert_eq!(stack(&f), [a, b]);
+
+    // from a tiled window the top one is focused first
+    f.mt.set_focus(Some(tiled));
+    f.mt.handle_action(Action::CycleFloating(1));
+    assert_eq!(f.mt.state.focused_id(), Some(b));
+    assert_eq!(stack(&f), [a, b]);
+
+    f.mt.handle_action(Action::CycleFloating(1));
+    assert_eq!(f.mt.state.focused_id(), Some(a));
+    assert_eq!(stack(&f), [b, a], "the cycled to window is raised");
+
+    f.mt.handle_action(Action::CycleFloating(-1));
+    assert_eq!(f.mt.state.focused_id(), Some(b));
+    assert_eq!(stack(&f), [a, b]);
+}
+
+#[test]
+fn float_size_is_clamped_to_the_area() {
+    let mut f = Fixture::new();
+    let area = f.mt.state.mon().unwrap().geometry();
+    let tag = f.mt.state.mon_mut().unwrap().tag_mut();
+    tag.layout.config.float_size = FloatSize::Size(600, 5000);
+    let size = tag.float_size(area.size);
+    assert_eq!((size.w, size.h), (600, area.size.h), "clamped to the area");
+
+    tag.layout.config.float_size = FloatSize::Fraction(0.5);
+    let half = tag.float_size(area.size);
+    assert_eq!((half.w, half.h), (area.size.w / 2, area.size.h / 2));
+}
+
+#[test]
+fn tiled_windows_keep_their_float_size() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    f.mt.state
+        .mon_mut()
+        .unwrap()
+        .tag_mut()
+        .layout
+        .config
+        .float_size = FloatSize::Size(600, 500);
+
+    open_window(&mut f, c);
+    let id = f.mt.state.focused_id().unwrap();
+    let own = f.mt.state.windows[id].window.geometry().size;
+    assert_eq!(
+        f.mt.state.windows[id].float_geo.size, own,
+        "a tiled window floats at its own size, not float_size"
+    );
+
+    f.mt.handle_action(Action::ToggleFloat);
+    f.mt.state.windows[id].resize_float((300, 200).into());
+    f.mt.state.windows[id].finish_resize_float();
+    f.mt.handle_action(Action::ToggleFloat);
+    assert!(!f.mt.state.windows[id].floating);
+    f.mt.handle_action(Action::ToggleFloat);
+    let size = f.mt.state.windows[id].float_geo.size;
+    assert_eq!(
+        (size.w, size.h),
+        (300, 200),
+        "float size from before tiling"
+    );
+}
+
+#[test]
+fn scrolling_over_the_gaps_cycles_focus() {
+    let mut f = Fixture::new();
+    f.mt.state.config.layout.outer_gap = 10;
+    let c = f.add_client();
+    open_window(&mut f, c);
+    let first = f.mt.state.focused_id().unwrap();
+    let w = open_window(&mut f, c);
+    f.client_mut(c).take_configures(w);
+    let second = f.mt.state.focused_id().unwrap();
+    let gap = (5.0, 400.0).into();
+    let window = (500.0, 400.0).into();
+
+    assert!(!f.mt.scroll_root(gap, 15.0), "off unless configured");
+    f.mt.state
+        .config
+        .seats
+        .get_mut("seat0")
+        .unwrap()
+        .root_scroll = Some(AxisAction::FocusStack);
+    assert!(!f.mt.scroll_root(window, 15.0), "windows get their scroll");
+    assert_eq!(f.mt.state.focused_id(), Some(second));
+
+    assert!(f.mt.scroll_root(gap, 5.0), "consumed");
+    assert_eq!(f.mt.state.focused_id(), Some(second), "less than a notch");
+    assert!(f.mt.scroll_root(gap, 10.0));
+    assert_eq!(f.mt.state.focused_id(), Some(first));
+    assert!(f.mt.scroll_root(gap, -15.0));
+    assert_eq!(f.mt.state.focused_id(), Some(second));
+}
+
+#[test]
+fn pointer_crosses_the_seam_between_outputs() {
+    let mut f = Fixture::new();
+    // right output sits lower, so part of the left edge has nothing beside it
+    f.add_output("right", (1000, 200));
+
+    let pos = f.mt.cross_monitor_edge((1004.0, 100.0).into());
+    assert_eq!(
+        pos,
+        Some((4.0, 0.0).into()),
+        "slid down onto the right output"
+    );
+    assert_eq!(f.mt.state.active_monitor, 1);
+
+    let pos = f.mt.cross_monitor_edge((-3.0, 300.0).into());
+    assert_eq!(pos, Some((997.0, 500.0).into()), "straight back across");
+    assert_eq!(f.mt.state.active_monitor, 0);
+
+    assert_eq!(
+        f.mt.cross_monitor_edge((500.0, 805.0).into()),
+        None,
+        "no output below"
+    );
+    assert_eq!(f.mt.state.active_monitor, 0);
+}
+
+#[test]
+fn focus_tag_global_switches_every_monitor() {
+    let mut f = Fixture::new();
+    f.add_output("right", (1000, 0));
+    let tags = |f: &Fixture| {
+        let m = &f.mt.state.monitors;
+        (m[0].active_tag, m[1].active_tag)
+    };
+
+    f.mt.handle_action(Action::FocusTagGlobal(2));
+    assert_eq!(tags(&f), (2, 2));
+
+    // FocusTag stays per monitor
+    f.mt.handle_action(Action::FocusTag(4));
+    assert_eq!(tags(&f), (4, 2));
+}
+
+#[test]
+fn size_locked_window_keeps_its_float_size_when_tiled() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+    let w = open_window(&mut f, c);
+    let id = f.mt.state.focused_id().unwrap();
+    let float = f.mt.state.windows[id].float_geo.size;
+    let tile =
+        f.mt.state
+            .mon()
+            .unwrap()
+            .tag()
+            .layout
+            .position_of(id)
+            .unwrap();
+    assert!(!float.is_empty() && float != tile.size);
+
+    f.mt.handle_action(Action::ToggleSizeLock);
+    f.roundtrip(c);
+    let last = f.client_mut(c).take_configures(w).pop().unwrap();
+    assert_eq!((last.width, last.height), (float.w, float.h));
+
+    f.mt.handle_action(Action::ToggleFloat);
+    f.mt.handle_action(Action::ToggleFloat);
+    f.roundtrip(c);
+    for cfg in f.client_mut(c).take_configures(w) {
+        assert_eq!(
+            (cfg.width, cfg.height),
+            (float.w, float.h),
+            "never the tile size"
+        );
+    }
+    let mon = f.mt.state.mon().unwrap();
+    let rect = mon.window_rect(&f.mt.state.windows, id).unwrap();
+    assert_eq!(rect.size, float);
+    let center = |r: Rectangle<i32, _>| (r.loc.x + r.size.w / 2, r.loc.y + r.size.h / 2);
+    let (a, b) = (center(rect), center(tile));
+    assert!(
+        (a.0 - b.0).abs() <= 1 && (a.1 - b.1).abs() <= 1,
+        "centered in its tile"
+    );
+}
+
+#[test]
+fn size_locked_window_larger_than_its_tile_is_configured_to_fit() {
+    let ron = r#"#![enable(implicit_some)]
+    (windows: [(match: (), init: (size: (2000, 2000), size_locked: true))])"#;
+    let mut f = Fixture::new();
+    f.mt.state.config.windows = Config::parse(ron).unwrap().windows;
+    let c = f.add_client();
+    let w = open_window(&mut f, c);
+    f.roundtrip(c);
+    let id = f.mt.state.focused_id().unwrap();
+    assert!(f.mt.state.windows[id].size_locked);
+    assert!(!f.mt.state.windows[id].floating);
+
+    let mon = f.mt.state.mon().unwrap();
+    let tile = mon.tag().layout.position_of(id).unwrap();
+    let rect = mon.window_rect(&f.mt.state.windows, id).unwrap();
+    assert_eq!(rect, tile, "clamped to the tile");
+    let last = f.client_mut(c).take_configures(w).pop().unwrap();
+    assert_eq!(
+        (last.width, last.height),
+        (tile.size.w, tile.size.h),
+        "configured at the size it is drawn at"
+    );
+}
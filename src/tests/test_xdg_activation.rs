@@ -21,7 +21,7 @@ fn get_token(f: &mut Fixture, c: usize) -> String {
 }
 
 fn all_window_ids(f: &Fixture) -> Vec<WindowId> {
-    f.mt.state.mon().tag().focus_stack.clone()
+    f.mt.state.mon().unwrap().tag().focus_stack.clone()
 }
 
 // ── Token lifecycle ────────────────────────────────
@@ -70,7 +70,7 @@ fn activation_of_focused_window_does_not_set_urgent() {
     let c = f.add_client();
 
     let w = open_window(&mut f, c);
-    let w_id = f.mt.state.mon().tag().focused_id().unwrap();
+    let w_id = f.mt.state.mon().unwrap().tag().focused_id().unwrap();
     assert!(f.mt.state.windows[w_id].focused, "w should be focused");
 
     let token = get_token(&mut f, c);
@@ -203,3 +203,73 @@ fn stale_token_ignored() {
         "fresh token should set urgent",
     );
 }
+
+// ── Urgent flash ───────────────────────────────────
+
+#[test]
+fn urgent_border_flashes_until_focused() {
+    let mut f = Fixture::new();
+    let c = f.add_client();
+
+    let w1 = open_window(&mut f, c);
+    let _w2 = open_window(&mut f, c);
+    let w1_id = all_window_ids(&f)[1];
+
+    let token = get_token(&mut f, c);
+    f.client(c).activate(&token, w1);
+    f.roundtrip(c);
+    assert!(f.mt.state.windows[w1_id].flash.is_some(), "w1 should flash");
+
+    f.mt.set_focus(Some(w1_id));
+    assert!(
+        f.mt.state.windows[w1_id].flash.is_none(),
+        "focus should stop the flash",
+    );
+}
+
+#[test]
+fn zero_flashes_keeps_the_urgent_border_static() {
+    let mut f = Fixture::new();
+    f.mt.state.config.urgent.flashes = 0;
+    let c = f.add_client();
+
+    let w1 = open_window(&mut f, c);
+    let _w2 = open_window(&mut f, c);
+    let w1_id = all_window_ids(&f)[1];
+
+    let token = get_token(&mut f, c);
+    f.client(c).activate(&token, w1);
+    f.roundtrip(c);
+    let we = &f.mt.state.windows[w1_id];
+    assert!(we.urgent && we.flash.is_none());
+}
+
+#[test]
+fn urgent_window_without_a_monitor_flashes_without_panicking() {
+    let mut f = Fixture::new();
+    f.mt.state.config.urgent.flashes = 1;
+    f.mt.state.config.urgent.duration = 20;
+    let c = f.add_client();
+
+    open_window(&mut f, c);
+    let id = all_window_ids(&f)[0];
+    let output = f.mt.state.mon().unwrap().output.clone();
+    f.mt.state.remove_monitor(&output);
+    assert!(f.mt.state.monitors.is_empty());
+
+    f.mt.set_urgent(id);
+    assert!(f.mt.state.windows[id].urgent);
+    // each dispatch waits for the next flash tick, no sleeping
+    for _ in 0..10 {
+        if f.mt.state.windows[id].flash.is_none() {
+            break;
+        }
+        f.event_loop
+            .dispatch(Some(std::time::Duration::from_millis(50)), &mut f.mt)
+            .unwrap();
+    }
+    assert!(
+        f.mt.state.windows[id].flash.is_none(),
+        "the flash ran out while unplugged"
+    );
+}